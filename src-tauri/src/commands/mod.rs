@@ -1,3 +1,7 @@
 //! Tauri commands module
 
 pub mod voice;
+pub mod voice_devices;
+pub mod voice_diagnostics;
+pub mod voice_setup;
+pub mod voice_turns;
@@ -2,19 +2,58 @@
 
 use parking_lot::Mutex;
 use std::sync::Arc;
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Emitter, State};
 
-use crate::voice::{
-    get_models_dir, list_input_devices, list_output_devices, AudioDeviceInfo, VoiceController,
-    VoiceState,
-};
+use crate::voice::config_store::{load_config, voice_config_path};
+use crate::voice::device_prefs::{device_prefs_path, DevicePreferences};
+use crate::voice::events::VoiceEventSink;
+use crate::voice::watchdog::spawn_watchdog;
+use crate::voice::{get_models_dir, VoiceConfig, VoiceController, VoiceState};
+
+/// Forwards voice events to the webview
+struct TauriEventSink(AppHandle);
+
+impl VoiceEventSink for TauriEventSink {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        let _ = self.0.emit(event, payload);
+    }
+}
 
 /// Managed state for the voice controller
-pub struct VoiceControllerState(pub Arc<Mutex<Option<VoiceController>>>);
+///
+/// The second field holds the device selection, which outlives any one
+/// controller and is applied whenever a new one starts.
+pub struct VoiceControllerState(pub Arc<Mutex<Option<VoiceController>>>, pub Mutex<DevicePreferences>);
 
 impl VoiceControllerState {
     pub fn new() -> Self {
-        Self(Arc::new(Mutex::new(None)))
+        Self(Arc::new(Mutex::new(None)), Mutex::new(DevicePreferences::default()))
+    }
+
+    /// Create the state with the device selection saved by a previous run
+    pub fn load(app: &AppHandle) -> Self {
+        let prefs = device_prefs_path(app)
+            .map(|path| DevicePreferences::load(&path))
+            .unwrap_or_default();
+        Self(Arc::new(Mutex::new(None)), Mutex::new(prefs))
+    }
+
+    /// Config of the running controller, or the saved config when stopped
+    pub fn current_config(&self, app: &AppHandle) -> VoiceConfig {
+        if let Some(ref controller) = *self.0.lock() {
+            return controller.config();
+        }
+        voice_config_path(app).map(|path| load_config(&path)).unwrap_or_default()
+    }
+
+    /// Persist the device selection for the next run
+    pub(super) fn save_device_preferences(&self, app: &AppHandle) {
+        let prefs = self.1.lock().clone();
+        if let Some(path) = device_prefs_path(app) {
+            if let Err(e) = prefs.save(&path) {
+                log::warn!("Failed to save device preferences: {}", e);
+            }
+        }
     }
 }
 
@@ -30,7 +69,8 @@ pub async fn start_voice_listening(
     app: AppHandle,
     state: State<'_, VoiceControllerState>,
 ) -> Result<(), String> {
-    let models_dir = get_models_dir(&app);
+    let config = voice_config_path(&app).map(|path| load_config(&path)).unwrap_or_default();
+    let models_dir = get_models_dir(&app, &config.model_files);
 
     let mut guard = state.0.lock();
 
@@ -43,11 +83,14 @@ pub async fn start_voice_listening(
 
     // Create new controller
     let mut controller = VoiceController::new(models_dir);
-    controller.set_app_handle(app.clone());
+    controller.set_event_sink(Arc::new(TauriEventSink(app.clone())));
+    state.1.lock().apply(&controller);
+    controller.set_config(config).map_err(|e| e.to_string())?;
 
     // Start the voice system
     controller.start().map_err(|e| e.to_string())?;
 
+    spawn_watchdog(state.0.clone(), controller.watchdog_stop());
     *guard = Some(controller);
 
     log::info!("Voice listening started");
@@ -69,15 +112,36 @@ pub async fn stop_voice_listening(state: State<'_, VoiceControllerState>) -> Res
     Ok(())
 }
 
-/// Manually trigger listening (push-to-talk)
+/// Manually trigger listening
+///
+/// The capture normally ends when VAD hears the end of speech; with
+/// `push_to_talk` it lasts until `stop_manual_trigger`.
 #[tauri::command]
 pub async fn trigger_voice_listening(
+    push_to_talk: Option<bool>,
     state: State<'_, VoiceControllerState>,
 ) -> Result<(), String> {
     let guard = state.0.lock();
 
     if let Some(ref controller) = *guard {
-        controller.manual_trigger();
+        if push_to_talk.unwrap_or(false) {
+            controller.push_to_talk();
+        } else {
+            controller.manual_trigger();
+        }
+        Ok(())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// End a manual capture (push-to-talk release) and send it to STT
+#[tauri::command]
+pub async fn stop_manual_trigger(state: State<'_, VoiceControllerState>) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.manual_release();
         Ok(())
     } else {
         Err("Voice system not started".to_string())
@@ -113,166 +177,132 @@ pub async fn set_wake_word_sensitivity(
     }
 }
 
-/// Enable or disable wake word detection
+/// Set the base wake word threshold (0.0-1.0, before sensitivity scaling)
 #[tauri::command]
-pub async fn set_wake_word_enabled(
-    enabled: bool,
+pub async fn set_wake_word_threshold(
+    threshold: f32,
     state: State<'_, VoiceControllerState>,
 ) -> Result<(), String> {
     let guard = state.0.lock();
 
     if let Some(ref controller) = *guard {
-        controller.set_wake_word_enabled(enabled);
+        controller.set_wake_word_threshold(threshold);
         Ok(())
     } else {
         Err("Voice system not started".to_string())
     }
 }
 
-/// Check if wake word detection is available (always true for OpenWakeWord)
-#[tauri::command]
-pub fn check_wake_word_available() -> bool {
-    // OpenWakeWord doesn't require API keys
-    true
-}
-
-/// Get current voice state
-#[tauri::command]
-pub fn get_voice_state(state: State<'_, VoiceControllerState>) -> VoiceState {
-    let guard = state.0.lock();
-
-    if let Some(ref controller) = *guard {
-        controller.current_state()
-    } else {
-        VoiceState::Idle
-    }
-}
-
-/// Check if voice system is running
+/// Check if the current sensitivity and threshold allow wake word detection at all
 #[tauri::command]
-pub fn is_voice_running(state: State<'_, VoiceControllerState>) -> bool {
+pub fn is_wake_word_detection_possible(state: State<'_, VoiceControllerState>) -> bool {
     let guard = state.0.lock();
 
     if let Some(ref controller) = *guard {
-        controller.is_running()
+        controller.is_detection_possible()
     } else {
-        false
+        VoiceConfig::default().is_detection_possible()
     }
 }
 
-/// Notify that transcription is complete (called from frontend after STT)
+/// Enable or disable wake word detection
 #[tauri::command]
-pub async fn voice_transcription_complete(
-    text: String,
+pub async fn set_wake_word_enabled(
+    enabled: bool,
     state: State<'_, VoiceControllerState>,
 ) -> Result<(), String> {
     let guard = state.0.lock();
 
     if let Some(ref controller) = *guard {
-        controller.transcription_complete(text);
+        controller.set_wake_word_enabled(enabled);
         Ok(())
     } else {
         Err("Voice system not started".to_string())
     }
 }
 
-/// Notify that AI response is ready (called from frontend after processing)
+/// Set the external presence signal; wake word scanning pauses while false
 #[tauri::command]
-pub async fn voice_response_ready(
-    response: String,
+pub async fn set_presence(
+    present: bool,
     state: State<'_, VoiceControllerState>,
 ) -> Result<(), String> {
     let guard = state.0.lock();
 
     if let Some(ref controller) = *guard {
-        controller.response_ready(response);
+        controller.set_presence(present);
         Ok(())
     } else {
         Err("Voice system not started".to_string())
     }
 }
 
-/// Notify that TTS is complete (called from frontend after speaking)
+/// Get the number of mel frames between wake word inference runs
 #[tauri::command]
-pub async fn voice_speech_complete(
-    state: State<'_, VoiceControllerState>,
-) -> Result<(), String> {
+pub fn get_inference_hop(state: State<'_, VoiceControllerState>) -> Result<usize, String> {
     let guard = state.0.lock();
 
     if let Some(ref controller) = *guard {
-        controller.speech_complete();
-        Ok(())
+        Ok(controller.inference_hop())
     } else {
         Err("Voice system not started".to_string())
     }
 }
 
-/// List available input (microphone) devices
-#[tauri::command]
-pub fn get_input_devices() -> Vec<AudioDeviceInfo> {
-    list_input_devices()
-}
-
-/// List available output (speaker) devices
+/// Set the number of mel frames between wake word inference runs (must be >= 1)
 #[tauri::command]
-pub fn get_output_devices() -> Vec<AudioDeviceInfo> {
-    list_output_devices()
-}
-
-/// Set the input device to use (requires restart of voice system)
-#[tauri::command]
-pub async fn set_input_device(
-    device_name: Option<String>,
+pub async fn set_inference_hop(
+    hop: usize,
     state: State<'_, VoiceControllerState>,
 ) -> Result<(), String> {
     let guard = state.0.lock();
 
     if let Some(ref controller) = *guard {
-        controller.set_input_device(device_name);
-        Ok(())
+        controller.set_inference_hop(hop).map_err(|e| e.to_string())
     } else {
-        // Store preference for when controller starts
-        // For now, just return Ok - preference will be applied on next start
-        Ok(())
+        Err("Voice system not started".to_string())
     }
 }
 
-/// Set the output device to use
+/// Check if wake word detection is available (always true for OpenWakeWord)
 #[tauri::command]
-pub async fn set_output_device(
-    device_name: Option<String>,
-    state: State<'_, VoiceControllerState>,
-) -> Result<(), String> {
+pub fn check_wake_word_available() -> bool {
+    // OpenWakeWord doesn't require API keys
+    true
+}
+
+/// Get current voice state
+#[tauri::command]
+pub fn get_voice_state(state: State<'_, VoiceControllerState>) -> VoiceState {
     let guard = state.0.lock();
 
     if let Some(ref controller) = *guard {
-        controller.set_output_device(device_name);
-        Ok(())
+        controller.current_state()
     } else {
-        Ok(())
+        VoiceState::Idle
     }
 }
 
-/// Get current input device
+/// Check if voice system is running
 #[tauri::command]
-pub fn get_current_input_device(state: State<'_, VoiceControllerState>) -> Option<String> {
+pub fn is_voice_running(state: State<'_, VoiceControllerState>) -> bool {
     let guard = state.0.lock();
 
     if let Some(ref controller) = *guard {
-        controller.get_input_device()
+        controller.is_running()
     } else {
-        None
+        false
     }
 }
 
-/// Get current output device
+/// Milliseconds spent in the current state, for timeout progress bars
 #[tauri::command]
-pub fn get_current_output_device(state: State<'_, VoiceControllerState>) -> Option<String> {
+pub fn get_time_in_state(state: State<'_, VoiceControllerState>) -> Result<u64, String> {
     let guard = state.0.lock();
 
     if let Some(ref controller) = *guard {
-        controller.get_output_device()
+        Ok(controller.time_in_state().as_millis() as u64)
     } else {
-        None
+        Err("Voice system not started".to_string())
     }
 }
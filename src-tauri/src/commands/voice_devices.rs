@@ -0,0 +1,90 @@
+//! Audio device Tauri commands
+
+use tauri::{AppHandle, State};
+
+use super::voice::VoiceControllerState;
+use crate::voice::device_prefs::AudioConfigSnapshot;
+use crate::voice::{list_input_devices, list_output_devices, AudioDeviceInfo};
+
+/// List available input (microphone) devices
+#[tauri::command]
+pub fn get_input_devices() -> Vec<AudioDeviceInfo> {
+    list_input_devices()
+}
+
+/// List available output (speaker) devices
+#[tauri::command]
+pub fn get_output_devices() -> Vec<AudioDeviceInfo> {
+    list_output_devices()
+}
+
+/// Devices and current selection in one consistent snapshot, for the settings panel
+#[tauri::command]
+pub fn get_audio_config(state: State<'_, VoiceControllerState>) -> AudioConfigSnapshot {
+    let guard = state.0.lock();
+    let selection = match *guard {
+        Some(ref controller) => controller.device_selection(),
+        None => state.1.lock().clone(),
+    };
+    AudioConfigSnapshot::new(list_input_devices(), list_output_devices(), selection)
+}
+
+/// Set the input device to use (requires restart of voice system)
+///
+/// The choice is saved and applied whenever the voice system starts.
+#[tauri::command]
+pub async fn set_input_device(
+    app: AppHandle,
+    device_name: Option<String>,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    state.1.lock().input_device = device_name.clone();
+    state.save_device_preferences(&app);
+
+    if let Some(ref controller) = *state.0.lock() {
+        controller.set_input_device(device_name);
+    }
+    Ok(())
+}
+
+/// Set the output device to use
+///
+/// The choice is saved and applied whenever the voice system starts.
+#[tauri::command]
+pub async fn set_output_device(
+    app: AppHandle,
+    device_name: Option<String>,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    state.1.lock().output_device = device_name.clone();
+    state.save_device_preferences(&app);
+
+    if let Some(ref controller) = *state.0.lock() {
+        controller.set_output_device(device_name);
+    }
+    Ok(())
+}
+
+/// Get current input device
+#[tauri::command]
+pub fn get_current_input_device(state: State<'_, VoiceControllerState>) -> Option<String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.get_input_device()
+    } else {
+        state.1.lock().input_device.clone()
+    }
+}
+
+/// Get current output device
+#[tauri::command]
+pub fn get_current_output_device(state: State<'_, VoiceControllerState>) -> Option<String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.get_output_device()
+    } else {
+        state.1.lock().output_device.clone()
+    }
+}
@@ -0,0 +1,128 @@
+//! Voice diagnostics Tauri commands
+
+use tauri::{AppHandle, State};
+
+use super::voice::VoiceControllerState;
+use crate::voice::cpu_usage::CpuUsage;
+use crate::voice::event_history::EventRecord;
+use crate::voice::memory::MemoryReport;
+use crate::voice::priority::AudioPriorityStatus;
+use crate::voice::simulation::{self, SimulationReport};
+use crate::voice::get_models_dir;
+
+/// Estimate the voice subsystem's CPU usage over the last few seconds
+#[tauri::command]
+pub fn get_cpu_usage(state: State<'_, VoiceControllerState>) -> Result<CpuUsage, String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        Ok(controller.cpu_usage())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Report whether the audio threads run at elevated scheduling priority
+#[tauri::command]
+pub fn get_audio_priority_status(state: State<'_, VoiceControllerState>) -> Result<AudioPriorityStatus, String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        Ok(controller.audio_priority())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Report the memory retained by voice buffers
+#[tauri::command]
+pub fn get_memory_report(state: State<'_, VoiceControllerState>) -> Result<MemoryReport, String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        Ok(controller.memory_report())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Count the audio chunks dropped this session because processing fell behind
+#[tauri::command]
+pub fn get_dropped_audio_chunks(state: State<'_, VoiceControllerState>) -> Result<u64, String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        Ok(controller.dropped_audio_chunks())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Recent voice events with their timestamps, oldest first
+#[tauri::command]
+pub fn get_event_history(state: State<'_, VoiceControllerState>) -> Result<Vec<EventRecord>, String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        Ok(controller.event_history())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Free retained audio buffers that aren't needed right now
+#[tauri::command]
+pub async fn release_retained_buffers(state: State<'_, VoiceControllerState>) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.release_retained_buffers();
+        Ok(())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Start recording raw audio and emitted events to a file for later replay
+#[tauri::command]
+pub async fn start_session_recording(
+    path: String,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller
+            .start_session_recording(std::path::Path::new(&path))
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Stop the active session recording
+#[tauri::command]
+pub async fn stop_session_recording(state: State<'_, VoiceControllerState>) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.stop_session_recording().map_err(|e| e.to_string())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Replay a WAV file through the wake word detector and VAD and report what fired
+///
+/// Runs offline on fresh models with the current config (or the default one),
+/// leaving any running voice session untouched.
+#[tauri::command]
+pub async fn simulate_detection(
+    path: String,
+    app: AppHandle,
+    state: State<'_, VoiceControllerState>,
+) -> Result<SimulationReport, String> {
+    let config = state.current_config(&app);
+    let models_dir = get_models_dir(&app, &config.model_files);
+    simulation::simulate_detection(&models_dir, std::path::Path::new(&path), &config).map_err(|e| e.to_string())
+}
@@ -0,0 +1,267 @@
+//! Voice setup Tauri commands - profiles and wake phrases
+
+use tauri::{AppHandle, State};
+
+use super::voice::VoiceControllerState;
+use crate::voice::audio_source::CpalAudioSource;
+use crate::voice::config_schema::ConfigFieldMeta;
+use crate::voice::config_validation::AppliedConfig;
+use crate::voice::config_store::{load_config, save_config, voice_config_path};
+use crate::voice::mic_test::{test_microphone, MicTestReport};
+use crate::voice::model_manifest;
+use crate::voice::playback::play_wav;
+use crate::voice::wake_phrases::{self, WakePhraseInfo};
+use crate::voice::{get_models_dir, profiles, VoiceConfig};
+
+/// List available user profiles
+#[tauri::command]
+pub fn list_profiles(app: AppHandle, state: State<'_, VoiceControllerState>) -> Vec<String> {
+    let models_dir = get_models_dir(&app, &state.current_config(&app).model_files);
+    profiles::list_profiles(&models_dir)
+}
+
+/// List wake word models available in the models directory's manifest
+#[tauri::command]
+pub fn list_wake_word_models(app: AppHandle, state: State<'_, VoiceControllerState>) -> Vec<String> {
+    let models_dir = get_models_dir(&app, &state.current_config(&app).model_files);
+    model_manifest::list_wake_word_models(&models_dir)
+}
+
+/// Switch to a wake word model from the manifest, saving the choice
+#[tauri::command]
+pub async fn set_wake_word_model(
+    name: String,
+    app: AppHandle,
+    state: State<'_, VoiceControllerState>,
+) -> Result<AppliedConfig, String> {
+    let path = voice_config_path(&app);
+    let guard = state.0.lock();
+
+    let applied = if let Some(ref controller) = *guard {
+        controller.set_wake_word_model(&name).map_err(|e| e.to_string())?
+    } else {
+        let saved = path.as_deref().map(load_config).unwrap_or_default();
+        let models_dir = get_models_dir(&app, &saved.model_files);
+        let config = model_manifest::config_for_model(&models_dir, &name, saved).map_err(|e| e.to_string())?;
+        AppliedConfig { config, restart_required: Vec::new() }
+    };
+    drop(guard);
+
+    if let Some(path) = path {
+        if let Err(e) = save_config(&applied.config, &path) {
+            log::warn!("Failed to save voice config: {}", e);
+        }
+    }
+    Ok(applied)
+}
+
+/// Switch to a user profile (or the default config with `None`)
+#[tauri::command]
+pub async fn set_active_profile(
+    name: Option<String>,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.set_active_profile(name.as_deref()).map_err(|e| e.to_string())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Get the active user profile
+#[tauri::command]
+pub fn get_active_profile(state: State<'_, VoiceControllerState>) -> Option<String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.active_profile()
+    } else {
+        None
+    }
+}
+
+/// List the wake phrases installed in the models directory
+#[tauri::command]
+pub fn list_available_wake_phrases(
+    app: AppHandle,
+    state: State<'_, VoiceControllerState>,
+) -> Vec<WakePhraseInfo> {
+    let models_dir = get_models_dir(&app, &state.current_config(&app).model_files);
+    wake_phrases::list_wake_phrases(&models_dir)
+}
+
+/// Play the bundled sample clip of a wake phrase on the selected output device
+#[tauri::command]
+pub async fn preview_wake_phrase(
+    name: String,
+    app: AppHandle,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let models_dir = get_models_dir(&app, &state.current_config(&app).model_files);
+    let path = wake_phrases::sample_path(&models_dir, &name)
+        .ok_or_else(|| format!("Invalid wake phrase name: {}", name))?;
+    if !path.exists() {
+        return Err(format!("No sample clip for wake phrase: {}", name));
+    }
+
+    let output_device = state.0.lock().as_ref().and_then(|c| c.get_output_device());
+    play_wav(&path, output_device.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Enable or disable plain-language status announcements for assistive technology
+#[tauri::command]
+pub async fn set_accessibility_events(
+    enabled: bool,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.set_accessibility_events(enabled);
+        Ok(())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Record a few seconds of ambient noise for noise suppression
+#[tauri::command]
+pub async fn record_noise_profile(
+    duration_ms: u64,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.record_noise_profile(duration_ms).map_err(|e| e.to_string())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Measure ambient noise (default 2 seconds) and set the silence threshold from it
+#[tauri::command]
+pub async fn calibrate_noise_floor(
+    duration_ms: Option<u64>,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller
+            .calibrate_noise_floor(duration_ms.unwrap_or(2000))
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Reload the wake word models without restarting audio capture
+#[tauri::command]
+pub async fn reload_models(state: State<'_, VoiceControllerState>) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.reload_models().map_err(|e| e.to_string())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Start collecting wake word score statistics afresh, e.g. before a tuning session
+#[tauri::command]
+pub async fn reset_wake_word_stats(state: State<'_, VoiceControllerState>) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.reset_wake_word_stats().map_err(|e| e.to_string())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Enable or disable subtraction of the recorded noise profile
+#[tauri::command]
+pub async fn set_noise_suppression(
+    enabled: bool,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.set_noise_suppression(enabled);
+        Ok(())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Capture a few seconds from an input device and report its levels
+///
+/// Uses the saved input device when `device_name` is not given. Refused while
+/// the voice system is running, since both would open the same device.
+#[tauri::command]
+pub async fn test_input_device(
+    device_name: Option<String>,
+    seconds: f32,
+    app: AppHandle,
+    state: State<'_, VoiceControllerState>,
+) -> Result<MicTestReport, String> {
+    let guard = state.0.lock();
+    let config = match *guard {
+        Some(ref controller) if controller.is_running() => {
+            return Err("Stop the voice system before testing the microphone".to_string());
+        }
+        Some(ref controller) => controller.config(),
+        None => voice_config_path(&app).map(|path| load_config(&path)).unwrap_or_default(),
+    };
+    drop(guard);
+
+    let device = device_name.or_else(|| state.1.lock().input_device.clone());
+    test_microphone(&CpalAudioSource, &config, device, seconds).map_err(|e| e.to_string())
+}
+
+/// Describe every voice config field for building the settings UI
+#[tauri::command]
+pub fn get_voice_config_schema() -> Vec<ConfigFieldMeta> {
+    VoiceConfig::schema()
+}
+
+/// Get the full voice config: the running one, or the saved one when stopped
+#[tauri::command]
+pub fn get_voice_config(app: AppHandle, state: State<'_, VoiceControllerState>) -> VoiceConfig {
+    state.current_config(&app)
+}
+
+/// Update some voice config fields, apply them and save them for the next start
+///
+/// `partial` maps field names to new values. Nothing is applied unless every
+/// field is valid; the error then lists each field that isn't. While running,
+/// `restart_required` lists the saved fields that wait for the next start.
+#[tauri::command]
+pub async fn update_voice_config(
+    partial: serde_json::Value,
+    app: AppHandle,
+    state: State<'_, VoiceControllerState>,
+) -> Result<AppliedConfig, String> {
+    let path = voice_config_path(&app);
+    let guard = state.0.lock();
+
+    let applied = if let Some(ref controller) = *guard {
+        controller.update_config(&partial).map_err(|e| e.to_string())?
+    } else {
+        let saved = path.as_deref().map(load_config).unwrap_or_default();
+        let config = saved.merged(&partial).map_err(|e| e.to_string())?;
+        AppliedConfig { config, restart_required: Vec::new() }
+    };
+    drop(guard);
+
+    if let Some(path) = path {
+        if let Err(e) = save_config(&applied.config, &path) {
+            log::warn!("Failed to save voice config: {}", e);
+        }
+    }
+    Ok(applied)
+}
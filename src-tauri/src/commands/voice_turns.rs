@@ -0,0 +1,113 @@
+//! Tauri commands the frontend uses to report the progress of a turn
+
+use tauri::State;
+
+use super::voice::VoiceControllerState;
+
+/// Notify that transcription is complete (called from frontend after STT)
+#[tauri::command]
+pub async fn voice_transcription_complete(
+    text: String,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.transcription_complete(text);
+        Ok(())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Notify that STT heard nothing intelligible, returning to idle
+#[tauri::command]
+pub async fn voice_no_speech_detected(state: State<'_, VoiceControllerState>) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.no_speech_detected();
+        Ok(())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Show interim transcription text (called from frontend as partial STT results arrive)
+#[tauri::command]
+pub async fn voice_partial_transcription(
+    text: String,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.partial_transcription(text);
+        Ok(())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Notify that AI response is ready (called from frontend after processing)
+#[tauri::command]
+pub async fn voice_response_ready(
+    response: String,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.response_ready(response);
+        Ok(())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Report an STT or AI failure (called from frontend), holding the Error state
+#[tauri::command]
+pub async fn voice_report_error(
+    message: String,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.report_error(message);
+        Ok(())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Notify that TTS is complete (called from frontend after speaking)
+#[tauri::command]
+pub async fn voice_speech_complete(
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.speech_complete();
+        Ok(())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
+
+/// Play synthesized TTS audio from Rust; speech completes when it finishes
+#[tauri::command]
+pub async fn voice_play_tts_audio(
+    samples: Vec<f32>,
+    sample_rate: u32,
+    state: State<'_, VoiceControllerState>,
+) -> Result<(), String> {
+    let guard = state.0.lock();
+
+    if let Some(ref controller) = *guard {
+        controller.play_tts_audio(samples, sample_rate).map_err(|e| e.to_string())
+    } else {
+        Err("Voice system not started".to_string())
+    }
+}
@@ -3,10 +3,9 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod commands;
-mod voice;
-
+use jarvis::commands;
 use commands::voice::VoiceControllerState;
+use tauri::Manager;
 
 fn main() {
     // Initialize logging
@@ -14,28 +13,69 @@ fn main() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
-        .manage(VoiceControllerState::new())
+        .setup(|app| {
+            app.manage(VoiceControllerState::load(app.handle()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Voice commands
             commands::voice::start_voice_listening,
             commands::voice::stop_voice_listening,
             commands::voice::trigger_voice_listening,
+            commands::voice::stop_manual_trigger,
             commands::voice::cancel_voice_operation,
             commands::voice::set_wake_word_sensitivity,
+            commands::voice::set_wake_word_threshold,
+            commands::voice::is_wake_word_detection_possible,
             commands::voice::set_wake_word_enabled,
+            commands::voice::set_presence,
+            commands::voice::get_inference_hop,
+            commands::voice::set_inference_hop,
             commands::voice::check_wake_word_available,
             commands::voice::get_voice_state,
             commands::voice::is_voice_running,
-            commands::voice::voice_transcription_complete,
-            commands::voice::voice_response_ready,
-            commands::voice::voice_speech_complete,
+            commands::voice::get_time_in_state,
+            commands::voice_turns::voice_partial_transcription,
+            commands::voice_turns::voice_transcription_complete,
+            commands::voice_turns::voice_no_speech_detected,
+            commands::voice_turns::voice_response_ready,
+            commands::voice_turns::voice_speech_complete,
+            commands::voice_turns::voice_report_error,
+            commands::voice_turns::voice_play_tts_audio,
+            commands::voice_setup::list_profiles,
+            commands::voice_setup::list_wake_word_models,
+            commands::voice_setup::set_wake_word_model,
+            commands::voice_setup::set_active_profile,
+            commands::voice_setup::get_active_profile,
+            commands::voice_setup::list_available_wake_phrases,
+            commands::voice_setup::preview_wake_phrase,
+            commands::voice_setup::get_voice_config_schema,
+            commands::voice_setup::get_voice_config,
+            commands::voice_setup::update_voice_config,
+            commands::voice_setup::set_accessibility_events,
+            commands::voice_setup::record_noise_profile,
+            commands::voice_setup::calibrate_noise_floor,
+            commands::voice_setup::reload_models,
+            commands::voice_setup::reset_wake_word_stats,
+            commands::voice_setup::set_noise_suppression,
+            commands::voice_setup::test_input_device,
+            commands::voice_diagnostics::get_cpu_usage,
+            commands::voice_diagnostics::get_memory_report,
+            commands::voice_diagnostics::get_dropped_audio_chunks,
+            commands::voice_diagnostics::get_event_history,
+            commands::voice_diagnostics::get_audio_priority_status,
+            commands::voice_diagnostics::release_retained_buffers,
+            commands::voice_diagnostics::start_session_recording,
+            commands::voice_diagnostics::stop_session_recording,
+            commands::voice_diagnostics::simulate_detection,
             // Audio device commands
-            commands::voice::get_input_devices,
-            commands::voice::get_output_devices,
-            commands::voice::set_input_device,
-            commands::voice::set_output_device,
-            commands::voice::get_current_input_device,
-            commands::voice::get_current_output_device,
+            commands::voice_devices::get_input_devices,
+            commands::voice_devices::get_output_devices,
+            commands::voice_devices::set_input_device,
+            commands::voice_devices::set_output_device,
+            commands::voice_devices::get_current_input_device,
+            commands::voice_devices::get_current_output_device,
+            commands::voice_devices::get_audio_config,
         ])
         .run(tauri::generate_context!())
         .expect("error while running Jarvis");
@@ -0,0 +1,99 @@
+//! Low-rate listening status for assistive technology
+//!
+//! `voice-accessibility-status` is emitted only on meaningful changes, with a
+//! plain-language message a screen reader can announce as-is. It is kept
+//! separate from the high-frequency telemetry events so AT tools aren't flooded.
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::sync::Arc;
+
+use super::controller_state::VoiceControllerState;
+use super::events::{emit_event, EventSink};
+use super::state_machine::VoiceState;
+
+/// Meaningful listening status changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AccessibilityStatus {
+    /// Voice system started and waiting for the wake word
+    Started,
+    /// Capturing the user's speech
+    Listening,
+    /// The user's speech was captured
+    Heard,
+    /// Listening ended without capturing speech (cancel, timeout)
+    StoppedListening,
+    /// Voice system stopped
+    Stopped,
+    /// Something went wrong
+    Error,
+}
+
+impl AccessibilityStatus {
+    /// Plain-language description suitable for announcing
+    pub fn message(self) -> &'static str {
+        match self {
+            AccessibilityStatus::Started => "Voice control is on",
+            AccessibilityStatus::Listening => "Now listening",
+            AccessibilityStatus::Heard => "Heard you",
+            AccessibilityStatus::StoppedListening => "Stopped listening",
+            AccessibilityStatus::Stopped => "Voice control is off",
+            AccessibilityStatus::Error => "Voice control had a problem",
+        }
+    }
+
+    /// Status to announce for a state change, if it is meaningful
+    pub fn for_transition(previous: Option<VoiceState>, new_state: VoiceState) -> Option<Self> {
+        match (previous, new_state) {
+            (_, VoiceState::Listening) => Some(AccessibilityStatus::Listening),
+            (Some(VoiceState::Listening), VoiceState::Transcribing) => Some(AccessibilityStatus::Heard),
+            (Some(VoiceState::Listening), VoiceState::Idle) => Some(AccessibilityStatus::StoppedListening),
+            _ => None,
+        }
+    }
+}
+
+/// Emit `voice-accessibility-status` when enabled in the config
+///
+/// Must not be called while holding a lock on `state`.
+pub fn emit_accessibility_status(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    status: AccessibilityStatus,
+) {
+    if !state.read().config.accessibility_events {
+        return;
+    }
+    emit_event(
+        sink,
+        state,
+        "voice-accessibility-status",
+        serde_json::json!({ "status": status, "message": status.message() }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_meaningful_transitions_are_announced() {
+        use VoiceState::*;
+
+        assert_eq!(
+            AccessibilityStatus::for_transition(Some(Idle), Listening),
+            Some(AccessibilityStatus::Listening)
+        );
+        assert_eq!(
+            AccessibilityStatus::for_transition(Some(Listening), Transcribing),
+            Some(AccessibilityStatus::Heard)
+        );
+        assert_eq!(
+            AccessibilityStatus::for_transition(Some(Listening), Idle),
+            Some(AccessibilityStatus::StoppedListening)
+        );
+        assert_eq!(AccessibilityStatus::for_transition(Some(Transcribing), Processing), None);
+        assert_eq!(AccessibilityStatus::for_transition(Some(Speaking), Idle), None);
+    }
+}
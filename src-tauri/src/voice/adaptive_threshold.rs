@@ -0,0 +1,107 @@
+//! Raising the wake word threshold when background scores drift upward
+//!
+//! In a noisy room the classifier scores of ordinary audio creep up, and a
+//! threshold tuned in a quiet room starts firing on them. This tracks a slow
+//! moving average of the scores that stay below the threshold (the noise
+//! floor of the scores, not of the audio) and keeps the threshold at least
+//! `adaptive_threshold_margin` above it. The adapted threshold never drops
+//! below the configured one and never rises past `adaptive_threshold_ceiling`.
+
+use super::config::VoiceConfig;
+
+/// Weight of the newest score in the noise floor average
+///
+/// At one inference per 80ms chunk this follows a change in the room over
+/// several seconds, while a single loud word barely moves it.
+const FLOOR_SMOOTHING: f32 = 0.02;
+
+/// Tracks the background score level and raises thresholds above it
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveThreshold {
+    enabled: bool,
+    margin: f32,
+    ceiling: f32,
+    floor: f32,
+}
+
+impl AdaptiveThreshold {
+    pub fn from_config(config: &VoiceConfig) -> Self {
+        Self {
+            enabled: config.adaptive_threshold,
+            margin: config.adaptive_threshold_margin,
+            ceiling: config.adaptive_threshold_ceiling,
+            floor: 0.0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Fold in the score of one inference, judged against its unadapted `threshold`
+    ///
+    /// Scores at or above the adapted threshold are candidate wake words, not
+    /// background, and are left out.
+    pub fn observe(&mut self, score: f32, threshold: f32) {
+        if !self.enabled || score >= self.apply(threshold) {
+            return;
+        }
+        self.floor += FLOOR_SMOOTHING * (score - self.floor);
+    }
+
+    /// `threshold` raised to stay `margin` above the score floor, up to the ceiling
+    pub fn apply(&self, threshold: f32) -> f32 {
+        if !self.enabled {
+            return threshold;
+        }
+        threshold.max((self.floor + self.margin).min(self.ceiling))
+    }
+
+    /// Current background score level
+    pub fn floor(&self) -> f32 {
+        self.floor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adaptive(enabled: bool) -> AdaptiveThreshold {
+        AdaptiveThreshold::from_config(&VoiceConfig {
+            adaptive_threshold: enabled,
+            adaptive_threshold_margin: 0.3,
+            adaptive_threshold_ceiling: 0.7,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_sustained_scores_raise_threshold_up_to_ceiling() {
+        let mut threshold = adaptive(true);
+        assert_eq!(threshold.apply(0.5), 0.5);
+
+        let mut previous = 0.5;
+        for _ in 0..500 {
+            threshold.observe(0.45, 0.5);
+            let adapted = threshold.apply(0.5);
+            assert!((previous..=0.7).contains(&adapted), "{}", adapted);
+            previous = adapted;
+        }
+        // The floor settles near 0.45, so 0.3 above it would be past the ceiling
+        assert_eq!(previous, 0.7);
+
+        // A configured threshold above the ceiling is never lowered
+        assert_eq!(threshold.apply(0.8), 0.8);
+    }
+
+    #[test]
+    fn test_disabled_leaves_threshold_alone() {
+        let mut threshold = adaptive(false);
+        for _ in 0..500 {
+            threshold.observe(0.45, 0.5);
+        }
+        assert_eq!(threshold.floor(), 0.0);
+        assert_eq!(threshold.apply(0.5), 0.5);
+    }
+}
@@ -0,0 +1,122 @@
+//! Automatic gain control ahead of wake word detection and VAD
+//!
+//! Gain moves toward whatever brings a chunk to the target RMS, dropping
+//! quickly on loud input and rising slowly on quiet input so speech pauses
+//! don't cause audible pumping. Adaptation freezes below the noise gate so
+//! silence is never amplified into noise.
+
+use super::config::VoiceConfig;
+use super::dsp::calculate_rms;
+use super::filters::AudioFilter;
+
+/// Lowest gain, for over-driven inputs
+const MIN_GAIN: f32 = 0.1;
+
+/// Fraction of the remaining gain change applied per chunk when reducing gain
+const ATTACK: f32 = 0.3;
+
+/// Fraction of the remaining gain change applied per chunk when raising gain
+const RELEASE: f32 = 0.05;
+
+/// Adaptive gain stage
+#[derive(Debug, Clone)]
+pub struct AutomaticGain {
+    target_rms: f32,
+    max_gain: f32,
+    noise_gate: f32,
+    gain: f32,
+}
+
+impl AutomaticGain {
+    pub fn new(target_rms: f32, max_gain: f32, noise_gate: f32) -> Self {
+        Self {
+            target_rms,
+            max_gain: max_gain.max(MIN_GAIN),
+            noise_gate,
+            gain: 1.0,
+        }
+    }
+
+    /// Build the stage when AGC is enabled in the config
+    pub fn from_config(config: &VoiceConfig) -> Option<Self> {
+        config
+            .agc_enabled
+            .then(|| Self::new(config.agc_target_rms, config.agc_max_gain, config.agc_noise_gate))
+    }
+
+    /// Current linear gain
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+}
+
+impl AudioFilter for AutomaticGain {
+    fn process(&mut self, samples: &mut [f32]) {
+        let rms = calculate_rms(samples);
+        if rms >= self.noise_gate && rms > 0.0 {
+            let desired = (self.target_rms / rms).clamp(MIN_GAIN, self.max_gain);
+            let rate = if desired < self.gain { ATTACK } else { RELEASE };
+            self.gain += rate * (desired - self.gain);
+        }
+        for sample in samples.iter_mut() {
+            *sample = (*sample * self.gain).clamp(-1.0, 1.0);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(amplitude: f32) -> Vec<f32> {
+        (0..1280).map(|i| (i as f32 * 0.05).sin() * amplitude).collect()
+    }
+
+    /// Run `chunks` copies of `input` through the AGC, returning the last output's RMS
+    fn settle(agc: &mut AutomaticGain, input: &[f32], chunks: usize) -> f32 {
+        let mut output = Vec::new();
+        for _ in 0..chunks {
+            output = input.to_vec();
+            agc.process(&mut output);
+        }
+        calculate_rms(&output)
+    }
+
+    #[test]
+    fn test_quiet_input_rises_toward_target() {
+        let mut agc = AutomaticGain::new(0.05, 10.0, 0.001);
+        let input = sine(0.02);
+
+        let early = settle(&mut agc, &input, 5);
+        let settled = settle(&mut agc, &input, 150);
+
+        assert!(early < settled, "gain rises gradually");
+        assert!((settled - 0.05).abs() < 0.005, "rms {}", settled);
+    }
+
+    #[test]
+    fn test_loud_input_drops_quickly() {
+        let mut agc = AutomaticGain::new(0.05, 10.0, 0.001);
+        let settled = settle(&mut agc, &sine(0.5), 20);
+        assert!((settled - 0.05).abs() < 0.005, "rms {}", settled);
+    }
+
+    #[test]
+    fn test_gain_is_capped() {
+        let mut agc = AutomaticGain::new(0.05, 4.0, 0.0001);
+        settle(&mut agc, &sine(0.001), 300);
+        assert!(agc.gain() <= 4.0);
+    }
+
+    #[test]
+    fn test_silence_freezes_adaptation() {
+        let mut agc = AutomaticGain::new(0.05, 10.0, 0.001);
+        settle(&mut agc, &vec![0.0; 1280], 100);
+        settle(&mut agc, &sine(0.0005), 100);
+        assert_eq!(agc.gain(), 1.0);
+    }
+}
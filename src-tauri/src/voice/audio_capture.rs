@@ -3,14 +3,23 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, FromSample, SampleFormat, Stream, StreamConfig};
 use parking_lot::Mutex;
-use rubato::{FftFixedIn, Resampler};
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use thiserror::Error;
-use tokio::sync::mpsc;
 
+use super::chunk::{capture_time_ms, AudioChunk, AudioSender, ChunkClock, Rechunker};
 use super::config::VoiceConfig;
+use super::downmix::{downmix_frame, validate_channel_mode, ChannelMode};
+use super::priority::elevate_current_thread;
+use super::resample::ChunkResampler;
+
+mod devices;
+mod pipeline;
+
+pub use devices::{list_input_devices, list_output_devices, AudioDeviceInfo};
+use devices::find_input_device_by_name;
+use pipeline::{CaptureBuffer, CapturePipeline};
 
 #[derive(Error, Debug)]
 pub enum AudioCaptureError {
@@ -24,73 +33,40 @@ pub enum AudioCaptureError {
     StreamError(String),
     #[error("Resampler error: {0}")]
     ResamplerError(String),
+    #[error("Failed to read audio file: {0}")]
+    FileError(String),
 }
 
-/// Information about an audio device
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AudioDeviceInfo {
-    /// Device name/identifier
-    pub name: String,
-    /// Whether this is the default device
-    pub is_default: bool,
-}
-
-/// List all available input (microphone) devices
-pub fn list_input_devices() -> Vec<AudioDeviceInfo> {
-    let host = cpal::default_host();
-    let default_device_name = host
-        .default_input_device()
-        .and_then(|d| d.name().ok());
-
-    host.input_devices()
-        .map(|devices| {
-            devices
-                .filter_map(|device| {
-                    let name = device.name().ok()?;
-                    let is_default = default_device_name.as_ref() == Some(&name);
-                    Some(AudioDeviceInfo { name, is_default })
-                })
-                .collect()
-        })
-        .unwrap_or_default()
-}
-
-/// List all available output (speaker) devices
-pub fn list_output_devices() -> Vec<AudioDeviceInfo> {
-    let host = cpal::default_host();
-    let default_device_name = host
-        .default_output_device()
-        .and_then(|d| d.name().ok());
-
-    host.output_devices()
-        .map(|devices| {
-            devices
-                .filter_map(|device| {
-                    let name = device.name().ok()?;
-                    let is_default = default_device_name.as_ref() == Some(&name);
-                    Some(AudioDeviceInfo { name, is_default })
-                })
-                .collect()
-        })
-        .unwrap_or_default()
+/// Negotiated capture stream format
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CaptureFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: String,
 }
 
-/// Find an input device by name
-fn find_input_device_by_name(name: &str) -> Option<Device> {
-    let host = cpal::default_host();
-    host.input_devices().ok()?.find(|d| {
-        d.name().map(|n| n == name).unwrap_or(false)
-    })
-}
+/// Block size when no resampler dictates one
+const PASSTHROUGH_CHUNK_SIZE: usize = 1024;
 
 /// Audio capture manager
 pub struct AudioCapture {
     device: Device,
     config: StreamConfig,
+    sample_format: SampleFormat,
+    channel_mode: ChannelMode,
     sample_rate: u32,
     target_sample_rate: u32,
+    /// Samples per chunk delivered to the processing loop, at the target rate
+    chunk_size: usize,
     is_capturing: Arc<AtomicBool>,
+    /// Set from cpal's error callback when the stream dies (e.g. device unplugged)
+    stream_failed: Arc<AtomicBool>,
+    /// Raise cpal's callback thread to high priority on its first callback
+    high_priority: bool,
+    /// Whether the callback thread obtained high priority
+    callback_elevated: Arc<AtomicBool>,
     stream: Option<Stream>,
+    pipeline: Option<CapturePipeline>,
 }
 
 impl AudioCapture {
@@ -115,6 +91,7 @@ impl AudioCapture {
             .default_input_config()
             .map_err(|e| AudioCaptureError::ConfigError(e.to_string()))?;
 
+        let sample_format = supported_config.sample_format();
         let sample_rate = supported_config.sample_rate().0;
         let channels = supported_config.channels();
 
@@ -136,15 +113,22 @@ impl AudioCapture {
         Ok(Self {
             device,
             config,
+            sample_format,
+            channel_mode: voice_config.channel_mode,
             sample_rate,
             target_sample_rate: voice_config.sample_rate,
+            chunk_size: voice_config.chunk_size,
             is_capturing: Arc::new(AtomicBool::new(false)),
+            stream_failed: Arc::new(AtomicBool::new(false)),
+            high_priority: voice_config.high_priority_audio,
+            callback_elevated: Arc::new(AtomicBool::new(false)),
             stream: None,
+            pipeline: None,
         })
     }
 
     /// Start capturing audio and send samples to the channel
-    pub fn start(&mut self, tx: mpsc::UnboundedSender<Vec<f32>>) -> Result<(), AudioCaptureError> {
+    pub fn start(&mut self, tx: AudioSender) -> Result<(), AudioCaptureError> {
         if self.is_capturing.load(Ordering::SeqCst) {
             return Ok(()); // Already capturing
         }
@@ -156,36 +140,36 @@ impl AudioCapture {
         let channels = self.config.channels as usize;
 
         // Create resampler if needed
-        let resampler: Arc<Mutex<Option<FftFixedIn<f32>>>> = if needs_resampling {
-            let chunk_size = 1024;
-            let resampler = FftFixedIn::<f32>::new(
-                source_rate as usize,
-                target_rate as usize,
-                chunk_size,
-                2,
-                1, // mono
-            )
-            .map_err(|e| AudioCaptureError::ResamplerError(e.to_string()))?;
-            Arc::new(Mutex::new(Some(resampler)))
+        let resampler = if needs_resampling {
+            Some(ChunkResampler::new(source_rate, target_rate, PASSTHROUGH_CHUNK_SIZE)?)
         } else {
-            Arc::new(Mutex::new(None))
+            None
         };
+        let resampler = Arc::new(Mutex::new(resampler));
 
         // Buffer for accumulating samples before resampling
-        let buffer: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::with_capacity(2048)));
-
-        let error_callback = |err| {
+        let buffer = Arc::new(Mutex::new(CaptureBuffer {
+            samples: Vec::with_capacity(2 * PASSTHROUGH_CHUNK_SIZE),
+            clock: ChunkClock::new(source_rate),
+            rechunker: Rechunker::new(self.chunk_size, target_rate),
+        }));
+
+        // Runs on cpal's thread; the capture thread polls the flag and reconnects
+        self.stream_failed.store(false, Ordering::SeqCst);
+        let stream_failed = self.stream_failed.clone();
+        let error_callback = move |err: cpal::StreamError| {
             log::error!("Audio capture error: {}", err);
+            stream_failed.store(true, Ordering::SeqCst);
         };
 
-        let stream = match self.device.default_input_config()?.sample_format() {
+        let stream = match self.sample_format {
             SampleFormat::F32 => self.build_stream::<f32>(
                 tx.clone(),
                 is_capturing.clone(),
                 resampler.clone(),
                 buffer.clone(),
                 channels,
-                error_callback,
+                error_callback.clone(),
             )?,
             SampleFormat::I16 => self.build_stream::<i16>(
                 tx.clone(),
@@ -193,7 +177,7 @@ impl AudioCapture {
                 resampler.clone(),
                 buffer.clone(),
                 channels,
-                error_callback,
+                error_callback.clone(),
             )?,
             SampleFormat::U16 => self.build_stream::<u16>(
                 tx.clone(),
@@ -212,6 +196,7 @@ impl AudioCapture {
 
         self.is_capturing.store(true, Ordering::SeqCst);
         self.stream = Some(stream);
+        self.pipeline = Some(CapturePipeline { tx, buffer, resampler });
 
         log::info!("Audio capture started");
         Ok(())
@@ -219,10 +204,10 @@ impl AudioCapture {
 
     fn build_stream<T>(
         &self,
-        tx: mpsc::UnboundedSender<Vec<f32>>,
+        tx: AudioSender,
         is_capturing: Arc<AtomicBool>,
-        resampler: Arc<Mutex<Option<FftFixedIn<f32>>>>,
-        buffer: Arc<Mutex<Vec<f32>>>,
+        resampler: Arc<Mutex<Option<ChunkResampler>>>,
+        buffer: Arc<Mutex<CaptureBuffer>>,
         channels: usize,
         error_callback: impl FnMut(cpal::StreamError) + Send + 'static,
     ) -> Result<Stream, AudioCaptureError>
@@ -230,19 +215,37 @@ impl AudioCapture {
         T: cpal::Sample + cpal::SizedSample + Send + 'static,
         f32: cpal::FromSample<T>,
     {
-        let chunk_size = 1024;
-
-        let data_callback = move |data: &[T], _: &cpal::InputCallbackInfo| {
+        // Cut chunks to exactly the size the resampler consumes
+        let chunk_size = resampler
+            .lock()
+            .as_ref()
+            .map_or(PASSTHROUGH_CHUNK_SIZE, ChunkResampler::input_frames_next);
+        let stream_failed = self.stream_failed.clone();
+        let channel_mode = validate_channel_mode(self.channel_mode, channels);
+        let callback_elevated = self.callback_elevated.clone();
+        let mut elevation_pending = self.high_priority;
+
+        let data_callback = move |data: &[T], info: &cpal::InputCallbackInfo| {
+            // The callback thread belongs to cpal, so it can only be raised from in here
+            if elevation_pending {
+                elevation_pending = false;
+                callback_elevated.store(elevate_current_thread("capture callback"), Ordering::Relaxed);
+            }
             if !is_capturing.load(Ordering::SeqCst) {
                 return;
             }
 
+            let timestamp = info.timestamp();
+            let latency = timestamp.callback.duration_since(&timestamp.capture).unwrap_or_default();
+
             // Convert to f32 and mix to mono if needed
             let samples: Vec<f32> = if channels > 1 {
+                let mut frame_f32 = Vec::with_capacity(channels);
                 data.chunks(channels)
                     .map(|frame| {
-                        let sum: f32 = frame.iter().map(|s| <f32 as FromSample<T>>::from_sample_(*s)).sum();
-                        sum / channels as f32
+                        frame_f32.clear();
+                        frame_f32.extend(frame.iter().map(|s| <f32 as FromSample<T>>::from_sample_(*s)));
+                        downmix_frame(&frame_f32, channel_mode)
                     })
                     .collect()
             } else {
@@ -250,29 +253,34 @@ impl AudioCapture {
             };
 
             let mut buf = buffer.lock();
-            buf.extend(samples);
+            let buffered = buf.samples.len();
+            buf.clock.on_block(buffered, capture_time_ms(latency));
+            buf.samples.extend(samples);
 
             // Process when we have enough samples
-            while buf.len() >= chunk_size {
-                let chunk: Vec<f32> = buf.drain(..chunk_size).collect();
+            while buf.samples.len() >= chunk_size {
+                let chunk: Vec<f32> = buf.samples.drain(..chunk_size).collect();
+                let timestamp_ms = buf.clock.take_chunk(chunk_size);
 
                 let output = {
                     let mut resampler_guard = resampler.lock();
                     if let Some(ref mut resampler) = *resampler_guard {
-                        match resampler.process(&[chunk], None) {
-                            Ok(resampled) => resampled.into_iter().next().unwrap_or_default(),
+                        match resampler.process(chunk, timestamp_ms) {
+                            Ok(resampled) => resampled,
                             Err(e) => {
+                                // Report as a stream failure so the capture thread reconnects
                                 log::error!("Resampling error: {}", e);
-                                continue;
+                                stream_failed.store(true, Ordering::SeqCst);
+                                return;
                             }
                         }
                     } else {
-                        chunk
+                        AudioChunk { timestamp_ms, samples: chunk }
                     }
                 };
 
-                if !output.is_empty() {
-                    let _ = tx.send(output);
+                if !output.samples.is_empty() {
+                    buf.send(&tx, output);
                 }
             }
         };
@@ -282,18 +290,66 @@ impl AudioCapture {
             .map_err(|e| AudioCaptureError::StreamError(e.to_string()))
     }
 
-    /// Stop capturing audio
+    /// Whether cpal's callback thread was raised to high priority
+    pub fn callback_elevated(&self) -> bool {
+        self.callback_elevated.load(Ordering::Relaxed)
+    }
+
+    /// Stop capturing audio, sending any partially filled chunk first
     pub fn stop(&mut self) {
         self.is_capturing.store(false, Ordering::SeqCst);
         self.stream = None;
+        if let Some(pipeline) = self.pipeline.take() {
+            pipeline.flush();
+        }
         log::info!("Audio capture stopped");
     }
 
+    /// Format the current stream was negotiated with
+    pub fn format(&self) -> CaptureFormat {
+        CaptureFormat {
+            sample_rate: self.sample_rate,
+            channels: self.config.channels,
+            sample_format: self.sample_format.to_string(),
+        }
+    }
+
+    /// Format the device currently reports as its default
+    ///
+    /// Differs from [`Self::format`] after the device switched modes on the
+    /// fly (e.g. a Bluetooth headset moving between HFP and A2DP).
+    pub fn device_format(&self) -> Result<CaptureFormat, AudioCaptureError> {
+        let supported = self.device.default_input_config()?;
+        Ok(CaptureFormat {
+            sample_rate: supported.sample_rate().0,
+            channels: supported.channels(),
+            sample_format: supported.sample_format().to_string(),
+        })
+    }
+
+    /// Rebuild the stream with the device's current default format
+    pub fn reconfigure(&mut self, tx: AudioSender) -> Result<(), AudioCaptureError> {
+        self.stop();
+
+        let supported = self.device.default_input_config()?;
+        self.sample_format = supported.sample_format();
+        self.sample_rate = supported.sample_rate().0;
+        self.config.channels = supported.channels();
+        self.config.sample_rate = cpal::SampleRate(self.sample_rate);
+
+        self.start(tx)
+    }
+
     /// Check if currently capturing
     pub fn is_capturing(&self) -> bool {
         self.is_capturing.load(Ordering::SeqCst)
     }
 
+    /// Whether the stream reported an error since it was started
+    pub fn has_failed(&self) -> bool {
+        self.stream_failed.load(Ordering::SeqCst)
+    }
+
     /// Get the device name
     pub fn device_name(&self) -> String {
         self.device.name().unwrap_or_else(|_| "Unknown".to_string())
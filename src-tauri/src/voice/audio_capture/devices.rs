@@ -0,0 +1,136 @@
+//! Input and output device enumeration
+
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::{Device, SupportedStreamConfigRange};
+use serde::{Deserialize, Serialize};
+
+/// Information about an audio device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioDeviceInfo {
+    /// Device name/identifier
+    pub name: String,
+    /// Whether this is the default device
+    pub is_default: bool,
+    /// Sample rate of the default stream config (0 if unknown)
+    pub default_sample_rate: u32,
+    /// Channel count of the default stream config (0 if unknown)
+    pub channels: u16,
+    /// Common sample rates within the device's supported ranges
+    pub supported_sample_rates: Vec<u32>,
+}
+
+/// Standard rates reported in `supported_sample_rates`
+const COMMON_SAMPLE_RATES: [u32; 9] = [8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000];
+
+impl AudioDeviceInfo {
+    /// Describe `device`, leaving capabilities empty if its configs can't be queried
+    fn from_device(device: &Device, name: String, is_default: bool, input: bool) -> Self {
+        let default_config = if input {
+            device.default_input_config()
+        } else {
+            device.default_output_config()
+        };
+        let (default_sample_rate, channels) = default_config
+            .map(|config| (config.sample_rate().0, config.channels()))
+            .unwrap_or((0, 0));
+        let ranges: Result<Vec<SupportedStreamConfigRange>, _> = if input {
+            device.supported_input_configs().map(Iterator::collect)
+        } else {
+            device.supported_output_configs().map(Iterator::collect)
+        };
+
+        Self {
+            name,
+            is_default,
+            default_sample_rate,
+            channels,
+            supported_sample_rates: supported_rates(&ranges.unwrap_or_default()),
+        }
+    }
+}
+
+/// Common rates that fall inside any of the supported ranges
+fn supported_rates(ranges: &[SupportedStreamConfigRange]) -> Vec<u32> {
+    COMMON_SAMPLE_RATES
+        .into_iter()
+        .filter(|rate| {
+            ranges
+                .iter()
+                .any(|range| (range.min_sample_rate().0..=range.max_sample_rate().0).contains(rate))
+        })
+        .collect()
+}
+
+/// List all available input (microphone) devices
+pub fn list_input_devices() -> Vec<AudioDeviceInfo> {
+    let host = cpal::default_host();
+    let default_device_name = host
+        .default_input_device()
+        .and_then(|d| d.name().ok());
+
+    host.input_devices()
+        .map(|devices| {
+            devices
+                .filter_map(|device| {
+                    let name = device.name().ok()?;
+                    let is_default = default_device_name.as_ref() == Some(&name);
+                    Some(AudioDeviceInfo::from_device(&device, name, is_default, true))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// List all available output (speaker) devices
+pub fn list_output_devices() -> Vec<AudioDeviceInfo> {
+    let host = cpal::default_host();
+    let default_device_name = host
+        .default_output_device()
+        .and_then(|d| d.name().ok());
+
+    host.output_devices()
+        .map(|devices| {
+            devices
+                .filter_map(|device| {
+                    let name = device.name().ok()?;
+                    let is_default = default_device_name.as_ref() == Some(&name);
+                    Some(AudioDeviceInfo::from_device(&device, name, is_default, false))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Find an input device by name
+pub(super) fn find_input_device_by_name(name: &str) -> Option<Device> {
+    let host = cpal::default_host();
+    host.input_devices().ok()?.find(|d| {
+        d.name().map(|n| n == name).unwrap_or(false)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cpal::{SampleFormat, SampleRate, SupportedBufferSize};
+
+    #[test]
+    fn test_supported_rates_within_ranges() {
+        let range = |min, max| {
+            SupportedStreamConfigRange::new(2, SampleRate(min), SampleRate(max), SupportedBufferSize::Unknown, SampleFormat::F32)
+        };
+        assert_eq!(supported_rates(&[range(44100, 48000)]), vec![44100, 48000]);
+        assert_eq!(supported_rates(&[range(8000, 8000), range(16000, 22050)]), vec![8000, 16000, 22050]);
+        assert!(supported_rates(&[]).is_empty());
+    }
+
+    // Requires an audio input device
+    #[test]
+    #[ignore]
+    fn test_default_input_device_reports_sample_rate() {
+        let devices = list_input_devices();
+        let default = devices.iter().find(|d| d.is_default).expect("default input device");
+        assert!(default.default_sample_rate > 0);
+        assert!(default.channels > 0);
+    }
+}
@@ -0,0 +1,55 @@
+//! Buffering between cpal's callback and the processing loop
+
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+use crate::voice::chunk::{AudioChunk, AudioSender, ChunkClock, Rechunker};
+use crate::voice::resample::ChunkResampler;
+
+/// Mono samples waiting to be cut into chunks, with the capture time of the first
+///
+/// Resampled output is cut again by `rechunker` so the processing loop always
+/// gets chunks of the configured `chunk_size`.
+pub(super) struct CaptureBuffer {
+    pub(super) samples: Vec<f32>,
+    pub(super) clock: ChunkClock,
+    pub(super) rechunker: Rechunker,
+}
+
+impl CaptureBuffer {
+    /// Send the complete `chunk_size` chunks available after adding `output`
+    pub(super) fn send(&mut self, tx: &AudioSender, output: AudioChunk) {
+        for chunk in self.rechunker.push(output) {
+            let _ = tx.send(chunk);
+        }
+    }
+}
+
+/// State shared with the stream callback, kept so residual audio can be flushed on stop
+pub(super) struct CapturePipeline {
+    pub(super) tx: AudioSender,
+    pub(super) buffer: Arc<Mutex<CaptureBuffer>>,
+    pub(super) resampler: Arc<Mutex<Option<ChunkResampler>>>,
+}
+
+impl CapturePipeline {
+    /// Send whatever is still buffered, draining the resampler
+    pub(super) fn flush(&self) {
+        let mut buffer = self.buffer.lock();
+        let residual = std::mem::take(&mut buffer.samples);
+        let timestamp_ms = buffer.clock.take_chunk(residual.len());
+
+        let output = match *self.resampler.lock() {
+            Some(ref mut resampler) => resampler.flush(residual, timestamp_ms),
+            None => Ok(AudioChunk { timestamp_ms, samples: residual }),
+        };
+        match output {
+            Ok(chunk) if !chunk.samples.is_empty() => buffer.send(&self.tx, chunk),
+            Ok(_) => {}
+            Err(e) => log::error!("Failed to flush resampler: {}", e),
+        }
+        if let Some(rest) = buffer.rechunker.flush() {
+            let _ = self.tx.send(rest);
+        }
+    }
+}
@@ -1,69 +1,59 @@
 //! Audio processing helpers for the voice controller
 
 use parking_lot::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
-use tokio::sync::mpsc;
+use std::time::Instant;
 
-use super::buffer::AudioBuffer;
+use super::chunk::AudioReceiver;
 use super::config::VoiceConfig;
-use super::state_machine::{StateAction, VoiceEvent, VoiceState, VoiceStateMachine};
-use super::vad::{VadResult, VoiceActivityDetector};
-use super::wake_word::WakeWordDetector;
-
-/// Shared state for the voice controller
-pub struct VoiceControllerState {
-    pub state_machine: VoiceStateMachine,
-    pub config: VoiceConfig,
-    pub is_running: bool,
-    pub wake_word_enabled: bool,
-    pub input_device: Option<String>,
-    pub output_device: Option<String>,
+use super::control::{ControlMessage, ControlReceiver};
+use super::controller_state::VoiceControllerState;
+use super::events::{emit_debug_log, emit_event, emit_state_changed, record_session_audio, EventSink};
+use super::filters::AudioFilter;
+use super::memory::sample_bytes;
+use super::state_handlers::{process_idle_state, process_listening_state, process_speaking_state};
+use super::state_machine::VoiceState;
+
+mod components;
+
+use components::{sync_detection_tuning, Components};
+
+/// Counts a processing loop as live until dropped, including on panic
+struct LiveLoop(Arc<AtomicUsize>);
+
+impl LiveLoop {
+    fn enter(counter: Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(counter)
+    }
 }
 
-impl VoiceControllerState {
-    pub fn new() -> Self {
-        Self {
-            state_machine: VoiceStateMachine::new(),
-            config: VoiceConfig::default(),
-            is_running: false,
-            wake_word_enabled: true,
-            input_device: None,
-            output_device: None,
-        }
+impl Drop for LiveLoop {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
     }
 }
 
 /// Run the audio processing loop in a dedicated thread
 pub fn run_audio_processing_loop(
-    app_handle: &Option<AppHandle>,
-    models_dir: &std::path::PathBuf,
+    sink: &EventSink,
+    models_dir: &std::path::Path,
     config: &VoiceConfig,
     state: &Arc<RwLock<VoiceControllerState>>,
-    audio_rx: &mut mpsc::UnboundedReceiver<Vec<f32>>,
+    audio_rx: &mut AudioReceiver,
+    control_rx: &mut ControlReceiver,
 ) {
-    emit_debug_log(app_handle, "info", "Audio processing thread started");
-
-    // Initialize components
-    emit_debug_log(app_handle, "info", "Loading wake word detector models...");
-    let mut wake_word_detector = match WakeWordDetector::new(models_dir, config.clone()) {
-        Ok(detector) => {
-            emit_debug_log(app_handle, "info", "Wake word detector initialized");
-            Some(detector)
-        }
-        Err(e) => {
-            emit_debug_log(app_handle, "error", &format!("Wake word init failed: {}", e));
-            log::error!("Failed to initialize wake word detector: {}", e);
-            if let Some(ref handle) = app_handle {
-                let _ = handle.emit("voice-error", format!("Wake word init failed: {}", e));
-            }
-            None
-        }
-    };
+    let _live = LiveLoop::enter(state.read().processing_loops.clone());
+    emit_debug_log(sink, "info", "Audio processing thread started");
 
-    let mut vad = VoiceActivityDetector::new(config);
-    let mut audio_buffer = AudioBuffer::new(config.chunk_size * 2);
+    let mut components = Components::load(sink, state, models_dir, config);
     let mut chunk_count: u64 = 0;
+    let mut reported_drops: u64 = 0;
+    let mut previous_state = VoiceState::Idle;
+    let preroll_bytes = state.read().preroll_bytes.clone();
+    let tuning_dirty = state.read().tuning_dirty.clone();
+    let health = state.read().health.clone();
 
     // Create a tokio runtime for this thread
     let rt = tokio::runtime::Builder::new_current_thread()
@@ -71,158 +61,325 @@ pub fn run_audio_processing_loop(
         .build()
         .expect("Failed to create tokio runtime");
 
-    emit_debug_log(app_handle, "info", "Entering audio processing loop...");
+    emit_debug_log(sink, "info", "Entering audio processing loop...");
+    // Start the stall clock only now that the models are loaded
+    health.beat(Instant::now());
 
     rt.block_on(async {
-        while let Some(samples) = audio_rx.recv().await {
+        while let Some(mut chunk) = audio_rx.recv().await {
+            let chunk_start = Instant::now();
+            health.beat(chunk_start);
             chunk_count += 1;
 
             if chunk_count == 1 {
-                emit_debug_log(app_handle, "info", &format!("First audio: {} samples", samples.len()));
-            } else if chunk_count % 100 == 0 {
-                emit_debug_log(app_handle, "debug", &format!("Processed {} chunks", chunk_count));
+                emit_debug_log(sink, "info", &format!("First audio: {} samples", chunk.samples.len()));
+            } else if chunk_count.is_multiple_of(100) {
+                emit_debug_log(sink, "debug", &format!("Processed {} chunks", chunk_count));
+                if let Some(ref detector) = components.wake_word_detector {
+                    let stats = detector.stats();
+                    log::debug!("Wake word inference: {}", stats.latency);
+                    emit_event(sink, state, "voice-wake-word-stats", stats);
+                }
+            }
+
+            let dropped = audio_rx.dropped();
+            if dropped > reported_drops {
+                log::warn!("Audio queue overrun, {} chunks dropped", dropped - reported_drops);
+                state.write().dropped_audio_chunks = dropped;
+                health.record_dropped(dropped);
+                emit_event(sink, state, "voice-audio-overrun", serde_json::json!({
+                    "dropped": dropped - reported_drops,
+                    "total_dropped": dropped,
+                }));
+                reported_drops = dropped;
             }
 
             let state_guard = state.read();
             if !state_guard.is_running {
-                emit_debug_log(app_handle, "info", "Voice system stopping...");
+                emit_debug_log(sink, "info", "Voice system stopping...");
                 break;
             }
-            let current_state = state_guard.state_machine.state();
-            let wake_word_enabled = state_guard.wake_word_enabled;
+            let mut current_state = state_guard.state_machine.state();
+            let wake_word_enabled =
+                state_guard.wake_word_enabled && state_guard.user_present && state_guard.schedule_active;
+            let recording = state_guard.session_recorder.is_some();
+            let muted = state_guard.config.mutes_input_in(current_state);
+            let time_in_state = state_guard.state_machine.time_in_state();
+            let expired_timeout = state_guard
+                .state_machine
+                .follow_up_timeout()
+                .or_else(|| state_guard.config.timeout_for(current_state))
+                .filter(|timeout| time_in_state >= *timeout);
             drop(state_guard);
 
-            audio_buffer.push_samples(&samples);
+            if let Some(timeout) = expired_timeout {
+                let result = state.write().state_machine.check_timeout(Instant::now(), timeout);
+                if let Some(result) = result {
+                    emit_debug_log(sink, "warn", &format!("{} timed out", current_state));
+                    emit_state_changed(sink, state, result.new_state);
+                    current_state = result.new_state;
+                    components.vad.reset();
+                    if let Some(ref mut detector) = components.wake_word_detector {
+                        detector.reset();
+                    }
+                }
+            }
+
+            // Frames from before a pause in scanning would make detection fire on stale audio
+            if let Some(ref mut detector) = components.wake_word_detector {
+                detector.set_scanning(wake_word_enabled);
+            }
+
+            // Each capture starts with fresh VAD, however Listening was entered
+            if current_state == VoiceState::Listening && previous_state != VoiceState::Listening {
+                components.vad.reset();
+            }
+            previous_state = current_state;
+
+            if recording {
+                record_session_audio(state, &chunk.samples);
+            }
+
+            let mut shutdown = false;
+            while let Ok(message) = control_rx.try_recv() {
+                if let ControlMessage::Shutdown = message {
+                    shutdown = true;
+                    break;
+                }
+                components.apply_control_message(sink, state, models_dir, message);
+            }
+            if shutdown {
+                emit_debug_log(sink, "info", "Voice system stopping...");
+                break;
+            }
+            // Our own TTS output would otherwise reach detection and the pre-roll
+            if muted {
+                continue;
+            }
+
+            if let Some(ref mut detector) = components.wake_word_detector {
+                sync_detection_tuning(state, &tuning_dirty, detector);
+            }
+
+            components.filter_chain.process(&mut chunk.samples);
+            components.record_noise(sink, state, &chunk.samples);
+            if let Some(ref mut suppressor) = components.noise_suppressor {
+                suppressor.process(&mut chunk.samples);
+            }
+
+            // Detection always sees the gained audio; STT only when configured to
+            let gained = components.agc.as_mut().map(|agc| {
+                let mut gained = chunk.clone();
+                agc.process(&mut gained.samples);
+                gained
+            });
+            let detection_chunk = gained.as_ref().unwrap_or(&chunk);
+
+            // Calibrate on what the VAD hears
+            components.calibrate(sink, state, models_dir, &detection_chunk.samples);
 
-            // Emit audio level for visualization
-            let rms = calculate_rms(&samples);
-            if let Some(ref handle) = app_handle {
-                let _ = handle.emit("voice-audio-level", rms);
+            // Meter what detection hears, after gain, smoothed and throttled
+            let level = components.level_meter.update(&detection_chunk.samples, detection_chunk.timestamp_ms);
+            if let Some(level) = level {
+                emit_event(sink, state, "voice-audio-level", level);
             }
+            let capture_chunk = if components.agc_capture { detection_chunk } else { &chunk };
+            components.preroll.push_samples(&capture_chunk.samples);
+            preroll_bytes.store(sample_bytes(components.preroll.allocated()), Ordering::Relaxed);
 
-            process_audio_state(
-                app_handle, state, current_state, wake_word_enabled,
-                &samples, &mut wake_word_detector, &mut vad,
-            );
+            let state_start = Instant::now();
+            match current_state {
+                VoiceState::Idle if wake_word_enabled => {
+                    process_idle_state(
+                        sink,
+                        state,
+                        detection_chunk,
+                        &components.preroll,
+                        &mut components.wake_word_detector,
+                        &mut components.vad,
+                        &mut components.cooldown,
+                    );
+                }
+                VoiceState::Listening => {
+                    process_listening_state(
+                        sink,
+                        state,
+                        detection_chunk,
+                        capture_chunk,
+                        &mut components.wake_word_detector,
+                        &mut components.vad,
+                    );
+                    // Lets the UI show that speech is being heard before it ends
+                    if components.vad_throttle.due(detection_chunk.timestamp_ms) {
+                        emit_event(sink, state, "voice-vad", components.vad.status());
+                    }
+                }
+                VoiceState::Speaking => {
+                    process_speaking_state(
+                        sink,
+                        state,
+                        detection_chunk,
+                        capture_chunk,
+                        &mut components.barge_in,
+                        &mut components.vad,
+                    );
+                }
+                _ => {}
+            }
+            if current_state != VoiceState::Speaking {
+                components.barge_in.reset();
+            }
+
+            // Idle chunks are dominated by wake word inference; everything else is capture
+            let state_time = state_start.elapsed();
+            let total_time = chunk_start.elapsed();
+            let inference_time = if current_state == VoiceState::Idle && wake_word_enabled {
+                state_time
+            } else {
+                std::time::Duration::ZERO
+            };
+            state.write().cpu_usage.record(chunk_start, inference_time, total_time - inference_time);
         }
     });
 
+    let mut state_guard = state.write();
+    state_guard.inference_canceller = None;
+    state_guard.cpu_usage.clear();
+    drop(state_guard);
     log::info!("Voice processing thread exiting");
 }
 
-/// Process audio based on current state
-fn process_audio_state(
-    app_handle: &Option<AppHandle>,
-    state: &Arc<RwLock<VoiceControllerState>>,
-    current_state: VoiceState,
-    wake_word_enabled: bool,
-    samples: &[f32],
-    wake_word_detector: &mut Option<WakeWordDetector>,
-    vad: &mut VoiceActivityDetector,
-) {
-    match current_state {
-        VoiceState::Idle => {
-            process_idle_state(app_handle, state, wake_word_enabled, samples, wake_word_detector, vad);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::chunk::{audio_channel, AudioChunk};
+    use crate::voice::control::control_channel;
+    use crate::voice::events::CollectingSink;
+    use crate::voice::state_machine::VoiceEvent;
+
+    /// Run one 80ms chunk per level through the loop, with `control` queued before the audio
+    fn run_levels(
+        state: &Arc<RwLock<VoiceControllerState>>,
+        config: &VoiceConfig,
+        levels: impl IntoIterator<Item = f32>,
+        queue_capacity: usize,
+        control: Option<ControlMessage>,
+    ) -> Arc<CollectingSink> {
+        let collected = Arc::new(CollectingSink::default());
+        let sink: EventSink = Some(collected.clone());
+        state.write().is_running = true;
+        let (audio_tx, mut audio_rx) = audio_channel(queue_capacity);
+        let (control_tx, mut control_rx) = control_channel();
+        if let Some(message) = control {
+            control_tx.send(message).unwrap();
         }
-        VoiceState::Listening => {
-            process_listening_state(app_handle, state, samples, wake_word_detector, vad);
+        for (i, level) in levels.into_iter().enumerate() {
+            let chunk = AudioChunk {
+                timestamp_ms: i as f64 * 80.0,
+                samples: vec![level; 1280],
+            };
+            audio_tx.send(chunk).unwrap();
         }
-        _ => {}
-    }
-}
+        drop(audio_tx);
 
-/// Process audio in idle state (wake word detection)
-fn process_idle_state(
-    app_handle: &Option<AppHandle>,
-    state: &Arc<RwLock<VoiceControllerState>>,
-    wake_word_enabled: bool,
-    samples: &[f32],
-    wake_word_detector: &mut Option<WakeWordDetector>,
-    vad: &mut VoiceActivityDetector,
-) {
-    if !wake_word_enabled {
-        return;
+        let models_dir = std::path::PathBuf::from("does-not-exist");
+        run_audio_processing_loop(&sink, &models_dir, config, state, &mut audio_rx, &mut control_rx);
+        collected
     }
 
-    if let Some(ref mut detector) = wake_word_detector {
-        match detector.process_audio(samples) {
-            Ok(Some(score)) => {
-                if detector.is_detected(score) {
-                    emit_debug_log(app_handle, "info", &format!("WAKE WORD! Score: {:.3}", score));
-                    log::info!("Wake word detected! Score: {}", score);
-
-                    let mut state_guard = state.write();
-                    state_guard.state_machine.transition(VoiceEvent::WakeWordDetected);
-                    let new_state = state_guard.state_machine.state();
-                    drop(state_guard);
-
-                    if let Some(ref handle) = app_handle {
-                        let _ = handle.emit("voice-wake-word", serde_json::json!({ "score": score }));
-                        let _ = handle.emit("voice-state-changed", new_state);
-                    }
+    #[test]
+    fn test_loop_emits_to_sink() {
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let collected = run_levels(&state, &VoiceConfig::default(), [0.1; 3], 32, None);
 
-                    vad.reset();
-                }
-            }
-            Ok(None) => {}
-            Err(e) => {
-                emit_debug_log(app_handle, "error", &format!("Wake word error: {}", e));
-            }
-        }
+        let levels = collected.payloads("voice-audio-level");
+        assert_eq!(levels.len(), 3);
+        assert!((levels[0]["rms"].as_f64().unwrap() - 0.1).abs() < 1e-6);
+        assert_eq!(collected.payloads("voice-error").len(), 1, "missing models reported");
+        assert!(collected
+            .payloads("debug-log")
+            .iter()
+            .any(|log| log["message"] == "Audio processing thread started"));
     }
-}
 
-/// Process audio in listening state (VAD for speech end)
-fn process_listening_state(
-    app_handle: &Option<AppHandle>,
-    state: &Arc<RwLock<VoiceControllerState>>,
-    samples: &[f32],
-    wake_word_detector: &mut Option<WakeWordDetector>,
-    vad: &mut VoiceActivityDetector,
-) {
-    state.write().state_machine.add_audio(samples);
+    #[test]
+    fn test_overrun_reported_once_per_backlog() {
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let collected = run_levels(&state, &VoiceConfig::default(), [0.1; 10], 4, None);
 
-    let vad_result = vad.process(samples);
-    if vad_result == VadResult::SpeechEnd {
-        log::info!("Speech end detected");
-
-        let mut state_guard = state.write();
-        let result = state_guard.state_machine.transition(VoiceEvent::VadSpeechEnd);
-        let new_state = result.new_state;
-        drop(state_guard);
+        assert_eq!(collected.payloads("voice-audio-level").len(), 4);
+        assert_eq!(
+            collected.payloads("voice-audio-overrun"),
+            vec![serde_json::json!({ "dropped": 6, "total_dropped": 6 })]
+        );
+        assert_eq!(state.read().dropped_audio_chunks, 6);
+    }
 
-        if let Some(ref handle) = app_handle {
-            let _ = handle.emit("voice-state-changed", new_state);
+    #[test]
+    fn test_reload_models_rebuilds_detector() {
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let reload = Some(ControlMessage::ReloadModels);
+        let collected = run_levels(&state, &VoiceConfig::default(), [0.1; 2], 32, reload);
 
-            if let Some(StateAction::SendToStt(audio)) = result.action {
-                let _ = handle.emit("voice-audio-captured", audio);
-            }
-        }
+        // Loaded once at startup and again on reload; the failure is reported and processing continues
+        assert_eq!(collected.payloads("voice-error").len(), 2);
+        assert_eq!(collected.payloads("voice-audio-level").len(), 2);
+    }
 
-        vad.reset();
+    #[test]
+    fn test_vad_status_emitted_while_listening() {
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        state.write().state_machine.transition(VoiceEvent::ManualTrigger);
+        let config = VoiceConfig {
+            silence_timeout_ms: 2400,
+            ..Default::default()
+        };
+        // Speech, then enough silence for the smoothed level to decay
+        let levels = (0..20).map(|i| if i < 3 { 0.5 } else { 0.0 });
+        let collected = run_levels(&state, &config, levels, 32, None);
 
-        if let Some(ref mut detector) = wake_word_detector {
-            detector.reset();
-        }
+        let statuses = collected.payloads("voice-vad");
+        let speech: Vec<bool> = statuses.iter().map(|s| s["is_speech"].as_bool().unwrap()).collect();
+        let silent: Vec<u64> = statuses.iter().map(|s| s["silent_frames"].as_u64().unwrap()).collect();
+        assert_eq!(speech.len(), 20);
+        assert!(speech[0]);
+        assert!(!speech[19]);
+        assert_eq!(silent[0], 0);
+        assert!(silent[19] > 0);
+        assert!(statuses[0]["rms"].as_f64().unwrap() > 0.0);
     }
-}
 
-/// Calculate RMS of audio samples
-pub fn calculate_rms(samples: &[f32]) -> f32 {
-    if samples.is_empty() {
-        return 0.0;
+    /// Run loud chunks through the loop while Speaking; returns the sink and retained pre-roll bytes
+    fn run_while_speaking(mute_mic_during_speaking: bool) -> (Arc<CollectingSink>, usize) {
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let config = VoiceConfig {
+            mute_mic_during_speaking,
+            ..Default::default()
+        };
+        let mut guard = state.write();
+        guard.config = config.clone();
+        guard.state_machine.transition(VoiceEvent::ManualTrigger);
+        guard.state_machine.transition(VoiceEvent::VadSpeechEnd);
+        guard.state_machine.transition(VoiceEvent::TranscriptionComplete("hello".to_string()));
+        guard.state_machine.transition(VoiceEvent::ResponseReady("Hi there".to_string()));
+        assert_eq!(guard.state_machine.state(), VoiceState::Speaking);
+        drop(guard);
+
+        let collected = run_levels(&state, &config, [0.5; 10], 32, None);
+        let preroll = state.read().preroll_bytes.load(Ordering::Relaxed);
+        (collected, preroll)
     }
-    let sum_squares: f32 = samples.iter().map(|&s| s * s).sum();
-    (sum_squares / samples.len() as f32).sqrt()
-}
 
-/// Emit a debug log message to the frontend
-pub fn emit_debug_log(app_handle: &Option<AppHandle>, level: &str, message: &str) {
-    log::info!("[{}] {}", level, message);
-    if let Some(ref handle) = app_handle {
-        let _ = handle.emit("debug-log", serde_json::json!({
-            "level": level,
-            "message": message
-        }));
+    #[test]
+    fn test_muted_speaking_chunks_skip_detection_and_preroll() {
+        let (collected, preroll) = run_while_speaking(true);
+        assert!(collected.payloads("voice-audio-level").is_empty());
+        assert!(collected.payloads("voice-barge-in").is_empty());
+        assert_eq!(preroll, 0);
+
+        // Without muting the same chunks are metered and retained
+        let (collected, preroll) = run_while_speaking(false);
+        assert_eq!(collected.payloads("voice-audio-level").len(), 10);
+        assert!(preroll > 0);
     }
 }
@@ -0,0 +1,307 @@
+//! Processing stages of the audio loop, and how control messages rebuild them
+
+use parking_lot::RwLock;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::voice::agc::AutomaticGain;
+use crate::voice::barge_in::BargeInDetector;
+use crate::voice::buffer::AudioBuffer;
+use crate::voice::config::VoiceConfig;
+use crate::voice::control::ControlMessage;
+use crate::voice::controller_state::VoiceControllerState;
+use crate::voice::cooldown::DetectionCooldown;
+use crate::voice::events::{emit_debug_log, emit_error, emit_event, EventSink};
+use crate::voice::filters::FilterChain;
+use crate::voice::level_meter::{EmitThrottle, LevelMeter};
+use crate::voice::noise_calibration::{CalibrationError, NoiseCalibration, NoiseCalibrator};
+use crate::voice::noise_profile::{NoiseProfile, NoiseProfileRecorder, SpectralSubtractor};
+use crate::voice::tts_playback::start_tts_playback;
+use crate::voice::vad::VoiceActivityDetector;
+use crate::voice::wake_word::WakeWordDetector;
+
+/// Everything the loop runs a chunk through, owned by the processing thread
+pub(super) struct Components {
+    pub(super) wake_word_detector: Option<WakeWordDetector>,
+    pub(super) vad: VoiceActivityDetector,
+    pub(super) filter_chain: FilterChain,
+    pub(super) preroll: AudioBuffer,
+    pub(super) cooldown: DetectionCooldown,
+    pub(super) barge_in: BargeInDetector,
+    pub(super) noise_suppressor: Option<SpectralSubtractor>,
+    /// Recording requested by `RecordNoiseProfile`, if in progress
+    pub(super) noise_recorder: Option<NoiseProfileRecorder>,
+    /// Measurement requested by `CalibrateNoiseFloor`, if in progress
+    pub(super) calibrator: Option<NoiseCalibrator>,
+    pub(super) agc: Option<AutomaticGain>,
+    /// Whether the audio sent to STT is gained too
+    pub(super) agc_capture: bool,
+    pub(super) level_meter: LevelMeter,
+    pub(super) vad_throttle: EmitThrottle,
+}
+
+impl Components {
+    pub(super) fn load(
+        sink: &EventSink,
+        state: &Arc<RwLock<VoiceControllerState>>,
+        models_dir: &Path,
+        config: &VoiceConfig,
+    ) -> Self {
+        Self {
+            wake_word_detector: load_wake_word_detector(sink, state, models_dir, config),
+            vad: VoiceActivityDetector::load(models_dir, config),
+            filter_chain: FilterChain::from_config(config),
+            preroll: AudioBuffer::new(config.preroll_samples()),
+            cooldown: DetectionCooldown::new(config.wake_word_cooldown_ms),
+            barge_in: BargeInDetector::new(config),
+            noise_suppressor: SpectralSubtractor::from_config(config),
+            noise_recorder: None,
+            calibrator: None,
+            agc: AutomaticGain::from_config(config),
+            agc_capture: config.agc_apply_to_capture,
+            level_meter: LevelMeter::new(config.level_emit_interval_ms),
+            vad_throttle: EmitThrottle::new(config.level_emit_interval_ms),
+        }
+    }
+
+    /// Rebuild every stage from `config`, keeping the detector if the new one fails to load
+    fn reload(
+        &mut self,
+        sink: &EventSink,
+        state: &Arc<RwLock<VoiceControllerState>>,
+        models_dir: &Path,
+        config: &VoiceConfig,
+    ) {
+        if let Some(detector) = load_wake_word_detector(sink, state, models_dir, config) {
+            self.wake_word_detector = Some(detector);
+        }
+        self.vad = VoiceActivityDetector::load(models_dir, config);
+        self.filter_chain = FilterChain::from_config(config);
+        self.preroll = AudioBuffer::new(config.preroll_samples());
+        self.cooldown.set_cooldown_ms(config.wake_word_cooldown_ms);
+        self.barge_in = BargeInDetector::new(config);
+        self.noise_suppressor = SpectralSubtractor::from_config(config);
+        self.agc = AutomaticGain::from_config(config);
+        self.agc_capture = config.agc_apply_to_capture;
+        self.level_meter = LevelMeter::new(config.level_emit_interval_ms);
+        self.vad_throttle = EmitThrottle::new(config.level_emit_interval_ms);
+    }
+
+    /// Apply a control message from the controller; `Shutdown` is left to the loop
+    pub(super) fn apply_control_message(
+        &mut self,
+        sink: &EventSink,
+        state: &Arc<RwLock<VoiceControllerState>>,
+        models_dir: &Path,
+        message: ControlMessage,
+    ) {
+        match message {
+            ControlMessage::Reload(new_config) => self.reload(sink, state, models_dir, &new_config),
+            ControlMessage::ReloadModels => {
+                // Audio keeps queueing in the channel while the models load
+                emit_debug_log(sink, "info", "Reloading wake word models...");
+                let config = state.read().config.clone();
+                if let Some(detector) = load_wake_word_detector(sink, state, models_dir, &config) {
+                    self.wake_word_detector = Some(detector);
+                }
+            }
+            ControlMessage::ReleaseBuffers => {
+                self.preroll.release();
+                if let Some(ref mut detector) = self.wake_word_detector {
+                    detector.clear_score_history();
+                }
+                emit_debug_log(sink, "info", "Released retained buffers");
+            }
+            ControlMessage::RecordNoiseProfile(duration_ms) => {
+                let sample_rate = state.read().config.sample_rate;
+                self.noise_recorder = Some(NoiseProfileRecorder::new(duration_ms, sample_rate));
+                emit_debug_log(sink, "info", &format!("Recording noise profile for {}ms", duration_ms));
+            }
+            ControlMessage::UpdateNoiseSuppression => {
+                self.noise_suppressor = SpectralSubtractor::from_config(&state.read().config);
+            }
+            ControlMessage::CalibrateNoiseFloor(duration_ms) => {
+                let sample_rate = state.read().config.sample_rate;
+                self.calibrator = Some(NoiseCalibrator::new(duration_ms, sample_rate));
+                emit_debug_log(sink, "info", &format!("Calibrating noise floor for {}ms", duration_ms));
+            }
+            ControlMessage::PlayTts { samples, sample_rate } => {
+                start_tts_playback(sink, state, samples, sample_rate);
+            }
+            ControlMessage::SetInferenceHop(hop) => {
+                if let Some(ref mut detector) = self.wake_word_detector {
+                    detector.set_inference_stride(hop);
+                }
+                emit_debug_log(sink, "info", &format!("Inference hop set to {} frames", hop));
+            }
+            ControlMessage::ResetWakeWordStats => {
+                if let Some(ref mut detector) = self.wake_word_detector {
+                    detector.reset_stats();
+                }
+            }
+            ControlMessage::Shutdown => {}
+        }
+    }
+
+    /// Feed a filtered chunk to a noise profile recording, storing the profile once complete
+    pub(super) fn record_noise(
+        &mut self,
+        sink: &EventSink,
+        state: &Arc<RwLock<VoiceControllerState>>,
+        samples: &[f32],
+    ) {
+        if let Some(profile) = self.noise_recorder.as_mut().and_then(|recorder| recorder.push(samples)) {
+            self.noise_recorder = None;
+            self.noise_suppressor = store_noise_profile(sink, state, profile);
+        }
+    }
+
+    /// Feed what the VAD hears to a noise floor calibration, rebuilding the VAD once complete
+    pub(super) fn calibrate(
+        &mut self,
+        sink: &EventSink,
+        state: &Arc<RwLock<VoiceControllerState>>,
+        models_dir: &Path,
+        samples: &[f32],
+    ) {
+        if let Some(result) = self.calibrator.as_mut().and_then(|c| c.push(samples)) {
+            self.calibrator = None;
+            if let Some(calibrated) = apply_calibration(sink, state, models_dir, result) {
+                self.vad = calibrated;
+            }
+        }
+    }
+}
+
+/// Load the wake word detector, publishing its canceller to the shared state
+///
+/// On failure the error is reported and `None` returned so the caller can
+/// keep running (or keep its previous detector when reloading).
+fn load_wake_word_detector(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    models_dir: &Path,
+    config: &VoiceConfig,
+) -> Option<WakeWordDetector> {
+    emit_debug_log(sink, "info", "Loading wake word detector models...");
+    match WakeWordDetector::new(models_dir, config.clone()) {
+        Ok(detector) => {
+            emit_debug_log(sink, "info", "Wake word detector initialized");
+            state.write().inference_canceller = Some(detector.canceller());
+            Some(detector)
+        }
+        Err(e) => {
+            emit_debug_log(sink, "error", &format!("Wake word init failed: {}", e));
+            log::error!("Failed to initialize wake word detector: {}", e);
+            emit_error(sink, state, format!("Wake word init failed: {}", e));
+            None
+        }
+    }
+}
+
+/// Copy changed sensitivity and threshold from the shared config into the detector
+///
+/// Only takes the state lock when `dirty` is set, keeping the per-chunk path lock-free.
+pub(super) fn sync_detection_tuning(
+    state: &Arc<RwLock<VoiceControllerState>>,
+    dirty: &AtomicBool,
+    detector: &mut WakeWordDetector,
+) {
+    if !dirty.swap(false, Ordering::AcqRel) {
+        return;
+    }
+    let state_guard = state.read();
+    detector.set_sensitivity(state_guard.config.sensitivity);
+    detector.set_threshold(state_guard.config.wake_word_threshold);
+}
+
+/// Store a calibrated silence threshold and rebuild the VAD with it
+///
+/// A rejected calibration leaves the threshold and VAD unchanged.
+fn apply_calibration(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    models_dir: &Path,
+    result: Result<NoiseCalibration, CalibrationError>,
+) -> Option<VoiceActivityDetector> {
+    match result {
+        Ok(calibration) => {
+            let mut state_guard = state.write();
+            state_guard.config.silence_threshold = calibration.silence_threshold;
+            let config = state_guard.config.clone();
+            drop(state_guard);
+
+            log::info!(
+                "Noise floor {:.4}, silence threshold set to {:.4}",
+                calibration.noise_floor,
+                calibration.silence_threshold
+            );
+            emit_event(sink, state, "voice-calibration-complete", calibration);
+            Some(VoiceActivityDetector::load(models_dir, &config))
+        }
+        Err(e) => {
+            emit_debug_log(sink, "warn", &e.to_string());
+            emit_event(sink, state, "voice-calibration-failed", serde_json::json!({
+                "reason": e.to_string(),
+            }));
+            None
+        }
+    }
+}
+
+/// Save a freshly recorded noise profile to the config and rebuild the suppression stage
+fn store_noise_profile(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    profile: NoiseProfile,
+) -> Option<SpectralSubtractor> {
+    let mut state_guard = state.write();
+    state_guard.config.noise_profile = Some(profile);
+    let suppressor = SpectralSubtractor::from_config(&state_guard.config);
+    drop(state_guard);
+
+    emit_event(
+        sink,
+        state,
+        "voice-noise-profile-recorded",
+        serde_json::json!({ "suppressing": suppressor.is_some() }),
+    );
+    suppressor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::wake_word::mock::mock_detector;
+
+    #[test]
+    fn test_sensitivity_change_reaches_detector() {
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let dirty = state.read().tuning_dirty.clone();
+        let mut mock = mock_detector(VoiceConfig::default(), 0.0);
+        let chunk = vec![0.0; 1280];
+
+        // Below the default 0.5 threshold
+        *mock.score.lock() = 0.3;
+        let (name, score) = loop {
+            if let Some(best) = mock.detector.process_audio(&chunk).unwrap() {
+                break best;
+            }
+        };
+        assert!(!mock.detector.is_detected(&name, score));
+
+        state.write().config.sensitivity = 2.0;
+        state.write().config.wake_word_threshold = 0.4;
+        sync_detection_tuning(&state, &dirty, &mut mock.detector);
+        assert_eq!(mock.detector.sensitivity(), 1.0, "unchanged until flagged");
+
+        dirty.store(true, Ordering::Release);
+        sync_detection_tuning(&state, &dirty, &mut mock.detector);
+        let (name, score) = mock.detector.process_audio(&chunk).unwrap().unwrap();
+        assert_eq!(mock.detector.sensitivity(), 2.0);
+        assert_eq!(mock.detector.threshold(), 0.4);
+        assert!(mock.detector.is_detected(&name, score), "0.3 clears 0.4 at sensitivity 2.0");
+        assert!(!dirty.load(Ordering::Acquire));
+    }
+}
@@ -0,0 +1,159 @@
+//! Where the processing loop's audio comes from
+//!
+//! Live sessions capture from a cpal input device on a dedicated thread.
+//! [`FileAudioSource`] replays a WAV file instead, so the whole pipeline can
+//! be driven deterministically in tests or to reproduce a recorded session.
+
+use parking_lot::RwLock;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use super::audio_capture::AudioCaptureError;
+use super::controller_state::VoiceControllerState;
+use super::capture_thread::spawn_capture_thread;
+use super::chunk::{unix_time_ms, AudioChunk, AudioSender};
+use super::config::VoiceConfig;
+use super::events::EventSink;
+use super::playback::{read_wav_mono, resample_linear};
+
+/// Producer of mono audio chunks at the configured sample rate
+pub trait AudioSource: Send + Sync {
+    /// Start sending chunks to `audio_tx` until `stop` is set
+    ///
+    /// Returns once audio is flowing, or with the error that prevented it.
+    fn start(
+        &self,
+        sink: EventSink,
+        state: Arc<RwLock<VoiceControllerState>>,
+        config: VoiceConfig,
+        input_device: Option<String>,
+        audio_tx: AudioSender,
+        stop: Arc<AtomicBool>,
+    ) -> Result<(), AudioCaptureError>;
+}
+
+/// Live capture from the selected (or default) input device
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpalAudioSource;
+
+impl AudioSource for CpalAudioSource {
+    fn start(
+        &self,
+        sink: EventSink,
+        state: Arc<RwLock<VoiceControllerState>>,
+        config: VoiceConfig,
+        input_device: Option<String>,
+        audio_tx: AudioSender,
+        stop: Arc<AtomicBool>,
+    ) -> Result<(), AudioCaptureError> {
+        spawn_capture_thread(sink, state, config, input_device, audio_tx, stop)
+    }
+}
+
+/// Replays a WAV file once, in `chunk_size` chunks
+///
+/// Unlike a live device, replay waits for room in the audio queue rather than
+/// dropping chunks. The input device selection is ignored. Files at another rate are resampled
+/// linearly, which is fine for replaying speech but not for measuring the resampler.
+#[derive(Debug, Clone)]
+pub struct FileAudioSource {
+    path: PathBuf,
+    /// Pace chunks at their duration, as a live device would, instead of as fast as possible
+    realtime: bool,
+}
+
+impl FileAudioSource {
+    pub fn new(path: impl Into<PathBuf>, realtime: bool) -> Self {
+        Self { path: path.into(), realtime }
+    }
+
+    /// Read the file into chunks stamped from `start_ms`, the last one possibly shorter
+    pub fn read_chunks(&self, config: &VoiceConfig, start_ms: f64) -> Result<Vec<AudioChunk>, AudioCaptureError> {
+        let (samples, rate) =
+            read_wav_mono(&self.path).map_err(|e| AudioCaptureError::FileError(e.to_string()))?;
+        let samples = resample_linear(&samples, rate, config.sample_rate);
+        let chunk_ms = config.chunk_size as f64 * 1000.0 / config.sample_rate as f64;
+
+        Ok(samples
+            .chunks(config.chunk_size.max(1))
+            .enumerate()
+            .map(|(i, chunk)| AudioChunk {
+                timestamp_ms: start_ms + i as f64 * chunk_ms,
+                samples: chunk.to_vec(),
+            })
+            .collect())
+    }
+}
+
+impl AudioSource for FileAudioSource {
+    fn start(
+        &self,
+        _sink: EventSink,
+        _state: Arc<RwLock<VoiceControllerState>>,
+        config: VoiceConfig,
+        _input_device: Option<String>,
+        audio_tx: AudioSender,
+        stop: Arc<AtomicBool>,
+    ) -> Result<(), AudioCaptureError> {
+        let chunks = self.read_chunks(&config, unix_time_ms(SystemTime::now()))?;
+        let chunk_duration = Duration::from_secs_f64(config.chunk_size as f64 / config.sample_rate as f64);
+        let realtime = self.realtime;
+        log::info!("Replaying {} chunks from {}", chunks.len(), self.path.display());
+
+        thread::spawn(move || {
+            for chunk in chunks {
+                if stop.load(Ordering::SeqCst) || audio_tx.send_waiting(chunk).is_err() {
+                    return;
+                }
+                if realtime {
+                    thread::sleep(chunk_duration);
+                }
+            }
+            log::info!("Audio file replay finished");
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::wav::write_wav;
+
+    #[test]
+    fn test_file_is_cut_into_configured_chunks() {
+        let path = std::env::temp_dir().join(format!("jarvis-source-{}.wav", std::process::id()));
+        write_wav(&path, &vec![0.25; 1280 * 3 + 100], 16000).unwrap();
+        let config = VoiceConfig::default();
+
+        let chunks = FileAudioSource::new(&path, false).read_chunks(&config, 1000.0).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let lengths: Vec<usize> = chunks.iter().map(|chunk| chunk.samples.len()).collect();
+        assert_eq!(lengths, vec![1280, 1280, 1280, 100]);
+        assert_eq!(chunks[2].timestamp_ms, 1160.0);
+        assert!((chunks[0].samples[0] - 0.25).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_replay_sends_every_chunk() {
+        let path = std::env::temp_dir().join(format!("jarvis-replay-{}.wav", std::process::id()));
+        write_wav(&path, &vec![0.0; 1280 * 4], 16000).unwrap();
+        let (audio_tx, mut audio_rx) = crate::voice::chunk::audio_channel(2);
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+
+        FileAudioSource::new(&path, false)
+            .start(None, state, VoiceConfig::default(), None, audio_tx, Arc::new(AtomicBool::new(false)))
+            .unwrap();
+        let mut received = 0;
+        while audio_rx.blocking_recv().is_some() {
+            received += 1;
+        }
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(received, 4);
+    }
+}
@@ -0,0 +1,67 @@
+//! Barge-in detection - the user talking over the assistant's TTS
+//!
+//! Speech must stay above `barge_in_threshold` for `barge_in_min_speech_ms`
+//! before it counts, so short bursts of the assistant's own output leaking
+//! into the microphone don't interrupt it.
+
+use super::dsp::calculate_rms;
+use super::config::VoiceConfig;
+
+/// Tracks sustained loud input while in `Speaking`
+#[derive(Debug, Clone)]
+pub struct BargeInDetector {
+    threshold: f32,
+    min_speech_ms: f64,
+    sample_rate: u32,
+    speech_ms: f64,
+}
+
+impl BargeInDetector {
+    pub fn new(config: &VoiceConfig) -> Self {
+        Self {
+            threshold: config.barge_in_threshold,
+            min_speech_ms: config.barge_in_min_speech_ms as f64,
+            sample_rate: config.sample_rate,
+            speech_ms: 0.0,
+        }
+    }
+
+    /// Process a chunk and return true once speech has lasted long enough
+    pub fn process(&mut self, samples: &[f32]) -> bool {
+        if calculate_rms(samples) < self.threshold {
+            self.speech_ms = 0.0;
+            return false;
+        }
+        self.speech_ms += samples.len() as f64 * 1000.0 / self.sample_rate as f64;
+        self.speech_ms >= self.min_speech_ms
+    }
+
+    pub fn reset(&mut self) {
+        self.speech_ms = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requires_sustained_speech() {
+        let config = VoiceConfig {
+            barge_in_min_speech_ms: 200,
+            ..Default::default()
+        };
+        let mut detector = BargeInDetector::new(&config);
+        let loud: Vec<f32> = (0..1280).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let quiet = vec![0.0; 1280];
+
+        // 80ms chunks: a short echo burst followed by silence doesn't count
+        assert!(!detector.process(&loud));
+        assert!(!detector.process(&loud));
+        assert!(!detector.process(&quiet));
+
+        assert!(!detector.process(&loud));
+        assert!(!detector.process(&loud));
+        assert!(detector.process(&loud));
+    }
+}
@@ -34,6 +34,16 @@ impl AudioBuffer {
         self.buffer.iter().skip(start).copied().collect()
     }
 
+    /// Get the samples covering the last `ms` milliseconds at `sample_rate`
+    pub fn get_last_ms(&self, ms: u32, sample_rate: u32) -> Vec<f32> {
+        self.get_last_n((ms as u64 * sample_rate as u64 / 1000) as usize)
+    }
+
+    /// Duration of the buffered audio at `sample_rate`, in whole milliseconds
+    pub fn duration_ms(&self, sample_rate: u32) -> u32 {
+        (self.buffer.len() as u64 * 1000 / sample_rate.max(1) as u64) as u32
+    }
+
     /// Get all samples as a vec
     pub fn get_all(&self) -> Vec<f32> {
         self.buffer.iter().copied().collect()
@@ -58,6 +68,17 @@ impl AudioBuffer {
     pub fn clear(&mut self) {
         self.buffer.clear();
     }
+
+    /// Clear the buffer and free its allocation
+    pub fn release(&mut self) {
+        self.buffer.clear();
+        self.buffer.shrink_to_fit();
+    }
+
+    /// Number of samples currently allocated
+    pub fn allocated(&self) -> usize {
+        self.buffer.capacity()
+    }
 }
 
 /// Ring buffer for mel spectrogram frames
@@ -66,6 +87,10 @@ pub struct MelBuffer {
     frames: VecDeque<Vec<f32>>,
     capacity: usize,
     frame_size: usize,
+    /// New frames required between inference runs
+    stride: usize,
+    /// Frames pushed since the last inference run
+    frames_since_inference: usize,
 }
 
 impl MelBuffer {
@@ -75,6 +100,8 @@ impl MelBuffer {
             frames: VecDeque::with_capacity(capacity),
             capacity,
             frame_size,
+            stride: 1,
+            frames_since_inference: 0,
         }
     }
 
@@ -84,6 +111,40 @@ impl MelBuffer {
             self.frames.pop_front();
         }
         self.frames.push_back(frame);
+        self.frames_since_inference += 1;
+    }
+
+    /// Push every frame of a raw melspectrogram output laid out as `[frames][frame_size]`
+    ///
+    /// Applies the OpenWakeWord transform `(value / 10.0) + 2.0` to each value.
+    /// A trailing incomplete frame is ignored. Returns the number of frames pushed.
+    pub fn push_melspec_output(&mut self, data: &[f32]) -> usize {
+        let mut pushed = 0;
+        for frame in data.chunks_exact(self.frame_size) {
+            self.push_frame(frame.iter().map(|&v| (v / 10.0) + 2.0).collect());
+            pushed += 1;
+        }
+        pushed
+    }
+
+    /// Set how many new frames must arrive between inference runs (min 1)
+    pub fn set_stride(&mut self, stride: usize) {
+        self.stride = stride.max(1);
+    }
+
+    /// Get the inference stride in frames
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Check if the buffer is full and `stride` new frames arrived since the last run
+    pub fn should_infer(&self) -> bool {
+        self.is_ready() && self.frames_since_inference >= self.stride
+    }
+
+    /// Record that inference ran on the current window
+    pub fn mark_inferred(&mut self) {
+        self.frames_since_inference = 0;
     }
 
     /// Get all frames flattened into a single vec (for model input)
@@ -109,6 +170,7 @@ impl MelBuffer {
     /// Clear the buffer
     pub fn clear(&mut self) {
         self.frames.clear();
+        self.frames_since_inference = 0;
     }
 }
 
@@ -139,6 +201,19 @@ mod tests {
         assert_eq!(buffer.get_last_n(3), vec![3.0, 4.0, 5.0]);
     }
 
+    #[test]
+    fn test_audio_buffer_get_last_ms() {
+        let mut buffer = AudioBuffer::new(16000);
+        buffer.push_samples(&vec![0.5; 16000]);
+        assert_eq!(buffer.get_last_ms(100, 16000).len(), 1600);
+        assert_eq!(buffer.duration_ms(16000), 1000);
+
+        let mut short = AudioBuffer::new(16000);
+        short.push_samples(&vec![0.5; 800]);
+        assert_eq!(short.get_last_ms(100, 16000).len(), 800);
+        assert_eq!(short.duration_ms(16000), 50);
+    }
+
     #[test]
     fn test_mel_buffer() {
         let mut buffer = MelBuffer::new(3, 32);
@@ -149,4 +224,55 @@ mod tests {
         assert!(buffer.is_ready());
         assert_eq!(buffer.get_flattened().len(), 96);
     }
+
+    #[test]
+    fn test_mel_buffer_push_melspec_output() {
+        let mut buffer = MelBuffer::new(10, 4);
+        // 5 frames of 4 bands plus 3 trailing values from an odd-length output
+        let data: Vec<f32> = (0..23).map(|i| i as f32).collect();
+
+        assert_eq!(buffer.push_melspec_output(&data), 5);
+        assert_eq!(buffer.len(), 5);
+        let flattened = buffer.get_flattened();
+        assert_eq!(flattened.len(), 20);
+        assert_eq!(flattened[0], 2.0);
+        assert_eq!(flattened[19], 19.0 / 10.0 + 2.0);
+    }
+
+    #[test]
+    fn test_mel_buffer_stride() {
+        let mut buffer = MelBuffer::new(3, 32);
+        buffer.set_stride(2);
+
+        let mut runs = 0;
+        for _ in 0..9 {
+            buffer.push_frame(vec![0.0; 32]);
+            if buffer.should_infer() {
+                buffer.mark_inferred();
+                runs += 1;
+            }
+        }
+
+        // Ready after 3 frames, then once every 2 frames: frames 3, 5, 7, 9
+        assert_eq!(runs, 4);
+    }
+
+    #[test]
+    fn test_mel_buffer_stride_spans_chunks() {
+        // One melspectrogram output per 80ms chunk carries 5 frames
+        let mut buffer = MelBuffer::new(10, 2);
+        buffer.set_stride(8);
+
+        let mut runs = Vec::new();
+        for chunk in 0..8 {
+            buffer.push_melspec_output(&[0.0; 10]);
+            if buffer.should_infer() {
+                buffer.mark_inferred();
+                runs.push(chunk);
+            }
+        }
+
+        // Full at chunk 1, then on each chunk that completes 8 new frames: every other chunk
+        assert_eq!(runs, vec![1, 3, 5, 7]);
+    }
 }
@@ -0,0 +1,84 @@
+//! Quality assessment of captured utterances before they are sent to STT
+
+use serde::Serialize;
+
+use super::dsp::{calculate_dbfs, calculate_peak, DBFS_FLOOR};
+use super::config::VoiceConfig;
+
+/// Absolute sample value at or above which a sample counts as clipped
+pub const CLIP_LEVEL: f32 = 0.999;
+
+/// Quality metrics for a captured utterance
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CaptureQuality {
+    /// Fraction of samples at full scale (0.0 - 1.0)
+    pub clipping_ratio: f32,
+    /// Peak level in dBFS
+    pub peak_dbfs: f32,
+    /// Whether the audio is likely usable for transcription
+    pub likely_usable: bool,
+}
+
+impl CaptureQuality {
+    /// Assess a captured buffer against the configured quality thresholds
+    pub fn assess(samples: &[f32], config: &VoiceConfig) -> Self {
+        if samples.is_empty() {
+            return Self {
+                clipping_ratio: 0.0,
+                peak_dbfs: DBFS_FLOOR,
+                likely_usable: false,
+            };
+        }
+
+        let clipped = samples.iter().filter(|s| s.abs() >= CLIP_LEVEL).count();
+        let clipping_ratio = clipped as f32 / samples.len() as f32;
+
+        let peak_dbfs = calculate_dbfs(calculate_peak(samples));
+
+        let likely_usable = clipping_ratio <= config.capture_max_clipping_ratio
+            && peak_dbfs >= config.capture_min_peak_dbfs;
+
+        Self {
+            clipping_ratio,
+            peak_dbfs,
+            likely_usable,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_capture_is_usable() {
+        let samples: Vec<f32> = (0..1600).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let quality = CaptureQuality::assess(&samples, &VoiceConfig::default());
+        assert_eq!(quality.clipping_ratio, 0.0);
+        assert!((quality.peak_dbfs - -6.02).abs() < 0.1);
+        assert!(quality.likely_usable);
+    }
+
+    #[test]
+    fn test_quiet_capture_is_flagged() {
+        let samples = vec![0.001; 1600];
+        let quality = CaptureQuality::assess(&samples, &VoiceConfig::default());
+        assert!((quality.peak_dbfs - -60.0).abs() < 0.1);
+        assert!(!quality.likely_usable);
+    }
+
+    #[test]
+    fn test_clipped_capture_is_flagged() {
+        let samples: Vec<f32> = (0..1600).map(|i| if i % 4 == 0 { 1.0 } else { 0.3 }).collect();
+        let quality = CaptureQuality::assess(&samples, &VoiceConfig::default());
+        assert!((quality.clipping_ratio - 0.25).abs() < 0.001);
+        assert!(!quality.likely_usable);
+    }
+
+    #[test]
+    fn test_silence_uses_floor() {
+        let quality = CaptureQuality::assess(&[0.0; 100], &VoiceConfig::default());
+        assert_eq!(quality.peak_dbfs, DBFS_FLOOR);
+        assert!(!quality.likely_usable);
+    }
+}
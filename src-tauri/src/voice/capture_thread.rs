@@ -0,0 +1,283 @@
+//! Capture thread - owns the audio stream and follows device format changes
+//!
+//! cpal streams cannot move between threads, so the stream lives on its own
+//! thread for the lifetime of the session. The thread polls the device's
+//! default format and rebuilds the stream when it changes, which happens when
+//! e.g. a Bluetooth headset switches between HFP and A2DP. When the stream
+//! dies (typically the device was unplugged) it reopens the selected device,
+//! or the default one if the selection is gone, with exponential backoff.
+
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::audio_capture::{AudioCapture, AudioCaptureError};
+use super::controller_state::VoiceControllerState;
+use super::events::{emit_debug_log, emit_error, emit_event, EventSink};
+use super::chunk::{AudioChunk, AudioSender};
+use super::config::VoiceConfig;
+use super::watchdog::restart_backoff;
+use super::VoiceError;
+
+/// How often the device's default format is checked for changes
+pub const FORMAT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the stop flag is checked
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Start capture on a dedicated thread
+///
+/// Returns once the stream is running, or with the error that prevented it.
+/// The stream is released when `stop` is set.
+pub fn spawn_capture_thread(
+    sink: EventSink,
+    state: Arc<RwLock<VoiceControllerState>>,
+    config: VoiceConfig,
+    input_device: Option<String>,
+    audio_tx: AudioSender,
+    stop: Arc<AtomicBool>,
+) -> Result<(), AudioCaptureError> {
+    let (ready_tx, ready_rx) = std::sync::mpsc::sync_channel(1);
+
+    thread::spawn(move || {
+        let capture = AudioCapture::with_device(&config, input_device.as_deref()).and_then(|mut capture| {
+            capture.start(audio_tx.clone())?;
+            Ok(capture)
+        });
+
+        let mut capture = match capture {
+            Ok(capture) => {
+                let _ = ready_tx.send(Ok(()));
+                capture
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+
+        let mut format = capture.format();
+        let mut last_poll = Instant::now();
+
+        while !stop.load(Ordering::SeqCst) {
+            thread::sleep(STOP_POLL_INTERVAL);
+            sync_capture_priority(&state, &capture);
+            if capture.has_failed() {
+                emit_debug_log(&sink, "warn", "Capture stream failed, reconnecting...");
+                capture.stop();
+                let reopened = reconnect_with_backoff(
+                    config.device_reconnect_attempts,
+                    config.device_reconnect_backoff_ms,
+                    &stop,
+                    |_| open_capture(&config, input_device.as_deref(), &audio_tx),
+                    |attempt, delay| {
+                        emit_event(&sink, &state, "voice-device-reconnecting", serde_json::json!({
+                            "attempt": attempt,
+                            "max_attempts": config.device_reconnect_attempts,
+                            "delay_ms": delay.as_millis() as u64,
+                        }));
+                        sleep_unless_stopped(delay, &stop);
+                    },
+                );
+                match reopened {
+                    Ok(reopened) => {
+                        capture = reopened;
+                        format = capture.format();
+                        emit_debug_log(&sink, "info", &format!("Reconnected to {}", capture.device_name()));
+                        emit_event(&sink, &state, "voice-capture-reconfigured", format.clone());
+                    }
+                    Err(_) if stop.load(Ordering::SeqCst) => break,
+                    Err(e) => {
+                        give_up_capture(&sink, &state, &audio_tx, &stop, e);
+                        return;
+                    }
+                }
+                continue;
+            }
+            if !config.auto_reconfigure_capture || last_poll.elapsed() < FORMAT_POLL_INTERVAL {
+                continue;
+            }
+            last_poll = Instant::now();
+
+            let current = match capture.device_format() {
+                Ok(current) => current,
+                Err(e) => {
+                    log::debug!("Could not query capture format: {}", e);
+                    continue;
+                }
+            };
+            if current == format {
+                continue;
+            }
+
+            emit_debug_log(&sink, "info", &format!("Capture format changed: {:?} -> {:?}", format, current));
+            match capture.reconfigure(audio_tx.clone()) {
+                Ok(()) => {
+                    format = capture.format();
+                    emit_event(&sink, &state, "voice-capture-reconfigured", format.clone());
+                }
+                Err(e) => {
+                    log::error!("Failed to reconfigure capture: {}", e);
+                    emit_error(&sink, &state, format!("Capture reconfigure failed: {}", e));
+                    // Don't retry against the same format every poll
+                    format = current;
+                }
+            }
+        }
+
+        capture.stop();
+    });
+
+    ready_rx
+        .recv()
+        .unwrap_or_else(|_| Err(AudioCaptureError::StreamError("Capture thread exited".to_string())))
+}
+
+/// Record whether the current stream's callback thread got high priority
+fn sync_capture_priority(state: &RwLock<VoiceControllerState>, capture: &AudioCapture) {
+    let elevated = capture.callback_elevated();
+    if state.read().audio_priority.capture_elevated != elevated {
+        state.write().audio_priority.capture_elevated = elevated;
+    }
+}
+
+/// Open and start capture on the preferred device, falling back to the default one
+fn open_capture(
+    config: &VoiceConfig,
+    preferred: Option<&str>,
+    audio_tx: &AudioSender,
+) -> Result<AudioCapture, AudioCaptureError> {
+    let mut capture = match AudioCapture::with_device(config, preferred) {
+        Err(AudioCaptureError::DeviceNotFound(name)) => {
+            log::warn!("Input device {} is gone, using the default device", name);
+            AudioCapture::with_device(config, None)?
+        }
+        result => result?,
+    };
+    capture.start(audio_tx.clone())?;
+    Ok(capture)
+}
+
+/// Retry `open` until it succeeds, `max_attempts` run out or `stop` is set
+///
+/// `wait` is called before each attempt with the 1-based attempt number and
+/// its backoff delay, and is expected to sleep for that long.
+pub fn reconnect_with_backoff<C>(
+    max_attempts: u32,
+    base_delay_ms: u64,
+    stop: &AtomicBool,
+    mut open: impl FnMut(u32) -> Result<C, AudioCaptureError>,
+    mut wait: impl FnMut(u32, Duration),
+) -> Result<C, AudioCaptureError> {
+    let mut last_error = AudioCaptureError::NoInputDevice;
+    for attempt in 1..=max_attempts {
+        wait(attempt, restart_backoff(base_delay_ms, attempt));
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        match open(attempt) {
+            Ok(capture) => return Ok(capture),
+            Err(e) => {
+                log::warn!("Capture reconnect attempt {} failed: {}", attempt, e);
+                last_error = e;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+/// Sleep for `delay`, returning early once `stop` is set
+fn sleep_unless_stopped(delay: Duration, stop: &AtomicBool) {
+    let deadline = Instant::now() + delay;
+    while !stop.load(Ordering::SeqCst) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        thread::sleep(remaining.min(STOP_POLL_INTERVAL));
+    }
+}
+
+/// Stop the voice system after the input device could not be reopened
+fn give_up_capture(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    audio_tx: &AudioSender,
+    stop: &AtomicBool,
+    error: AudioCaptureError,
+) {
+    log::error!("Giving up on audio capture: {}", error);
+    state.write().is_running = false;
+    stop.store(true, Ordering::SeqCst);
+    emit_error(sink, state, VoiceError::from(error).to_string());
+    // Wake the processing loop so it sees the system stopped and exits
+    let _ = audio_tx.send(AudioChunk {
+        timestamp_ms: 0.0,
+        samples: Vec::new(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Capture source that fails a fixed number of times before opening
+    struct MockCaptureSource {
+        failures: u32,
+        opened: u32,
+    }
+
+    impl MockCaptureSource {
+        fn open(&mut self, attempt: u32) -> Result<u32, AudioCaptureError> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(AudioCaptureError::DeviceNotFound("USB Mic".to_string()));
+            }
+            self.opened += 1;
+            Ok(attempt)
+        }
+    }
+
+    #[test]
+    fn test_reconnect_backs_off_until_device_returns() {
+        let stop = AtomicBool::new(false);
+        let mut source = MockCaptureSource { failures: 2, opened: 0 };
+        let mut waits = Vec::new();
+
+        let result = reconnect_with_backoff(5, 100, &stop, |attempt| source.open(attempt), |attempt, delay| {
+            waits.push((attempt, delay.as_millis()))
+        });
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(source.opened, 1);
+        assert_eq!(waits, vec![(1, 100), (2, 200), (3, 400)]);
+    }
+
+    #[test]
+    fn test_reconnect_gives_up_after_max_attempts() {
+        let stop = AtomicBool::new(false);
+        let mut source = MockCaptureSource { failures: u32::MAX, opened: 0 };
+        let mut attempts = 0;
+
+        let result = reconnect_with_backoff(4, 100, &stop, |attempt| source.open(attempt), |_, _| attempts += 1);
+
+        assert!(matches!(result, Err(AudioCaptureError::DeviceNotFound(_))));
+        assert_eq!(attempts, 4);
+        assert_eq!(source.opened, 0);
+    }
+
+    #[test]
+    fn test_reconnect_stops_when_requested() {
+        let stop = AtomicBool::new(false);
+        let mut source = MockCaptureSource { failures: 0, opened: 0 };
+
+        let result = reconnect_with_backoff(5, 100, &stop, |attempt| source.open(attempt), |_, _| {
+            stop.store(true, Ordering::SeqCst)
+        });
+
+        assert!(result.is_err());
+        assert_eq!(source.opened, 0);
+    }
+}
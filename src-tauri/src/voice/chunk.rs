@@ -0,0 +1,253 @@
+//! Timestamped audio chunks passed from capture to the processing loop
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::{self, error::TryRecvError, error::TrySendError};
+
+/// Mono samples tagged with the capture time of their first sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioChunk {
+    /// Wall-clock capture time of the first sample, in ms since the UNIX epoch
+    pub timestamp_ms: f64,
+    pub samples: Vec<f32>,
+}
+
+/// The processing loop has exited and no longer receives audio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueClosed;
+
+/// Create the bounded queue from capture to the processing loop
+pub fn audio_channel(capacity: usize) -> (AudioSender, AudioReceiver) {
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+    let dropped = Arc::new(AtomicU64::new(0));
+    (AudioSender { tx, dropped: dropped.clone() }, AudioReceiver { rx, dropped })
+}
+
+/// Sending half of the audio queue
+#[derive(Debug, Clone)]
+pub struct AudioSender {
+    tx: mpsc::Sender<AudioChunk>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl AudioSender {
+    /// Queue a chunk without blocking
+    ///
+    /// When the processing loop is a full queue behind (a stalled inference or
+    /// a model reload), the chunk is dropped and counted instead, so the
+    /// backlog, and with it detection latency, stays bounded. Fails only once
+    /// the receiver is gone.
+    pub fn send(&self, chunk: AudioChunk) -> Result<(), QueueClosed> {
+        match self.tx.try_send(chunk) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(TrySendError::Closed(_)) => Err(QueueClosed),
+        }
+    }
+
+    /// Queue a chunk, waiting for room instead of dropping it
+    ///
+    /// For sources that can pause, like file replay. Must not be called from async code.
+    pub fn send_waiting(&self, chunk: AudioChunk) -> Result<(), QueueClosed> {
+        self.tx.blocking_send(chunk).map_err(|_| QueueClosed)
+    }
+
+    /// Chunks dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Receiving half of the audio queue
+#[derive(Debug)]
+pub struct AudioReceiver {
+    rx: mpsc::Receiver<AudioChunk>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl AudioReceiver {
+    pub async fn recv(&mut self) -> Option<AudioChunk> {
+        self.rx.recv().await
+    }
+
+    pub fn blocking_recv(&mut self) -> Option<AudioChunk> {
+        self.rx.blocking_recv()
+    }
+
+    pub fn try_recv(&mut self) -> Result<AudioChunk, TryRecvError> {
+        self.rx.try_recv()
+    }
+
+    /// Chunks the senders dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Milliseconds since the UNIX epoch
+pub fn unix_time_ms(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64() * 1000.0
+}
+
+/// Wall-clock time of a capture that happened `latency` before now
+pub fn capture_time_ms(latency: Duration) -> f64 {
+    unix_time_ms(SystemTime::now()) - latency.as_secs_f64() * 1000.0
+}
+
+/// Tracks the capture time of the first sample still waiting in the capture buffer
+///
+/// The capture callback delivers arbitrary block sizes which are re-cut into
+/// fixed chunks; each chunk's timestamp is advanced from the buffer start by
+/// the duration of the samples before it. The offset is kept as a sample count
+/// so rounding doesn't accumulate while the buffer never fully drains.
+#[derive(Debug, Clone)]
+pub struct ChunkClock {
+    sample_rate: u32,
+    buffer_start_ms: f64,
+    taken_samples: u64,
+}
+
+impl ChunkClock {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            buffer_start_ms: 0.0,
+            taken_samples: 0,
+        }
+    }
+
+    /// Note a callback block captured at `capture_ms`, given the samples already buffered
+    pub fn on_block(&mut self, buffered: usize, capture_ms: f64) {
+        if buffered == 0 {
+            self.buffer_start_ms = capture_ms;
+            self.taken_samples = 0;
+        }
+    }
+
+    /// Timestamp of a chunk of `len` samples taken from the front of the buffer
+    pub fn take_chunk(&mut self, len: usize) -> f64 {
+        let timestamp = self.buffer_start_ms + self.taken_samples as f64 * 1000.0 / self.sample_rate as f64;
+        self.taken_samples += len as u64;
+        timestamp
+    }
+}
+
+/// Re-cuts variable-length chunks into chunks of exactly `chunk_size` samples
+///
+/// Resampled capture output doesn't line up with the frame size the wake word
+/// models expect, so the remainder is carried over to the next push.
+#[derive(Debug, Clone)]
+pub struct Rechunker {
+    chunk_size: usize,
+    samples: Vec<f32>,
+    clock: ChunkClock,
+}
+
+impl Rechunker {
+    pub fn new(chunk_size: usize, sample_rate: u32) -> Self {
+        Self {
+            chunk_size: chunk_size.max(1),
+            samples: Vec::with_capacity(2 * chunk_size),
+            clock: ChunkClock::new(sample_rate),
+        }
+    }
+
+    /// Buffer `chunk`, returning every complete chunk now available
+    pub fn push(&mut self, chunk: AudioChunk) -> Vec<AudioChunk> {
+        self.clock.on_block(self.samples.len(), chunk.timestamp_ms);
+        self.samples.extend(chunk.samples);
+
+        let mut ready = Vec::new();
+        while self.samples.len() >= self.chunk_size {
+            ready.push(AudioChunk {
+                timestamp_ms: self.clock.take_chunk(self.chunk_size),
+                samples: self.samples.drain(..self.chunk_size).collect(),
+            });
+        }
+        ready
+    }
+
+    /// Take the buffered remainder as a final chunk, zero-padded to `chunk_size`
+    ///
+    /// Padding keeps the frame-size guarantee for the models downstream.
+    pub fn flush(&mut self) -> Option<AudioChunk> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let timestamp_ms = self.clock.take_chunk(self.chunk_size);
+        let mut samples = std::mem::take(&mut self.samples);
+        samples.resize(self.chunk_size, 0.0);
+        Some(AudioChunk { timestamp_ms, samples })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_clock_advances_by_chunk_duration() {
+        let mut clock = ChunkClock::new(16000);
+
+        clock.on_block(0, 1000.0);
+        assert_eq!(clock.take_chunk(1600), 1000.0);
+        // A block arriving while samples are still buffered doesn't move the start
+        clock.on_block(400, 1200.0);
+        assert_eq!(clock.take_chunk(1600), 1100.0);
+        assert_eq!(clock.take_chunk(1600), 1200.0);
+    }
+
+    #[test]
+    fn test_full_queue_drops_and_counts_chunks() {
+        let (audio_tx, mut audio_rx) = audio_channel(4);
+        for i in 0..100 {
+            let chunk = AudioChunk { timestamp_ms: i as f64 * 80.0, samples: vec![0.0; 1280] };
+            assert_eq!(audio_tx.send(chunk), Ok(()));
+        }
+        drop(audio_tx);
+
+        let mut received = Vec::new();
+        while let Some(chunk) = audio_rx.blocking_recv() {
+            received.push(chunk.timestamp_ms);
+        }
+        assert_eq!(received, vec![0.0, 80.0, 160.0, 240.0]);
+        assert_eq!(audio_rx.dropped(), 96);
+    }
+
+    #[test]
+    fn test_send_to_closed_queue_fails() {
+        let (audio_tx, audio_rx) = audio_channel(4);
+        drop(audio_rx);
+        let chunk = AudioChunk { timestamp_ms: 0.0, samples: Vec::new() };
+        assert_eq!(audio_tx.send(chunk.clone()), Err(QueueClosed));
+        assert_eq!(audio_tx.send_waiting(chunk), Err(QueueClosed));
+    }
+
+    #[test]
+    fn test_rechunker_emits_exact_chunks() {
+        let mut rechunker = Rechunker::new(1280, 16000);
+        let mut output = Vec::new();
+        let mut timestamp_ms = 1000.0;
+        for len in [341, 1000, 2047, 5, 1280, 3000, 17] {
+            output.extend(rechunker.push(AudioChunk { timestamp_ms, samples: vec![0.1; len] }));
+            timestamp_ms += len as f64 / 16.0;
+        }
+
+        assert!(output.iter().all(|chunk| chunk.samples.len() == 1280));
+        assert_eq!(output.len(), 7690 / 1280);
+        for (i, chunk) in output.iter().enumerate() {
+            assert_eq!(chunk.timestamp_ms, 1000.0 + i as f64 * 80.0);
+        }
+
+        let rest = rechunker.flush().unwrap();
+        assert_eq!(rest.samples.len(), 1280);
+        assert_eq!(rest.timestamp_ms, 1000.0 + output.len() as f64 * 80.0);
+        assert!(rest.samples[..7690 % 1280].iter().all(|&s| s == 0.1));
+        assert!(rest.samples[7690 % 1280..].iter().all(|&s| s == 0.0));
+        assert!(rechunker.flush().is_none());
+    }
+}
@@ -0,0 +1,159 @@
+//! ONNX session loading and named classifiers over the shared embeddings
+
+use ort::ep::{self, ExecutionProvider as _, ExecutionProviderDispatch};
+use ort::session::{builder::GraphOptimizationLevel, Session};
+use ort::value::Tensor;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::inference_cancel::InferenceCanceller;
+use super::inference_model::extract_f32;
+use super::wake_word::WakeWordError;
+
+/// Hardware backend ONNX Runtime runs the models on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExecutionProvider {
+    #[default]
+    Cpu,
+    /// NVIDIA GPUs
+    Cuda,
+    /// Apple Neural Engine / GPU on macOS
+    CoreMl,
+    /// DirectX 12 GPUs on Windows
+    DirectMl,
+}
+
+impl ExecutionProvider {
+    /// The provider to register, or `Cpu` if this runtime build lacks it
+    pub fn resolve(self) -> Self {
+        let available = match self {
+            Self::Cpu => return self,
+            Self::Cuda => ep::CUDA::default().is_available(),
+            Self::CoreMl => ep::CoreML::default().is_available(),
+            Self::DirectMl => ep::DirectML::default().is_available(),
+        };
+        match available {
+            Ok(true) => self,
+            Ok(false) => {
+                log::warn!("{:?} execution provider is not available, falling back to CPU", self);
+                Self::Cpu
+            }
+            Err(e) => {
+                log::warn!("Could not query {:?} execution provider ({}), falling back to CPU", self, e);
+                Self::Cpu
+            }
+        }
+    }
+
+    fn dispatch(self) -> Option<ExecutionProviderDispatch> {
+        match self {
+            Self::Cpu => None,
+            Self::Cuda => Some(ep::CUDA::default().build()),
+            Self::CoreMl => Some(ep::CoreML::default().build()),
+            Self::DirectMl => Some(ep::DirectML::default().build()),
+        }
+    }
+}
+
+/// Load an optimized ONNX session from `path` on the given execution provider
+pub fn load_session(path: &Path, provider: ExecutionProvider) -> Result<Session, WakeWordError> {
+    if !path.exists() {
+        return Err(WakeWordError::ModelNotFound(path.display().to_string()));
+    }
+
+    let mut builder = Session::builder()
+        .map_err(|e| WakeWordError::ModelLoadError(e.to_string()))?
+        .with_optimization_level(GraphOptimizationLevel::Level3)
+        .map_err(|e| WakeWordError::ModelLoadError(e.to_string()))?;
+    if let Some(dispatch) = provider.resolve().dispatch() {
+        builder = builder
+            .with_execution_providers([dispatch])
+            .map_err(|e| WakeWordError::ModelLoadError(e.to_string()))?;
+    }
+    builder
+        .commit_from_file(path)
+        .map_err(|e| {
+            log::error!("Failed to load model {:?}: {}", path, e);
+            WakeWordError::ModelLoadError(e.to_string())
+        })
+}
+
+struct Classifier {
+    name: String,
+    session: Session,
+}
+
+/// Single-output classifiers scored against the same embeddings
+#[derive(Default)]
+pub struct ClassifierSet {
+    classifiers: Vec<Classifier>,
+}
+
+impl ClassifierSet {
+    /// Load a classifier for each `(name, filename)` pair
+    pub fn load<'a>(
+        models_dir: &Path,
+        models: impl IntoIterator<Item = (&'a str, &'a str)>,
+        provider: ExecutionProvider,
+    ) -> Result<Self, WakeWordError> {
+        let mut classifiers = Vec::new();
+        for (name, model) in models {
+            let path = models_dir.join(model);
+            log::info!("Loading classifier '{}' from {:?}", name, path);
+            classifiers.push(Classifier {
+                name: name.to_string(),
+                session: load_session(&path, provider)?,
+            });
+        }
+        Ok(Self { classifiers })
+    }
+
+    /// Check if any classifiers are loaded
+    pub fn is_empty(&self) -> bool {
+        self.classifiers.is_empty()
+    }
+
+    /// Score every classifier against the embeddings
+    pub fn score(
+        &mut self,
+        embeddings: &[f32],
+        canceller: &InferenceCanceller,
+    ) -> Result<Vec<(String, f32)>, WakeWordError> {
+        let mut scores = Vec::with_capacity(self.classifiers.len());
+        for classifier in &mut self.classifiers {
+            let shape = [1_usize, embeddings.len()];
+            let input_tensor = Tensor::from_array((shape, embeddings.to_vec()))
+                .map_err(|e| WakeWordError::InferenceError(e.to_string()))?;
+
+            let outputs = classifier
+                .session
+                .run_with_options(ort::inputs![input_tensor], &canceller.run_options)
+                .map_err(|e| canceller.run_error(e))?;
+
+            let data = extract_f32(&outputs[0], &classifier.name)?;
+
+            scores.push((classifier.name.clone(), data.first().copied().unwrap_or(0.0)));
+        }
+        Ok(scores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_is_always_available() {
+        assert_eq!(ExecutionProvider::Cpu.resolve(), ExecutionProvider::Cpu);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_unavailable_provider_falls_back_to_cpu() {
+        assert_eq!(ExecutionProvider::DirectMl.resolve(), ExecutionProvider::Cpu);
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/models/melspectrogram.onnx");
+        assert!(load_session(&path, ExecutionProvider::DirectMl).is_ok());
+    }
+}
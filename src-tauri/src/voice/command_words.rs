@@ -0,0 +1,85 @@
+//! Command words - short phrases handled immediately without a listening turn
+//!
+//! Each command word has its own classifier model fed by the same embeddings
+//! as the primary wake word. A detection in `Idle` emits `voice-command`
+//! and leaves the state machine untouched, so "jarvis mute" mutes without
+//! opening a conversation.
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::controller_state::VoiceControllerState;
+use super::classifiers::{ClassifierSet, ExecutionProvider};
+use super::events::{emit_debug_log, emit_event, EventSink};
+use super::wake_word::WakeWordError;
+
+/// A command phrase and its classifier model
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandModel {
+    /// Command name reported in `voice-command`
+    pub command: String,
+    /// Classifier filename, relative to the models directory
+    pub model: String,
+}
+
+/// Load the classifier for each command model
+pub fn load_command_words(
+    models_dir: &Path,
+    models: &[CommandModel],
+    provider: ExecutionProvider,
+) -> Result<ClassifierSet, WakeWordError> {
+    ClassifierSet::load(models_dir, models.iter().map(|m| (m.command.as_str(), m.model.as_str())), provider)
+}
+
+/// Pick the highest-scoring command at or above the threshold
+pub fn detect_command(scores: &[(String, f32)], threshold: f32) -> Option<String> {
+    scores
+        .iter()
+        .filter(|(_, score)| *score >= threshold)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(command, _)| command.clone())
+}
+
+/// Report a detected command word without changing the voice state
+pub fn handle_command_word(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    command: &str,
+) {
+    emit_debug_log(sink, "info", &format!("Command word: {}", command));
+    log::info!("Command word detected: {}", command);
+    emit_event(sink, state, "voice-command", serde_json::json!({ "command": command }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::events::CollectingSink;
+    use crate::voice::state_machine::VoiceState;
+
+    #[test]
+    fn test_detect_command_picks_best_above_threshold() {
+        let scores = vec![
+            ("mute".to_string(), 0.7),
+            ("louder".to_string(), 0.9),
+            ("quieter".to_string(), 0.2),
+        ];
+        assert_eq!(detect_command(&scores, 0.5).as_deref(), Some("louder"));
+        assert_eq!(detect_command(&scores, 0.95), None);
+    }
+
+    #[test]
+    fn test_command_word_emits_event_and_stays_idle() {
+        let collected = Arc::new(CollectingSink::default());
+        let sink: EventSink = Some(collected.clone());
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+
+        handle_command_word(&sink, &state, "mute");
+
+        assert_eq!(state.read().state_machine.state(), VoiceState::Idle);
+        assert_eq!(collected.payloads("voice-command"), vec![serde_json::json!({ "command": "mute" })]);
+        assert!(collected.payloads("voice-state-changed").is_empty());
+    }
+}
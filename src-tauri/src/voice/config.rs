@@ -1,12 +1,40 @@
 //! Voice system configuration
 
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::classifiers::ExecutionProvider;
+use super::command_words::CommandModel;
+use super::downmix::ChannelMode;
+use super::filters::FilterSpec;
+use super::noise_profile::NoiseProfile;
+use super::schedule::ScheduleWindow;
+use super::vad::VadBackend;
+use super::wake_word_models::WakeWordModel;
+
+mod derived;
+
+/// Highest effective threshold, so low sensitivities leave strong detections reachable
+pub const MAX_EFFECTIVE_THRESHOLD: f32 = 0.99;
+
+/// Lowest effective threshold, so high sensitivities don't trigger on noise
+pub const MIN_EFFECTIVE_THRESHOLD: f32 = 0.01;
+
+/// Upper bound on the pre-roll so a long setting can't prepend seconds of silence
+pub const MAX_PREROLL_MS: u32 = 1000;
+
+/// Highest VAD smoothing factor; at 1.0 the smoothed level would never change
+pub const MAX_VAD_SMOOTHING_FACTOR: f32 = 0.99;
+
 /// Configuration for the voice system
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VoiceConfig {
     /// Sample rate for audio processing (OpenWakeWord expects 16kHz)
     pub sample_rate: u32,
-    /// Number of samples per audio chunk (80ms at 16kHz = 1280 samples)
+    /// Samples per chunk delivered from capture to detection (80ms at 16kHz = 1280 samples)
     pub chunk_size: usize,
+    /// Chunks that may wait for the processing loop before new ones are dropped
+    pub audio_queue_capacity: usize,
     /// Number of mel frames to accumulate before inference
     pub mel_frame_count: usize,
     /// Wake word detection threshold (0.0 - 1.0)
@@ -15,8 +43,186 @@ pub struct VoiceConfig {
     pub sensitivity: f32,
     /// Silence threshold for VAD (RMS level)
     pub silence_threshold: f32,
-    /// Frames of silence before speech end detection
-    pub silence_frames_threshold: usize,
+    /// Level the energy VAD must reach to declare speech (`None` = `silence_threshold`)
+    pub speech_onset_threshold: Option<f32>,
+    /// Level the energy VAD must fall below to count silence (`None` = `silence_threshold`)
+    pub speech_offset_threshold: Option<f32>,
+    /// Weight of the previous level in the energy VAD's smoothed RMS, in [0, 1)
+    ///
+    /// Higher values mean more smoothing: steadier decisions but a slower
+    /// response to speech starting and stopping. 0 uses each chunk's raw level.
+    pub vad_smoothing_factor: f32,
+    /// Silence after speech before speech end is detected, in milliseconds
+    pub silence_timeout_ms: u64,
+    /// Speech frames an utterance needs to be transcribed; shorter ones are discarded
+    pub min_speech_frames: usize,
+    /// Model filenames inside the models directory
+    pub model_files: ModelFiles,
+    /// Maximum fraction of clipped samples for a capture to be considered usable
+    pub capture_max_clipping_ratio: f32,
+    /// Minimum peak level (dBFS) for a capture to be considered usable
+    pub capture_min_peak_dbfs: f32,
+    /// Ordered filters applied to each incoming chunk before detection
+    pub filter_chain: Vec<FilterSpec>,
+    /// Remove DC offset and low-frequency rumble ahead of the filter chain
+    pub highpass_enabled: bool,
+    /// Cutoff of the high-pass filter in Hz
+    pub highpass_cutoff_hz: f32,
+    /// New mel frames required between wake word inference runs (1 = every frame)
+    pub inference_stride: usize,
+    /// Reject detections caused by a single isolated high score (claps, clicks)
+    pub reject_impulsive: bool,
+    /// Recent wake word scores averaged before comparing to the threshold (1 = raw score)
+    pub wake_word_smoothing_frames: usize,
+    /// Raise the threshold when background wake word scores drift upward (see `voice::adaptive_threshold`)
+    pub adaptive_threshold: bool,
+    /// Distance the adapted threshold keeps above the background score level
+    pub adaptive_threshold_margin: f32,
+    /// Highest threshold adaptation may raise to
+    pub adaptive_threshold_ceiling: f32,
+    /// Active user profile, if any (see `voice::profiles`)
+    pub profile: Option<String>,
+    /// Command word classifiers handled without a listening turn
+    pub command_models: Vec<CommandModel>,
+    /// Rebuild the capture stream when the device changes its default format
+    pub auto_reconfigure_capture: bool,
+    /// Emit `voice-accessibility-status` announcements for assistive technology
+    pub accessibility_events: bool,
+    /// Phrase name for each output of a multi-label wake word classifier (empty = single output)
+    pub wake_word_labels: Vec<String>,
+    /// Audio before the wake word detection prepended to the capture (capped at `MAX_PREROLL_MS`)
+    pub preroll_ms: u32,
+    /// Send the pre-roll (including the wake word itself) to STT with the utterance
+    pub include_preroll_in_stt: bool,
+    /// Audio right before the detection kept for STT even when the pre-roll is trimmed,
+    /// so a first word spoken over the end of the wake word isn't clipped (capped at `MAX_PREROLL_MS`)
+    pub wake_lookback_ms: u32,
+    /// Cut leading and trailing silence from the capture before it goes to STT
+    pub trim_silence: bool,
+    /// Silence kept after the last loud frame when trimming
+    pub trailing_pad_ms: u32,
+    /// How multi-channel capture is reduced to mono: averaged, or a single channel
+    pub channel_mode: ChannelMode,
+    /// Maximum time in Listening before giving up and returning to Idle, except while push-to-talk is held
+    pub listening_timeout_ms: u64,
+    /// Time after entering Listening during which a pause can't end the capture (0 = off)
+    pub listening_grace_ms: u64,
+    /// Maximum time waiting on STT (Transcribing) or the AI (Processing)
+    pub backend_timeout_ms: u64,
+    /// How long the Error state is held before clearing back to Idle on its own
+    pub error_hold_ms: u64,
+    /// Listen this long for a follow-up after speaking, without the wake word (0 = disabled)
+    pub follow_up_window_ms: u64,
+    /// Return to Idle instead of processing a transcription that is empty or only whitespace
+    pub skip_empty_transcriptions: bool,
+    /// Additional wake words scored alongside the primary classifier
+    pub wake_word_models: Vec<WakeWordModel>,
+    /// Ignore further wake word detections for this long after one fires
+    pub wake_word_cooldown_ms: u64,
+    /// Run the capture and processing threads at elevated priority where permitted
+    pub high_priority_audio: bool,
+    /// Speech detector used to find the end of an utterance
+    pub vad_backend: VadBackend,
+    /// Hardware backend for ONNX inference, falling back to CPU when unavailable
+    pub execution_provider: ExecutionProvider,
+    /// How long the device lists must stay unchanged before `voice-devices-changed` fires
+    pub device_change_debounce_ms: u64,
+    /// RMS level above which input during TTS counts as the user speaking
+    pub barge_in_threshold: f32,
+    /// Sustained speech required before interrupting TTS, to ignore echo
+    pub barge_in_min_speech_ms: u32,
+    /// Drop microphone input while TTS plays so speaker output can't re-trigger
+    /// the wake word (this also disables barge-in)
+    pub mute_mic_during_speaking: bool,
+    /// Windows of local time during which wake word scanning runs (`None` = always)
+    pub active_schedule: Option<Vec<ScheduleWindow>>,
+    /// Subtract the recorded noise profile from incoming audio
+    pub noise_suppression: bool,
+    /// Ambient noise spectrum recorded with `record_noise_profile`
+    pub noise_profile: Option<NoiseProfile>,
+    /// Apply automatic gain control before wake word detection and VAD
+    pub agc_enabled: bool,
+    /// RMS level the automatic gain aims for
+    pub agc_target_rms: f32,
+    /// Upper bound on the automatic gain
+    pub agc_max_gain: f32,
+    /// RMS below which the gain stops adapting, so silence isn't amplified
+    pub agc_noise_gate: f32,
+    /// Also apply the automatic gain to the audio sent to STT
+    pub agc_apply_to_capture: bool,
+    /// Minimum time between `voice-audio-level` events (50 = 20Hz)
+    pub level_emit_interval_ms: u64,
+    /// Attempts to reopen the input device after its stream fails
+    pub device_reconnect_attempts: u32,
+    /// Delay before the first reconnect attempt, doubling on each further attempt
+    pub device_reconnect_backoff_ms: u64,
+    /// Restart the whole voice system when the watchdog detects a failure
+    pub auto_restart: bool,
+    /// Restarts attempted before giving up with `voice-auto-restart-failed`
+    pub auto_restart_max_attempts: u32,
+    /// Delay before the first restart, doubling on each further attempt
+    pub auto_restart_backoff_ms: u64,
+    /// Time without processed audio after which the system counts as stalled
+    pub watchdog_stall_ms: u64,
+    /// Consecutive wake word inference failures that count as degraded
+    pub watchdog_max_inference_failures: u32,
+    /// Dropped audio chunks per run that count as an overrun failure (0 disables)
+    pub watchdog_max_dropped_chunks: u64,
+    /// Write each captured utterance to a WAV file for debugging
+    pub record_utterances: bool,
+    /// Directory that receives recorded utterances
+    pub utterance_dir: PathBuf,
+    /// WebSocket STT service that captures are streamed to (`None` = the frontend transcribes)
+    pub stt_endpoint: Option<String>,
+    /// Recent events kept for `get_event_history` (0 = no history)
+    pub event_history_capacity: usize,
+}
+
+/// Filenames of the OpenWakeWord models, relative to the models directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelFiles {
+    /// Melspectrogram feature extractor
+    pub melspec: String,
+    /// Shared speech embedding model
+    pub embedding: String,
+    /// Wake word classifier
+    pub wakeword: String,
+}
+
+impl Default for ModelFiles {
+    fn default() -> Self {
+        Self {
+            melspec: "melspectrogram.onnx".to_string(),
+            embedding: "embedding_model.onnx".to_string(),
+            wakeword: "hey_jarvis.onnx".to_string(),
+        }
+    }
+}
+
+impl ModelFiles {
+    /// Path to the melspectrogram model
+    pub fn melspec_path(&self, models_dir: &Path) -> PathBuf {
+        models_dir.join(&self.melspec)
+    }
+
+    /// Path to the embedding model
+    pub fn embedding_path(&self, models_dir: &Path) -> PathBuf {
+        models_dir.join(&self.embedding)
+    }
+
+    /// Path to the wake word classifier
+    pub fn wakeword_path(&self, models_dir: &Path) -> PathBuf {
+        models_dir.join(&self.wakeword)
+    }
+
+    /// All model paths, in pipeline order
+    pub fn paths(&self, models_dir: &Path) -> [PathBuf; 3] {
+        [
+            self.melspec_path(models_dir),
+            self.embedding_path(models_dir),
+            self.wakeword_path(models_dir),
+        ]
+    }
 }
 
 impl Default for VoiceConfig {
@@ -24,18 +230,75 @@ impl Default for VoiceConfig {
         Self {
             sample_rate: 16000,
             chunk_size: 1280,           // 80ms at 16kHz
+            audio_queue_capacity: 16,   // ~1.3s of backlog
             mel_frame_count: 76,        // OpenWakeWord expectation
             wake_word_threshold: 0.5,
             sensitivity: 1.0,
             silence_threshold: 0.01,
-            silence_frames_threshold: 16, // ~1.3 seconds at 80ms chunks
+            speech_onset_threshold: None,
+            speech_offset_threshold: None,
+            vad_smoothing_factor: 0.7,
+            silence_timeout_ms: 1280,
+            min_speech_frames: 0,
+            model_files: ModelFiles::default(),
+            capture_max_clipping_ratio: 0.01,
+            capture_min_peak_dbfs: -40.0,
+            filter_chain: Vec::new(),
+            highpass_enabled: false,
+            highpass_cutoff_hz: 80.0,
+            inference_stride: 1,
+            reject_impulsive: false,
+            wake_word_smoothing_frames: 1,
+            adaptive_threshold: false,
+            adaptive_threshold_margin: 0.3,
+            adaptive_threshold_ceiling: 0.8,
+            profile: None,
+            command_models: Vec::new(),
+            auto_reconfigure_capture: true,
+            accessibility_events: true,
+            wake_word_labels: Vec::new(),
+            preroll_ms: 300,
+            include_preroll_in_stt: true,
+            wake_lookback_ms: 0,
+            trim_silence: true,
+            trailing_pad_ms: 200,
+            channel_mode: ChannelMode::Mono,
+            listening_timeout_ms: 10_000,
+            listening_grace_ms: 0,
+            backend_timeout_ms: 30_000,
+            error_hold_ms: 5_000,
+            follow_up_window_ms: 0,
+            skip_empty_transcriptions: true,
+            wake_word_models: Vec::new(),
+            wake_word_cooldown_ms: 1500,
+            high_priority_audio: false,
+            vad_backend: VadBackend::Energy,
+            execution_provider: ExecutionProvider::Cpu,
+            device_change_debounce_ms: 1000,
+            barge_in_threshold: 0.05,
+            barge_in_min_speech_ms: 300,
+            mute_mic_during_speaking: false,
+            active_schedule: None,
+            noise_suppression: false,
+            noise_profile: None,
+            agc_enabled: false,
+            agc_target_rms: 0.05,
+            agc_max_gain: 10.0,
+            agc_noise_gate: 0.002,
+            agc_apply_to_capture: false,
+            level_emit_interval_ms: 50,
+            device_reconnect_attempts: 5,
+            device_reconnect_backoff_ms: 500,
+            auto_restart: false,
+            auto_restart_max_attempts: 5,
+            auto_restart_backoff_ms: 1000,
+            watchdog_stall_ms: 5000,
+            watchdog_max_inference_failures: 20,
+            watchdog_max_dropped_chunks: 500,
+            record_utterances: false,
+            utterance_dir: std::env::temp_dir().join("jarvis-utterances"),
+            stt_endpoint: None,
+            event_history_capacity: 200,
         }
     }
 }
-
-impl VoiceConfig {
-    /// Calculate effective threshold based on sensitivity
-    pub fn effective_threshold(&self) -> f32 {
-        self.wake_word_threshold / self.sensitivity
-    }
-}
@@ -0,0 +1,202 @@
+//! Values derived from the configured settings
+
+use std::time::Duration;
+
+use crate::voice::states::VoiceState;
+
+use super::{VoiceConfig, MAX_EFFECTIVE_THRESHOLD, MAX_PREROLL_MS, MIN_EFFECTIVE_THRESHOLD};
+
+impl VoiceConfig {
+    /// Calculate effective threshold based on sensitivity
+    ///
+    /// The threshold is divided by the sensitivity, so higher sensitivity means
+    /// a lower threshold and easier triggering: 0.5 at sensitivity 2.0 becomes
+    /// 0.25. A score at or above the result counts as a detection.
+    pub fn effective_threshold(&self) -> f32 {
+        self.scaled_threshold(self.wake_word_threshold)
+    }
+
+    /// Apply sensitivity scaling to a base threshold, clamped to
+    /// [`MIN_EFFECTIVE_THRESHOLD`]..=[`MAX_EFFECTIVE_THRESHOLD`]
+    pub fn scaled_threshold(&self, threshold: f32) -> f32 {
+        (threshold / self.sensitivity).clamp(MIN_EFFECTIVE_THRESHOLD, MAX_EFFECTIVE_THRESHOLD)
+    }
+
+    /// How long a state may last before timing out back to Idle
+    ///
+    /// `None` for states that end on their own (Idle, Speaking).
+    pub fn timeout_for(&self, state: VoiceState) -> Option<Duration> {
+        match state {
+            VoiceState::Listening => Some(Duration::from_millis(self.listening_timeout_ms)),
+            VoiceState::Transcribing | VoiceState::Processing => {
+                Some(Duration::from_millis(self.backend_timeout_ms))
+            }
+            VoiceState::Error => Some(Duration::from_millis(self.error_hold_ms)),
+            VoiceState::Idle | VoiceState::Speaking => None,
+        }
+    }
+
+    /// Energy VAD `(onset, offset)` thresholds, defaulting to `silence_threshold`
+    pub fn speech_thresholds(&self) -> (f32, f32) {
+        (
+            self.speech_onset_threshold.unwrap_or(self.silence_threshold),
+            self.speech_offset_threshold.unwrap_or(self.silence_threshold),
+        )
+    }
+
+    /// Whether input chunks are discarded unprocessed in `state`
+    pub fn mutes_input_in(&self, state: VoiceState) -> bool {
+        self.mute_mic_during_speaking && state == VoiceState::Speaking
+    }
+
+    /// Audio kept before a detection: the pre-roll or the lookback, whichever is longer
+    pub fn preroll_window_ms(&self) -> u32 {
+        self.preroll_ms.max(self.wake_lookback_ms).min(MAX_PREROLL_MS)
+    }
+
+    /// Number of pre-roll samples to keep, enough to cover the lookback too
+    pub fn preroll_samples(&self) -> usize {
+        self.ms_to_samples(self.preroll_window_ms())
+    }
+
+    /// Number of pre-roll samples kept for STT when the rest of the pre-roll is trimmed
+    pub fn lookback_samples(&self) -> usize {
+        self.ms_to_samples(self.wake_lookback_ms.min(MAX_PREROLL_MS))
+    }
+
+    /// Number of seeded pre-roll samples that go to STT with the utterance
+    ///
+    /// `preroll_ms` of them when the pre-roll is included, even if the
+    /// lookback made the seeded window longer; otherwise just the lookback.
+    pub fn stt_preroll_samples(&self) -> usize {
+        if self.include_preroll_in_stt {
+            self.ms_to_samples(self.preroll_ms.min(MAX_PREROLL_MS))
+        } else {
+            self.lookback_samples()
+        }
+    }
+
+    fn ms_to_samples(&self, ms: u32) -> usize {
+        (ms as usize * self.sample_rate as usize) / 1000
+    }
+
+    /// Chunks of silence covering `silence_timeout_ms` at the configured chunk size
+    ///
+    /// Rounded up, and at least one chunk.
+    pub fn silence_frames_threshold(&self) -> usize {
+        let samples = self.silence_timeout_ms as usize * self.sample_rate as usize / 1000;
+        samples.div_ceil(self.chunk_size.max(1)).max(1)
+    }
+
+    /// Duration of `chunks` chunks in milliseconds
+    pub fn chunks_to_ms(&self, chunks: usize) -> u64 {
+        (chunks * self.chunk_size * 1000 / self.sample_rate.max(1) as usize) as u64
+    }
+
+    /// Trailing silence kept when trimming, in samples
+    pub fn trailing_pad_samples(&self) -> usize {
+        (self.trailing_pad_ms as usize * self.sample_rate as usize) / 1000
+    }
+
+    /// Check if the threshold/sensitivity combination leaves detection possible
+    ///
+    /// False when the unclamped threshold is at or above the clamp, i.e. only
+    /// a near-perfect score could ever trigger.
+    pub fn is_detection_possible(&self) -> bool {
+        self.wake_word_threshold / self.sensitivity < MAX_EFFECTIVE_THRESHOLD
+    }
+
+    /// Warnings about settings that are valid but unlikely to work
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.is_detection_possible() {
+            warnings.push(format!(
+                "wake word threshold {} at sensitivity {} makes detection practically impossible",
+                self.wake_word_threshold, self.sensitivity
+            ));
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_effective_threshold_is_clamped() {
+        let config = VoiceConfig {
+            wake_word_threshold: 0.5,
+            sensitivity: 0.1,
+            ..Default::default()
+        };
+        assert_eq!(config.effective_threshold(), MAX_EFFECTIVE_THRESHOLD);
+        assert!(!config.is_detection_possible());
+    }
+
+    #[test]
+    fn test_effective_threshold_at_extreme_sensitivities() {
+        let threshold_at = |sensitivity: f32| {
+            VoiceConfig {
+                wake_word_threshold: 0.5,
+                sensitivity,
+                ..Default::default()
+            }
+            .effective_threshold()
+        };
+
+        assert_eq!(threshold_at(0.1), MAX_EFFECTIVE_THRESHOLD);
+        assert_eq!(threshold_at(1000.0), MIN_EFFECTIVE_THRESHOLD);
+        assert!(threshold_at(2.0) < threshold_at(1.0), "higher sensitivity triggers more easily");
+    }
+
+    #[test]
+    fn test_warns_when_detection_impossible() {
+        let config = VoiceConfig {
+            sensitivity: 0.4,
+            ..Default::default()
+        };
+        assert_eq!(config.warnings().len(), 1);
+        assert!(VoiceConfig::default().warnings().is_empty());
+        assert!(VoiceConfig::default().is_detection_possible());
+    }
+
+    #[test]
+    fn test_preroll_covers_lookback() {
+        let config = VoiceConfig {
+            preroll_ms: 100,
+            wake_lookback_ms: 200,
+            ..Default::default()
+        };
+        assert_eq!(config.preroll_samples(), 3200);
+        assert_eq!(config.lookback_samples(), 3200);
+        assert_eq!(config.stt_preroll_samples(), 1600);
+        assert_eq!(VoiceConfig::default().lookback_samples(), 0);
+    }
+
+    #[test]
+    fn test_silence_frames_follow_chunk_size() {
+        let frames_at = |chunk_size| {
+            let config = VoiceConfig { chunk_size, ..Default::default() };
+            (config.silence_frames_threshold(), config.chunks_to_ms(config.silence_frames_threshold()))
+        };
+
+        // 1280ms of silence is 16 chunks of 80ms or 40 chunks of 32ms
+        assert_eq!(frames_at(1280), (16, 1280));
+        assert_eq!(frames_at(512), (40, 1280));
+        // Rounded up so the timeout is never shortened
+        assert_eq!(VoiceConfig { silence_timeout_ms: 100, ..Default::default() }.silence_frames_threshold(), 2);
+    }
+
+    #[test]
+    fn test_mute_mic_during_speaking() {
+        let muted = VoiceConfig {
+            mute_mic_during_speaking: true,
+            ..Default::default()
+        };
+        assert!(muted.mutes_input_in(VoiceState::Speaking));
+        assert!(!muted.mutes_input_in(VoiceState::Idle));
+        assert!(!muted.mutes_input_in(VoiceState::Listening));
+        assert!(!VoiceConfig::default().mutes_input_in(VoiceState::Speaking));
+    }
+}
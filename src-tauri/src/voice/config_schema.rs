@@ -0,0 +1,325 @@
+//! Schema of `VoiceConfig` fields for generating settings UIs
+//!
+//! Centralizes each field's type, default, valid range, and whether changing
+//! it requires restarting the voice system. The ranges are enforced in
+//! [`config_validation`](super::config_validation).
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::config::{VoiceConfig, MAX_EFFECTIVE_THRESHOLD, MAX_PREROLL_MS, MAX_VAD_SMOOTHING_FACTOR};
+use super::score_history::SCORE_HISTORY_CAPACITY;
+
+/// Value type of a config field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigFieldKind {
+    Integer,
+    Float,
+    Bool,
+    String,
+    List,
+    Object,
+}
+
+/// Metadata describing one `VoiceConfig` field
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigFieldMeta {
+    pub name: &'static str,
+    pub kind: ConfigFieldKind,
+    pub default: Value,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub requires_restart: bool,
+    pub description: &'static str,
+}
+
+fn field(
+    name: &'static str,
+    kind: ConfigFieldKind,
+    default: Value,
+    range: (Option<f64>, Option<f64>),
+    requires_restart: bool,
+    description: &'static str,
+) -> ConfigFieldMeta {
+    ConfigFieldMeta {
+        name,
+        kind,
+        default,
+        min: range.0,
+        max: range.1,
+        requires_restart,
+        description,
+    }
+}
+
+impl VoiceConfig {
+    /// Describe every config field, in declaration order
+    pub fn schema() -> Vec<ConfigFieldMeta> {
+        Self::default().describe()
+    }
+
+    /// Fields flagged `requires_restart` whose value differs in `other`
+    pub fn restart_required_changes(&self, other: &VoiceConfig) -> Vec<&'static str> {
+        self.describe()
+            .into_iter()
+            .zip(other.describe())
+            .filter(|(current, changed)| current.requires_restart && current.default != changed.default)
+            .map(|(current, _)| current.name)
+            .collect()
+    }
+
+    /// Field metadata with `default` holding this config's values
+    pub(super) fn describe(&self) -> Vec<ConfigFieldMeta> {
+        use ConfigFieldKind::*;
+
+        // Exhaustive destructuring: adding a field fails to compile until it is described here
+        let VoiceConfig {
+            sample_rate,
+            chunk_size,
+            audio_queue_capacity,
+            mel_frame_count,
+            wake_word_threshold,
+            sensitivity,
+            silence_threshold,
+            speech_onset_threshold,
+            speech_offset_threshold,
+            vad_smoothing_factor,
+            silence_timeout_ms,
+            min_speech_frames,
+            model_files,
+            capture_max_clipping_ratio,
+            capture_min_peak_dbfs,
+            filter_chain,
+            highpass_enabled,
+            highpass_cutoff_hz,
+            inference_stride,
+            reject_impulsive,
+            wake_word_smoothing_frames,
+            adaptive_threshold,
+            adaptive_threshold_margin,
+            adaptive_threshold_ceiling,
+            profile,
+            command_models,
+            auto_reconfigure_capture,
+            accessibility_events,
+            wake_word_labels,
+            preroll_ms,
+            include_preroll_in_stt,
+            wake_lookback_ms,
+            trim_silence,
+            trailing_pad_ms,
+            channel_mode,
+            listening_timeout_ms,
+            listening_grace_ms,
+            backend_timeout_ms,
+            error_hold_ms,
+            follow_up_window_ms,
+            skip_empty_transcriptions,
+            wake_word_models,
+            wake_word_cooldown_ms,
+            high_priority_audio,
+            vad_backend,
+            execution_provider,
+            device_change_debounce_ms,
+            barge_in_threshold,
+            barge_in_min_speech_ms,
+            mute_mic_during_speaking,
+            active_schedule,
+            noise_suppression,
+            noise_profile,
+            agc_enabled,
+            agc_target_rms,
+            agc_max_gain,
+            agc_noise_gate,
+            agc_apply_to_capture,
+            level_emit_interval_ms,
+            device_reconnect_attempts,
+            device_reconnect_backoff_ms,
+            auto_restart,
+            auto_restart_max_attempts,
+            auto_restart_backoff_ms,
+            watchdog_stall_ms,
+            watchdog_max_inference_failures,
+            watchdog_max_dropped_chunks,
+            record_utterances,
+            utterance_dir,
+            stt_endpoint,
+            event_history_capacity,
+        } = self;
+
+        vec![
+            field("sample_rate", Integer, json!(sample_rate), (Some(8000.0), Some(48000.0)), true,
+                "Sample rate for audio processing (OpenWakeWord expects 16kHz)"),
+            field("chunk_size", Integer, json!(chunk_size), (Some(1.0), None), true,
+                "Samples per chunk delivered from capture to detection (1280 = 80ms at 16kHz)"),
+            field("audio_queue_capacity", Integer, json!(audio_queue_capacity), (Some(1.0), None), true,
+                "Chunks queued for processing before new audio is dropped to stay near real time"),
+            field("mel_frame_count", Integer, json!(mel_frame_count), (Some(1.0), None), true,
+                "Number of mel frames to accumulate before inference"),
+            field("wake_word_threshold", Float, json!(wake_word_threshold),
+                (Some(0.0), Some(MAX_EFFECTIVE_THRESHOLD as f64)), true,
+                "Wake word detection threshold"),
+            field("sensitivity", Float, json!(sensitivity), (Some(0.1), Some(3.0)), false,
+                "Sensitivity multiplier; the effective threshold is threshold / sensitivity"),
+            field("silence_threshold", Float, json!(silence_threshold), (Some(0.0), Some(1.0)), true,
+                "Silence threshold for VAD (RMS level)"),
+            field("speech_onset_threshold", Float, json!(speech_onset_threshold), (Some(0.0), Some(1.0)), true,
+                "RMS level that starts speech in the energy VAD (unset = silence_threshold)"),
+            field("speech_offset_threshold", Float, json!(speech_offset_threshold), (Some(0.0), Some(1.0)), true,
+                "RMS level below which the energy VAD counts silence (unset = silence_threshold)"),
+            field("vad_smoothing_factor", Float, json!(vad_smoothing_factor),
+                (Some(0.0), Some(MAX_VAD_SMOOTHING_FACTOR as f64)), true,
+                "Weight of the previous level in the energy VAD; higher = smoother but slower to react"),
+            field("silence_timeout_ms", Integer, json!(silence_timeout_ms), (Some(1.0), None), true,
+                "Silence after speech before speech end is detected, in milliseconds"),
+            field("min_speech_frames", Integer, json!(min_speech_frames), (Some(0.0), None), false,
+                "Chunks of speech an utterance needs; shorter noises are discarded (0 = off)"),
+            field("model_files", Object, json!(model_files), (None, None), false,
+                "Model filenames inside the models directory"),
+            field("capture_max_clipping_ratio", Float, json!(capture_max_clipping_ratio),
+                (Some(0.0), Some(1.0)), true,
+                "Maximum fraction of clipped samples for a usable capture"),
+            field("capture_min_peak_dbfs", Float, json!(capture_min_peak_dbfs), (Some(-100.0), Some(0.0)), true,
+                "Minimum peak level (dBFS) for a usable capture"),
+            field("filter_chain", List, json!(filter_chain), (None, None), true,
+                "Ordered filters applied to each incoming chunk"),
+            field("highpass_enabled", Bool, json!(highpass_enabled), (None, None), true,
+                "Remove DC offset and low-frequency rumble before the filter chain"),
+            field("highpass_cutoff_hz", Float, json!(highpass_cutoff_hz), (Some(1.0), Some(1000.0)), true,
+                "Cutoff of the high-pass filter in Hz"),
+            field("inference_stride", Integer, json!(inference_stride), (Some(1.0), None), false,
+                "New mel frames required between wake word inference runs"),
+            field("reject_impulsive", Bool, json!(reject_impulsive), (None, None), true,
+                "Reject detections caused by a single isolated high score"),
+            field("wake_word_smoothing_frames", Integer, json!(wake_word_smoothing_frames),
+                (Some(1.0), Some(SCORE_HISTORY_CAPACITY as f64)), true,
+                "Recent wake word scores averaged before comparing to the threshold (1 = off)"),
+            field("adaptive_threshold", Bool, json!(adaptive_threshold), (None, None), true,
+                "Raise the threshold when background wake word scores drift upward"),
+            field("adaptive_threshold_margin", Float, json!(adaptive_threshold_margin), (Some(0.0), Some(1.0)), true,
+                "Distance the adapted threshold keeps above the background score level"),
+            field("adaptive_threshold_ceiling", Float, json!(adaptive_threshold_ceiling),
+                (Some(0.0), Some(MAX_EFFECTIVE_THRESHOLD as f64)), true,
+                "Highest threshold adaptation may raise to"),
+            field("profile", String, json!(profile), (None, None), false,
+                "Active user profile"),
+            field("command_models", List, json!(command_models), (None, None), true,
+                "Command word classifiers handled without a listening turn"),
+            field("auto_reconfigure_capture", Bool, json!(auto_reconfigure_capture), (None, None), true,
+                "Rebuild the capture stream when the device changes its default format"),
+            field("accessibility_events", Bool, json!(accessibility_events), (None, None), false,
+                "Emit plain-language listening status announcements for assistive technology"),
+            field("wake_word_labels", List, json!(wake_word_labels), (None, None), true,
+                "Phrase name for each output of a multi-label wake word classifier"),
+            field("preroll_ms", Integer, json!(preroll_ms), (Some(0.0), Some(MAX_PREROLL_MS as f64)), true,
+                "Audio from before the wake word detection prepended to the capture"),
+            field("include_preroll_in_stt", Bool, json!(include_preroll_in_stt), (None, None), false,
+                "Send the pre-roll, including the wake word, to STT (off trims it from the capture)"),
+            field("wake_lookback_ms", Integer, json!(wake_lookback_ms), (Some(0.0), Some(MAX_PREROLL_MS as f64)), true,
+                "Audio right before the detection kept for STT even when the pre-roll is trimmed"),
+            field("trim_silence", Bool, json!(trim_silence), (None, None), false,
+                "Cut leading and trailing silence from the capture before sending it to STT"),
+            field("trailing_pad_ms", Integer, json!(trailing_pad_ms), (Some(0.0), None), false,
+                "Silence kept after the end of speech when trimming"),
+            field("channel_mode", String, json!(channel_mode), (None, None), true,
+                "How multi-channel capture is reduced to mono: mono, mid, left, right, or channel:N for a zero-based index"),
+            field("listening_timeout_ms", Integer, json!(listening_timeout_ms), (Some(1000.0), None), false,
+                "Maximum time listening before returning to idle, except while push-to-talk is held"),
+            field("listening_grace_ms", Integer, json!(listening_grace_ms), (Some(0.0), None), false,
+                "Time after the wake word during which a pause does not end the capture (0 = off)"),
+            field("backend_timeout_ms", Integer, json!(backend_timeout_ms), (Some(1000.0), None), false,
+                "Maximum time waiting on transcription or the AI response"),
+            field("error_hold_ms", Integer, json!(error_hold_ms), (Some(0.0), None), false,
+                "How long an error is shown before returning to idle"),
+            field("follow_up_window_ms", Integer, json!(follow_up_window_ms), (Some(0.0), None), true,
+                "Listen this long for a follow-up after speaking, without the wake word (0 = off)"),
+            field("skip_empty_transcriptions", Bool, json!(skip_empty_transcriptions), (None, None), true,
+                "Go back to idle when nothing intelligible was transcribed"),
+            field("wake_word_models", List, json!(wake_word_models), (None, None), true,
+                "Additional wake word classifiers, each with an optional threshold"),
+            field("wake_word_cooldown_ms", Integer, json!(wake_word_cooldown_ms), (Some(0.0), None), false,
+                "Time after a detection during which further detections are ignored"),
+            field("high_priority_audio", Bool, json!(high_priority_audio), (None, None), true,
+                "Run the audio threads at elevated scheduling priority where permitted"),
+            field("vad_backend", String, json!(vad_backend), (None, None), true,
+                "Speech detector for end of utterance (energy, silero); silero needs silero_vad.onnx"),
+            field("execution_provider", String, json!(execution_provider), (None, None), true,
+                "ONNX Runtime backend (cpu, cuda, coreMl, directMl); falls back to cpu if unavailable"),
+            field("device_change_debounce_ms", Integer, json!(device_change_debounce_ms), (Some(0.0), None), true,
+                "How long device lists must be stable before a change is reported"),
+            field("barge_in_threshold", Float, json!(barge_in_threshold), (Some(0.0), Some(1.0)), false,
+                "RMS level above which input during TTS counts as the user speaking"),
+            field("barge_in_min_speech_ms", Integer, json!(barge_in_min_speech_ms), (Some(0.0), None), false,
+                "Sustained speech required before interrupting TTS"),
+            field("mute_mic_during_speaking", Bool, json!(mute_mic_during_speaking), (None, None), false,
+                "Ignore the microphone while TTS plays, for speakers without echo cancellation (disables barge-in)"),
+            field("active_schedule", List, json!(active_schedule), (None, None), true,
+                "Local time windows (start, end, days) during which wake word scanning runs"),
+            field("noise_suppression", Bool, json!(noise_suppression), (None, None), false,
+                "Subtract the recorded ambient noise spectrum from incoming audio"),
+            field("noise_profile", Object, json!(noise_profile), (None, None), false,
+                "Ambient noise spectrum recorded for noise suppression"),
+            field("agc_enabled", Bool, json!(agc_enabled), (None, None), true,
+                "Automatically adjust input gain before wake word detection and VAD"),
+            field("agc_target_rms", Float, json!(agc_target_rms), (Some(0.001), Some(1.0)), true,
+                "RMS level the automatic gain aims for"),
+            field("agc_max_gain", Float, json!(agc_max_gain), (Some(1.0), Some(100.0)), true,
+                "Upper bound on the automatic gain"),
+            field("agc_noise_gate", Float, json!(agc_noise_gate), (Some(0.0), Some(1.0)), true,
+                "RMS below which the gain stops adapting"),
+            field("agc_apply_to_capture", Bool, json!(agc_apply_to_capture), (None, None), true,
+                "Also apply the automatic gain to audio sent to STT"),
+            field("level_emit_interval_ms", Integer, json!(level_emit_interval_ms), (Some(0.0), None), true,
+                "Minimum time between audio level updates for the meter"),
+            field("device_reconnect_attempts", Integer, json!(device_reconnect_attempts), (Some(0.0), None), true,
+                "Attempts to reopen the input device after it disconnects"),
+            field("device_reconnect_backoff_ms", Integer, json!(device_reconnect_backoff_ms), (Some(0.0), None), true,
+                "Delay before the first reconnect attempt, doubling on each further attempt"),
+            field("auto_restart", Bool, json!(auto_restart), (None, None), false,
+                "Automatically restart the voice system when it stops working"),
+            field("auto_restart_max_attempts", Integer, json!(auto_restart_max_attempts), (Some(0.0), None), false,
+                "Restarts attempted before giving up"),
+            field("auto_restart_backoff_ms", Integer, json!(auto_restart_backoff_ms), (Some(0.0), None), false,
+                "Delay before the first restart, doubling on each further attempt"),
+            field("watchdog_stall_ms", Integer, json!(watchdog_stall_ms), (Some(1000.0), None), false,
+                "Time without processed audio after which the system counts as stalled"),
+            field("watchdog_max_inference_failures", Integer, json!(watchdog_max_inference_failures), (Some(1.0), None), false,
+                "Consecutive wake word inference failures that count as degraded"),
+            field("watchdog_max_dropped_chunks", Integer, json!(watchdog_max_dropped_chunks), (Some(0.0), None), false,
+                "Dropped audio chunks after which the system counts as overrun (0 disables)"),
+            field("record_utterances", Bool, json!(record_utterances), (None, None), false,
+                "Save each captured utterance as a WAV file for debugging"),
+            field("utterance_dir", String, json!(utterance_dir), (None, None), false,
+                "Directory that receives recorded utterance WAV files"),
+            field("stt_endpoint", String, json!(stt_endpoint), (None, None), false,
+                "WebSocket STT service to stream captures to instead of the frontend (empty = frontend)"),
+            field("event_history_capacity", Integer, json!(event_history_capacity), (Some(0.0), Some(10000.0)), true,
+                "Recent voice events kept for inspection in the UI (0 = off)"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_names_are_unique() {
+        let schema = VoiceConfig::schema();
+        let mut names: Vec<_> = schema.iter().map(|f| f.name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), schema.len());
+    }
+
+    #[test]
+    fn test_schema_defaults_within_range() {
+        for field in VoiceConfig::schema() {
+            if let Some(value) = field.default.as_f64() {
+                assert!(field.min.is_none_or(|min| value >= min), "{} below min", field.name);
+                assert!(field.max.is_none_or(|max| value <= max), "{} above max", field.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Saved voice config
+//!
+//! Changes made through `update_voice_config` are written to a JSON file in
+//! the app config directory and applied whenever the voice system starts.
+//! The file is merged over the defaults, so fields added in later versions
+//! pick up their default values.
+
+use serde_json::Value;
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use super::config::VoiceConfig;
+
+/// File inside the app config directory holding the saved config
+pub const VOICE_CONFIG_FILE: &str = "voice_config.json";
+
+/// Read the saved config, falling back to defaults if missing or invalid
+///
+/// Fields the current version doesn't know are dropped.
+pub fn load_config(path: &Path) -> VoiceConfig {
+    let Ok(json) = std::fs::read_to_string(path) else {
+        return VoiceConfig::default();
+    };
+    let defaults = VoiceConfig::default();
+    let saved = serde_json::from_str::<Value>(&json).map(|mut saved| {
+        if let (Some(fields), Ok(Value::Object(known))) = (saved.as_object_mut(), serde_json::to_value(&defaults)) {
+            fields.retain(|name, _| known.contains_key(name));
+        }
+        saved
+    });
+    match saved {
+        Ok(saved) => defaults.merged(&saved).unwrap_or_else(|e| {
+            log::warn!("Ignoring invalid voice config {:?}: {}", path, e);
+            VoiceConfig::default()
+        }),
+        Err(e) => {
+            log::warn!("Ignoring unreadable voice config {:?}: {}", path, e);
+            VoiceConfig::default()
+        }
+    }
+}
+
+/// Write the config, creating the parent directory if needed
+pub fn save_config(config: &VoiceConfig, path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(config).map_err(io::Error::other)?;
+    std::fs::write(path, json)
+}
+
+/// Location of the saved voice config
+pub fn voice_config_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(VOICE_CONFIG_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("jarvis-config-{}", std::process::id()))
+            .join(VOICE_CONFIG_FILE);
+        let config = VoiceConfig { sensitivity: 1.5, listening_timeout_ms: 4000, ..Default::default() };
+
+        assert_eq!(load_config(&path), VoiceConfig::default());
+        save_config(&config, &path).unwrap();
+        assert_eq!(load_config(&path), config);
+
+        // Older or newer versions may have written a different set of fields
+        std::fs::write(&path, r#"{ "sensitivity": 1.5, "removed_field": 1 }"#).unwrap();
+        assert_eq!(load_config(&path), VoiceConfig { sensitivity: 1.5, ..Default::default() });
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,236 @@
+//! Checking configs and partial updates from the UI against the schema ranges
+//!
+//! [`VoiceConfig::validate`] guards the pipeline, so a setting the UI would
+//! refuse can't start it either. A partial update is a JSON object of field
+//! names to values; it applies as a whole or not at all, with every failing
+//! field reported together.
+
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+use super::config::VoiceConfig;
+
+/// Sample rates the processing pipeline accepts
+pub const SUPPORTED_SAMPLE_RATES: [u32; 4] = [8000, 16000, 44100, 48000];
+
+/// A config value that would break the pipeline
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    #[error("{name} must be at least {min}, got {value}")]
+    BelowMin { name: &'static str, min: f64, value: f64 },
+    #[error("{name} must be at most {max}, got {value}")]
+    AboveMax { name: &'static str, max: f64, value: f64 },
+    #[error("sample_rate {0} is not supported (expected one of {SUPPORTED_SAMPLE_RATES:?})")]
+    UnsupportedSampleRate(u32),
+}
+
+impl ConfigError {
+    /// Name of the offending field
+    pub fn field(&self) -> &'static str {
+        match *self {
+            Self::BelowMin { name, .. } | Self::AboveMax { name, .. } => name,
+            Self::UnsupportedSampleRate(_) => "sample_rate",
+        }
+    }
+}
+
+/// A field a partial config update could not apply
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &str, message: impl ToString) -> Self {
+        Self { field: field.to_string(), message: message.to_string() }
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// A partial config update that was rejected as a whole
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ConfigUpdateError {
+    #[error("config update must be a JSON object of field names to values")]
+    NotAnObject,
+    #[error("invalid config fields: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    InvalidFields(Vec<FieldError>),
+}
+
+/// A config change that was applied, and the fields it could not change in place
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppliedConfig {
+    pub config: VoiceConfig,
+    /// Changed fields that only take effect once the voice system restarts
+    pub restart_required: Vec<&'static str>,
+}
+
+impl VoiceConfig {
+    /// Check every numeric field against its schema range
+    ///
+    /// Unlike [`VoiceConfig::warnings`], a failure here means the pipeline would
+    /// panic or divide by zero, so the system refuses to start.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match self.validation_errors().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Every field outside its schema range, in declaration order
+    pub fn validation_errors(&self) -> Vec<ConfigError> {
+        let mut errors = Vec::new();
+        if !SUPPORTED_SAMPLE_RATES.contains(&self.sample_rate) {
+            errors.push(ConfigError::UnsupportedSampleRate(self.sample_rate));
+        }
+        for field in self.describe() {
+            // Unset optional fields serialize as null and have nothing to check
+            let Some(value) = field.default.as_f64() else {
+                continue;
+            };
+            if let Some(min) = field.min.filter(|&min| value < min) {
+                errors.push(ConfigError::BelowMin { name: field.name, min, value });
+            }
+            if let Some(max) = field.max.filter(|&max| value > max) {
+                errors.push(ConfigError::AboveMax { name: field.name, max, value });
+            }
+        }
+        errors
+    }
+
+    /// This config with the fields in `partial` (a JSON object keyed by field name) replaced
+    ///
+    /// The update applies as a whole or not at all: unknown fields, values of
+    /// the wrong type and values outside their range are all reported together.
+    pub fn merged(&self, partial: &Value) -> Result<VoiceConfig, ConfigUpdateError> {
+        let updates = partial.as_object().ok_or(ConfigUpdateError::NotAnObject)?;
+        let current = serde_json::to_value(self)
+            .map_err(|e| ConfigUpdateError::InvalidFields(vec![FieldError::new("config", e)]))?;
+
+        let mut errors = Vec::new();
+        for (name, value) in updates {
+            if current.get(name).is_none() {
+                errors.push(FieldError::new(name, "unknown field"));
+            } else if let Err(e) = with_fields(&current, [(name, value)]) {
+                errors.push(FieldError::new(name, e));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(ConfigUpdateError::InvalidFields(errors));
+        }
+
+        let config = with_fields(&current, updates)
+            .map_err(|e| ConfigUpdateError::InvalidFields(vec![FieldError::new("config", e)]))?;
+        let errors: Vec<_> = config
+            .validation_errors()
+            .into_iter()
+            .map(|e| FieldError::new(e.field(), e))
+            .collect();
+        if !errors.is_empty() {
+            return Err(ConfigUpdateError::InvalidFields(errors));
+        }
+        Ok(config)
+    }
+}
+
+/// Deserialize `current` (a serialized config) with `fields` replaced
+fn with_fields<'a>(
+    current: &Value,
+    fields: impl IntoIterator<Item = (&'a String, &'a Value)>,
+) -> Result<VoiceConfig, serde_json::Error> {
+    let mut value = current.clone();
+    for (name, field) in fields {
+        value[name.as_str()] = field.clone();
+    }
+    serde_json::from_value(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn assert_rejected(config: VoiceConfig, name: &str) {
+        match config.validate() {
+            Err(ConfigError::BelowMin { name: field, .. } | ConfigError::AboveMax { name: field, .. }) => {
+                assert_eq!(field, name)
+            }
+            other => panic!("expected {} to be rejected, got {:?}", name, other),
+        }
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert_eq!(VoiceConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_fields_are_rejected() {
+        assert_eq!(
+            VoiceConfig { sample_rate: 0, ..Default::default() }.validate(),
+            Err(ConfigError::UnsupportedSampleRate(0))
+        );
+        assert_eq!(
+            VoiceConfig { sample_rate: 22050, ..Default::default() }.validate(),
+            Err(ConfigError::UnsupportedSampleRate(22050))
+        );
+        assert_rejected(VoiceConfig { chunk_size: 0, ..Default::default() }, "chunk_size");
+        assert_rejected(VoiceConfig { mel_frame_count: 0, ..Default::default() }, "mel_frame_count");
+        assert_rejected(VoiceConfig { silence_threshold: 1.5, ..Default::default() }, "silence_threshold");
+        assert_rejected(VoiceConfig { silence_threshold: -0.1, ..Default::default() }, "silence_threshold");
+        assert_rejected(
+            VoiceConfig { speech_onset_threshold: Some(2.0), ..Default::default() },
+            "speech_onset_threshold",
+        );
+        assert_rejected(VoiceConfig { barge_in_threshold: 1.1, ..Default::default() }, "barge_in_threshold");
+        assert_rejected(VoiceConfig { inference_stride: 0, ..Default::default() }, "inference_stride");
+    }
+
+    #[test]
+    fn test_partial_update_changes_only_given_fields() {
+        let config = VoiceConfig::default()
+            .merged(&json!({ "silence_threshold": 0.02, "listening_timeout_ms": 5000, "stt_endpoint": "ws://stt" }))
+            .unwrap();
+
+        let expected = VoiceConfig {
+            silence_threshold: 0.02,
+            listening_timeout_ms: 5000,
+            stt_endpoint: Some("ws://stt".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn test_invalid_update_lists_every_failed_field() {
+        let update = json!({
+            "chunk_size": 0,
+            "silence_threshold": 2.0,
+            "sensitivity": "high",
+            "no_such_field": true,
+            "trim_silence": false,
+        });
+        let Err(ConfigUpdateError::InvalidFields(errors)) = VoiceConfig::default().merged(&update) else {
+            panic!("expected the update to be rejected");
+        };
+        let mut fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        fields.sort();
+        assert_eq!(fields, vec!["no_such_field", "sensitivity"]);
+
+        // Once the types are right, range checks report every remaining field
+        let update = json!({ "chunk_size": 0, "silence_threshold": 2.0 });
+        let Err(ConfigUpdateError::InvalidFields(errors)) = VoiceConfig::default().merged(&update) else {
+            panic!("expected the update to be rejected");
+        };
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["chunk_size", "silence_threshold"]);
+
+        assert_eq!(VoiceConfig::default().merged(&json!([1, 2])), Err(ConfigUpdateError::NotAnObject));
+    }
+}
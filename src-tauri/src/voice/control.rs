@@ -0,0 +1,44 @@
+//! Control messages sent from the controller to the audio processing thread
+//!
+//! The processing thread drains pending messages before each chunk, so a
+//! change takes effect on the next chunk without restarting the pipeline.
+
+use tokio::sync::mpsc;
+
+use super::config::VoiceConfig;
+
+/// Runtime adjustments for the audio processing thread
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlMessage {
+    /// Run inference every `n` new mel frames (n >= 1)
+    SetInferenceHop(usize),
+    /// Rebuild the detection components with a new config, keeping capture running
+    Reload(Box<VoiceConfig>),
+    /// Rebuild only the wake word detector from the shared config, e.g. after a model file changed
+    ReloadModels,
+    /// Free non-essential retained audio (pre-roll)
+    ReleaseBuffers,
+    /// Start collecting wake word score statistics afresh
+    ResetWakeWordStats,
+    /// Record this many ms of ambient audio as the noise profile
+    RecordNoiseProfile(u64),
+    /// Rebuild the noise suppression stage from the shared config
+    UpdateNoiseSuppression,
+    /// Measure this many ms of ambient audio and derive `silence_threshold` from it
+    CalibrateNoiseFloor(u64),
+    /// Play synthesized speech on the output device while Speaking
+    PlayTts { samples: Vec<f32>, sample_rate: u32 },
+    /// Exit the processing loop as soon as possible
+    Shutdown,
+}
+
+/// Sender half held by the controller
+pub type ControlSender = mpsc::UnboundedSender<ControlMessage>;
+
+/// Receiver half owned by the processing thread
+pub type ControlReceiver = mpsc::UnboundedReceiver<ControlMessage>;
+
+/// Create a new control channel
+pub fn control_channel() -> (ControlSender, ControlReceiver) {
+    mpsc::unbounded_channel()
+}
@@ -0,0 +1,86 @@
+//! Input and output device selection of a voice controller
+
+use super::VoiceController;
+use crate::voice::audio_capture::AudioDeviceInfo;
+use crate::voice::device_prefs::DevicePreferences;
+use crate::voice::events::emit_event;
+
+impl VoiceController {
+    /// Set the input device to use
+    pub fn set_input_device(&self, device_name: Option<String>) {
+        self.state.write().input_device = device_name;
+    }
+
+    /// Set the output device to use
+    pub fn set_output_device(&self, device_name: Option<String>) {
+        self.state.write().output_device = device_name;
+    }
+
+    /// Get current input device
+    pub fn get_input_device(&self) -> Option<String> {
+        self.state.read().input_device.clone()
+    }
+
+    /// Get current output device
+    pub fn get_output_device(&self) -> Option<String> {
+        self.state.read().output_device.clone()
+    }
+
+    /// Input and output selection, read together so they are consistent
+    pub fn device_selection(&self) -> DevicePreferences {
+        let state = self.state.read();
+        DevicePreferences {
+            input_device: state.input_device.clone(),
+            output_device: state.output_device.clone(),
+        }
+    }
+
+    /// Selected input device if it is among `available`, otherwise fall back to the default
+    ///
+    /// A device unplugged since it was chosen clears the selection and emits
+    /// `voice-device-fallback` instead of failing the start.
+    pub(super) fn validate_input_device(&self, available: &[AudioDeviceInfo]) -> Option<String> {
+        let selected = self.state.read().input_device.clone()?;
+        if available.iter().any(|device| device.name == selected) {
+            return Some(selected);
+        }
+        log::warn!("Input device {} not found, using the default device", selected);
+        self.state.write().input_device = None;
+        emit_event(&self.sink, &self.state, "voice-device-fallback", serde_json::json!({
+            "missing": selected,
+        }));
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_missing_input_device_falls_back_to_default() {
+        use crate::voice::events::CollectingSink;
+
+        let sink = Arc::new(CollectingSink::default());
+        let mut controller = VoiceController::new(PathBuf::from("resources/models"));
+        controller.set_event_sink(sink.clone());
+        let available = vec![AudioDeviceInfo {
+            name: "Built-in Microphone".to_string(),
+            is_default: true,
+            default_sample_rate: 48000,
+            channels: 1,
+            supported_sample_rates: vec![48000],
+        }];
+
+        controller.set_input_device(Some("Built-in Microphone".to_string()));
+        assert_eq!(controller.validate_input_device(&available).as_deref(), Some("Built-in Microphone"));
+        assert!(sink.payloads("voice-device-fallback").is_empty());
+
+        controller.set_input_device(Some("USB Mic".to_string()));
+        assert_eq!(controller.validate_input_device(&available), None);
+        assert_eq!(controller.get_input_device(), None);
+        assert_eq!(sink.payloads("voice-device-fallback"), vec![serde_json::json!({ "missing": "USB Mic" })]);
+    }
+}
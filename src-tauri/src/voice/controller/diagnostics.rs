@@ -0,0 +1,98 @@
+//! Diagnostics and housekeeping for a running voice controller
+
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::VoiceController;
+use crate::voice::control::ControlMessage;
+use crate::voice::cpu_usage::CpuUsage;
+use crate::voice::event_history::EventRecord;
+use crate::voice::events::emit_event;
+use crate::voice::memory::{sample_bytes, MemoryReport};
+use crate::voice::priority::AudioPriorityStatus;
+use crate::voice::session_recording::SessionRecorder;
+use crate::voice::watchdog::HealthFailure;
+use crate::voice::VoiceError;
+
+impl VoiceController {
+    /// Estimate the CPU share of the processing loop over the last few seconds
+    pub fn cpu_usage(&self) -> CpuUsage {
+        self.state.read().cpu_usage.usage(Instant::now())
+    }
+
+    /// Report whether the audio threads run at elevated priority
+    pub fn audio_priority(&self) -> AudioPriorityStatus {
+        self.state.read().audio_priority
+    }
+
+    /// Audio chunks dropped this session because processing fell behind
+    pub fn dropped_audio_chunks(&self) -> u64 {
+        self.state.read().dropped_audio_chunks
+    }
+
+    /// Recent events, oldest first, for finding out what happened around a missed wake word
+    pub fn event_history(&self) -> Vec<EventRecord> {
+        self.state.read().event_history.records()
+    }
+
+    /// Report the memory retained by voice buffers
+    pub fn memory_report(&self) -> MemoryReport {
+        let state = self.state.read();
+        MemoryReport::new(
+            state.preroll_bytes.load(Ordering::Relaxed),
+            sample_bytes(state.state_machine.captured_allocated()),
+            state.session_recorder.as_ref().map_or(0, |r| r.bytes_written() as usize),
+        )
+    }
+
+    /// First failing health signal of the running processing loop as of `now`
+    pub fn health_failure(&self, now: Instant) -> Option<HealthFailure> {
+        let state = self.state.read();
+        state.health.check(now, &state.config)
+    }
+
+    /// Flag that stops the watchdog supervising this controller once it is dropped
+    pub fn watchdog_stop(&self) -> Arc<AtomicBool> {
+        self.watchdog_stop.clone()
+    }
+
+    /// Emit an event to the frontend on behalf of this controller
+    pub fn notify<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        emit_event(&self.sink, &self.state, event, payload);
+    }
+
+    /// Free retained audio that isn't needed for the current interaction
+    ///
+    /// The pre-roll refills within a second and the wake word score history
+    /// within a few inferences; an in-progress capture is kept.
+    pub fn release_retained_buffers(&self) {
+        self.state.write().state_machine.release_captured_audio();
+        if let Some(ref control_tx) = self.control_tx {
+            let _ = control_tx.send(ControlMessage::ReleaseBuffers);
+        }
+    }
+
+    /// Start recording raw audio chunks and emitted events to a file
+    ///
+    /// Replaces any recording already in progress.
+    pub fn start_session_recording(&self, path: &Path) -> Result<(), VoiceError> {
+        let recorder = SessionRecorder::create(path)?;
+        if let Some(previous) = self.state.write().session_recorder.replace(recorder) {
+            previous.finish()?;
+        }
+        log::info!("Session recording started: {:?}", path);
+        Ok(())
+    }
+
+    /// Stop the active session recording and flush it to disk
+    pub fn stop_session_recording(&self) -> Result<(), VoiceError> {
+        if let Some(recorder) = self.state.write().session_recorder.take() {
+            recorder.finish()?;
+            log::info!("Session recording stopped");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+//! Driving and observing voice turns: triggers, frontend results and state queries
+
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use super::VoiceController;
+use crate::voice::control::ControlMessage;
+use crate::voice::events::{emit_event, emit_transition};
+use crate::voice::state_handlers::{end_capture, finish_transcription, report_failure};
+use crate::voice::state_machine::{StateAction, VoiceEvent, VoiceState};
+use crate::voice::{VoiceError, VoiceFrontendEvent};
+
+impl VoiceController {
+    /// Manually trigger listening (push-to-talk)
+    pub fn manual_trigger(&self) {
+        let result = self.state.write().state_machine.transition(VoiceEvent::ManualTrigger);
+        emit_transition(&self.sink, &self.state, &result);
+    }
+
+    /// Start a push-to-talk capture that ends on [`Self::manual_release`] rather than on VAD
+    pub fn push_to_talk(&self) {
+        let result = self.state.write().state_machine.transition(VoiceEvent::PushToTalk);
+        emit_transition(&self.sink, &self.state, &result);
+    }
+
+    /// End a manual capture now and send it to STT, whatever VAD thinks
+    pub fn manual_release(&self) {
+        end_capture(&self.sink, &self.state, VoiceEvent::ManualRelease);
+    }
+
+    /// Cancel current operation
+    pub fn cancel(&self) {
+        let mut state = self.state.write();
+        state.stop_tts_playback();
+        let result = state.state_machine.transition(VoiceEvent::Cancel);
+        drop(state);
+        emit_transition(&self.sink, &self.state, &result);
+    }
+
+    /// Get current state
+    pub fn current_state(&self) -> VoiceState {
+        self.state.read().state_machine.state()
+    }
+
+    /// How long the current state has lasted, e.g. for timeout progress
+    pub fn time_in_state(&self) -> Duration {
+        self.state.read().state_machine.time_in_state()
+    }
+
+    /// Wait for the next state transition and return the new state
+    ///
+    /// Only transitions that happen after this call resolve the future.
+    pub fn next_state_change(&self) -> impl Future<Output = VoiceState> {
+        let mut state_rx = self.state.read().state_machine.subscribe();
+        async move {
+            let _ = state_rx.changed().await;
+            *state_rx.borrow()
+        }
+    }
+
+    /// Receive state changes, wake word hits, audio levels and errors in Rust
+    ///
+    /// Events are published alongside the frontend events, to every receiver.
+    /// A receiver more than `SUBSCRIBER_CAPACITY` events behind gets
+    /// `RecvError::Lagged` with the number it missed, then continues from the
+    /// oldest event still buffered.
+    pub fn subscribe(&self) -> broadcast::Receiver<VoiceFrontendEvent> {
+        self.state.read().events_tx.subscribe()
+    }
+
+    /// Show an interim STT hypothesis while the user is speaking or being transcribed
+    ///
+    /// Doesn't change state; emits `voice-partial-transcription` with the text.
+    pub fn partial_transcription(&self, text: String) {
+        let result = self.state.write().state_machine.transition(VoiceEvent::PartialTranscription(text));
+        if let Some(StateAction::ShowPartial(text)) = result.action {
+            emit_event(&self.sink, &self.state, "voice-partial-transcription", serde_json::json!({
+                "text": text,
+            }));
+        } else {
+            emit_transition(&self.sink, &self.state, &result);
+        }
+    }
+
+    /// Notify that transcription is complete
+    ///
+    /// Blank text goes back to Idle with `voice-no-speech` unless
+    /// `skip_empty_transcriptions` is disabled.
+    pub fn transcription_complete(&self, text: String) {
+        let result = self.state.write().state_machine.transition(VoiceEvent::TranscriptionComplete(text));
+        finish_transcription(&self.sink, &self.state, &result);
+    }
+
+    /// Notify that the transcription found no intelligible speech
+    pub fn no_speech_detected(&self) {
+        let result = self.state.write().state_machine.transition(VoiceEvent::NoSpeechDetected);
+        finish_transcription(&self.sink, &self.state, &result);
+    }
+
+    /// Notify that AI response is ready
+    pub fn response_ready(&self, response: String) {
+        let result = self.state.write().state_machine.transition(VoiceEvent::ResponseReady(response));
+        emit_transition(&self.sink, &self.state, &result);
+    }
+
+    /// Report a failure (e.g. STT or the AI backend) and hold the Error state
+    ///
+    /// The state clears on [`Self::cancel`] or after `error_hold_ms`.
+    pub fn report_error(&self, message: String) {
+        report_failure(&self.sink, &self.state, message);
+    }
+
+    /// Notify that TTS speech is complete
+    pub fn speech_complete(&self) {
+        let result = self.state.write().state_machine.transition(VoiceEvent::SpeechComplete);
+        emit_transition(&self.sink, &self.state, &result);
+    }
+
+    /// Play synthesized speech on the selected output device
+    ///
+    /// Speech completes on its own once playback drains, so the frontend
+    /// doesn't need to call [`Self::speech_complete`]. Barge-in, cancel and
+    /// stop cut playback short.
+    pub fn play_tts_audio(&self, samples: Vec<f32>, sample_rate: u32) -> Result<(), VoiceError> {
+        if self.current_state() != VoiceState::Speaking {
+            return Err(VoiceError::NotSpeaking);
+        }
+        let Some(ref control_tx) = self.control_tx else {
+            return Err(VoiceError::NotInitialized);
+        };
+        let _ = control_tx.send(ControlMessage::PlayTts { samples, sample_rate });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_next_state_change() {
+        let controller = VoiceController::new(PathBuf::from("resources/models"));
+        let next = controller.next_state_change();
+
+        controller.manual_trigger();
+
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        assert_eq!(rt.block_on(next), VoiceState::Listening);
+    }
+
+    #[test]
+    fn test_time_in_state_restarts_on_transition() {
+        let controller = VoiceController::new(PathBuf::from("resources/models"));
+        thread::sleep(Duration::from_millis(200));
+        controller.manual_trigger();
+        thread::sleep(Duration::from_millis(30));
+
+        let elapsed = controller.time_in_state();
+        assert!(elapsed >= Duration::from_millis(30), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(200), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn test_subscriber_receives_state_change() {
+        let controller = VoiceController::new(PathBuf::from("resources/models"));
+        let mut events_rx = controller.subscribe();
+
+        controller.manual_trigger();
+
+        assert_eq!(events_rx.try_recv().unwrap(), VoiceFrontendEvent::StateChanged(VoiceState::Listening));
+    }
+
+    #[test]
+    fn test_blank_transcription_emits_no_speech() {
+        use crate::voice::events::CollectingSink;
+
+        let sink = Arc::new(CollectingSink::default());
+        let mut controller = VoiceController::new(PathBuf::from("resources/models"));
+        controller.set_event_sink(sink.clone());
+        controller.manual_trigger();
+        controller.state.write().state_machine.transition(VoiceEvent::VadSpeechEnd);
+
+        controller.transcription_complete("  ".to_string());
+        assert_eq!(controller.current_state(), VoiceState::Idle);
+        assert_eq!(sink.payloads("voice-no-speech").len(), 1);
+    }
+}
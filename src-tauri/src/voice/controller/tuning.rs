@@ -0,0 +1,378 @@
+//! Runtime tuning of a voice controller: detection settings, gating and profiles
+
+use serde_json::Value;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use super::VoiceController;
+use crate::voice::config::VoiceConfig;
+use crate::voice::config_validation::AppliedConfig;
+use crate::voice::control::ControlMessage;
+use crate::voice::events::{emit_debug_log, emit_event};
+use crate::voice::model_manifest::config_for_model;
+use crate::voice::profiles::load_profile_config;
+use crate::voice::VoiceError;
+
+impl VoiceController {
+    /// Set wake word sensitivity
+    pub fn set_sensitivity(&self, sensitivity: f32) {
+        let mut state = self.state.write();
+        state.config.sensitivity = sensitivity.clamp(0.1, 3.0);
+        let warnings = state.config.warnings();
+        state.tuning_dirty.store(true, Ordering::Release);
+        drop(state);
+
+        for warning in warnings {
+            emit_debug_log(&self.sink, "warn", &warning);
+        }
+    }
+
+    /// Set the base wake word threshold before sensitivity scaling
+    pub fn set_wake_word_threshold(&self, threshold: f32) {
+        let mut state = self.state.write();
+        state.config.wake_word_threshold = threshold.clamp(0.0, 1.0);
+        let warnings = state.config.warnings();
+        state.tuning_dirty.store(true, Ordering::Release);
+        drop(state);
+
+        for warning in warnings {
+            emit_debug_log(&self.sink, "warn", &warning);
+        }
+    }
+
+    /// Check if the current threshold and sensitivity allow wake word detection
+    pub fn is_detection_possible(&self) -> bool {
+        self.state.read().config.is_detection_possible()
+    }
+
+    /// Get the number of mel frames between wake word inference runs
+    pub fn inference_hop(&self) -> usize {
+        self.state.read().config.inference_stride
+    }
+
+    /// Set the number of mel frames between wake word inference runs
+    ///
+    /// Takes effect on the next processed chunk when running, otherwise on next start.
+    pub fn set_inference_hop(&self, hop: usize) -> Result<(), VoiceError> {
+        if hop < 1 {
+            return Err(VoiceError::InvalidConfig("inference hop must be at least 1".to_string()));
+        }
+
+        self.state.write().config.inference_stride = hop;
+        if let Some(ref control_tx) = self.control_tx {
+            let _ = control_tx.send(ControlMessage::SetInferenceHop(hop));
+        }
+        Ok(())
+    }
+
+    /// Enable or disable wake word detection
+    pub fn set_wake_word_enabled(&self, enabled: bool) {
+        self.state.write().wake_word_enabled = enabled;
+    }
+
+    /// Report whether a user is present, from an external camera/presence signal
+    ///
+    /// Wake word inference only runs while this,
+    /// [`set_wake_word_enabled`](Self::set_wake_word_enabled) and the listening
+    /// schedule (`active_schedule`) all allow it; any one pauses scanning.
+    /// Manual triggers and in-progress captures are unaffected.
+    pub fn set_presence(&self, present: bool) {
+        let mut state = self.state.write();
+        if state.user_present == present {
+            return;
+        }
+        state.user_present = present;
+        drop(state);
+
+        emit_event(
+            &self.sink,
+            &self.state,
+            "voice-presence-changed",
+            serde_json::json!({ "present": present }),
+        );
+    }
+
+    /// Enable or disable `voice-accessibility-status` announcements
+    pub fn set_accessibility_events(&self, enabled: bool) {
+        self.state.write().config.accessibility_events = enabled;
+    }
+
+    /// Record `duration_ms` of ambient audio as the noise profile
+    ///
+    /// Stay quiet while recording. The profile is stored in the config and
+    /// `voice-noise-profile-recorded` fires once it is ready.
+    pub fn record_noise_profile(&self, duration_ms: u64) -> Result<(), VoiceError> {
+        let Some(ref control_tx) = self.control_tx else {
+            return Err(VoiceError::NotInitialized);
+        };
+        let _ = control_tx.send(ControlMessage::RecordNoiseProfile(duration_ms));
+        Ok(())
+    }
+
+    /// Measure the ambient noise floor and set `silence_threshold` from it
+    ///
+    /// The user should stay quiet for `duration_ms`, clamped to 0.1-10s. Completes asynchronously
+    /// with `voice-calibration-complete`, or `voice-calibration-failed` if the
+    /// recording was too loud to be ambient noise.
+    pub fn calibrate_noise_floor(&self, duration_ms: u64) -> Result<(), VoiceError> {
+        let Some(ref control_tx) = self.control_tx else {
+            return Err(VoiceError::NotInitialized);
+        };
+        let _ = control_tx.send(ControlMessage::CalibrateNoiseFloor(duration_ms));
+        Ok(())
+    }
+
+    /// Rebuild the wake word detector from the current config while capture keeps running
+    ///
+    /// If loading fails the previous detector stays in use and `voice-error` fires.
+    pub fn reload_models(&self) -> Result<(), VoiceError> {
+        let Some(ref control_tx) = self.control_tx else {
+            return Err(VoiceError::NotInitialized);
+        };
+        let _ = control_tx.send(ControlMessage::ReloadModels);
+        Ok(())
+    }
+
+    /// Reset the wake word score statistics reported in `voice-wake-word-stats`
+    pub fn reset_wake_word_stats(&self) -> Result<(), VoiceError> {
+        let Some(ref control_tx) = self.control_tx else {
+            return Err(VoiceError::NotInitialized);
+        };
+        let _ = control_tx.send(ControlMessage::ResetWakeWordStats);
+        Ok(())
+    }
+
+    /// Enable or disable subtracting the recorded noise profile from incoming audio
+    pub fn set_noise_suppression(&self, enabled: bool) {
+        self.state.write().config.noise_suppression = enabled;
+        if let Some(ref control_tx) = self.control_tx {
+            let _ = control_tx.send(ControlMessage::UpdateNoiseSuppression);
+        }
+    }
+
+    /// Switch to a named user profile, or back to the config from before any profile with `None`
+    ///
+    /// Swaps the whole config and reloads the models in place when running.
+    /// The config in effect when the first profile is activated, including
+    /// saved settings and runtime tuning, is what `None` restores.
+    pub fn set_active_profile(&self, name: Option<&str>) -> Result<(), VoiceError> {
+        let config = match name {
+            Some(name) => load_profile_config(&self.models_dir, name)?,
+            None => {
+                let base = self.state.read().base_config.clone();
+                base.unwrap_or_else(|| VoiceConfig { profile: None, ..self.config() })
+            }
+        };
+        let previous = self.config();
+        let restart_required = self.set_config(config)?;
+        let mut state = self.state.write();
+        match name {
+            Some(_) if previous.profile.is_none() => state.base_config = Some(previous),
+            Some(_) => {}
+            None => state.base_config = None,
+        }
+        drop(state);
+
+        emit_event(
+            &self.sink,
+            &self.state,
+            "voice-profile-changed",
+            serde_json::json!({ "profile": name, "restart_required": restart_required }),
+        );
+        log::info!("Active voice profile: {:?}", name);
+        Ok(())
+    }
+
+    /// Switch to the named wake word bundle from the models directory's manifest
+    ///
+    /// Reloads the detector in place when running, like any config change.
+    pub fn set_wake_word_model(&self, name: &str) -> Result<AppliedConfig, VoiceError> {
+        let config = config_for_model(&self.models_dir, name, self.config())
+            .map_err(|e| VoiceError::InvalidConfig(e.to_string()))?;
+        let restart_required = self.set_config(config.clone())?;
+        Ok(AppliedConfig { config, restart_required })
+    }
+
+    /// Replace the whole config, reloading the pipeline in place when running
+    ///
+    /// Returns the changed fields the running pipeline can't pick up (see
+    /// `requires_restart` in the schema); they are stored and take effect on
+    /// the next start. Always empty when stopped.
+    pub fn set_config(&self, config: VoiceConfig) -> Result<Vec<&'static str>, VoiceError> {
+        config.validate().map_err(|e| VoiceError::InvalidConfig(e.to_string()))?;
+
+        let mut state = self.state.write();
+        let restart_required = match self.control_tx {
+            Some(_) => state.config.restart_required_changes(&config),
+            None => Vec::new(),
+        };
+        state
+            .state_machine
+            .set_follow_up_window(Duration::from_millis(config.follow_up_window_ms));
+        state.state_machine.set_skip_empty_transcriptions(config.skip_empty_transcriptions);
+        state.config = config.clone();
+        drop(state);
+        if let Some(ref control_tx) = self.control_tx {
+            let _ = control_tx.send(ControlMessage::Reload(Box::new(config)));
+        }
+        if !restart_required.is_empty() {
+            emit_debug_log(
+                &self.sink,
+                "warn",
+                &format!("Restart the voice system to apply: {}", restart_required.join(", ")),
+            );
+        }
+        Ok(restart_required)
+    }
+
+    /// Apply a partial update (field name to value) to the current config
+    ///
+    /// Nothing changes unless every field is valid; the error lists each one that isn't.
+    pub fn update_config(&self, partial: &Value) -> Result<AppliedConfig, VoiceError> {
+        let config = self
+            .config()
+            .merged(partial)
+            .map_err(|e| VoiceError::InvalidConfig(e.to_string()))?;
+        let restart_required = self.set_config(config.clone())?;
+        Ok(AppliedConfig { config, restart_required })
+    }
+
+    /// Get a copy of the current configuration
+    pub fn config(&self) -> VoiceConfig {
+        self.state.read().config.clone()
+    }
+
+    /// Get the active profile name
+    pub fn active_profile(&self) -> Option<String> {
+        self.state.read().config.profile.clone()
+    }
+
+    /// Get the base models directory
+    pub fn models_dir(&self) -> &Path {
+        &self.models_dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::events::CollectingSink;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_presence_changes_are_emitted_once() {
+        let sink = Arc::new(CollectingSink::default());
+        let mut controller = VoiceController::new(PathBuf::from("resources/models"));
+        controller.set_event_sink(sink.clone());
+
+        controller.set_presence(false);
+        controller.set_presence(false);
+        assert!(!controller.state.read().user_present);
+        controller.set_presence(true);
+
+        let presence: Vec<serde_json::Value> = sink
+            .payloads("voice-presence-changed")
+            .into_iter()
+            .map(|payload| payload["present"].clone())
+            .collect();
+        assert_eq!(presence, vec![false, true]);
+    }
+
+    #[test]
+    fn test_tuning_changes_flag_the_loop() {
+        let controller = VoiceController::new(PathBuf::from("resources/models"));
+        let dirty = controller.state.read().tuning_dirty.clone();
+        assert!(!dirty.load(Ordering::Acquire));
+
+        controller.set_sensitivity(2.0);
+        assert!(dirty.swap(false, Ordering::AcqRel));
+        controller.set_wake_word_threshold(0.6);
+        assert!(dirty.load(Ordering::Acquire));
+        assert_eq!(controller.state.read().config.wake_word_threshold, 0.6);
+    }
+
+    #[test]
+    fn test_rejected_update_leaves_config_unchanged() {
+        let controller = VoiceController::new(PathBuf::from("resources/models"));
+
+        let error = controller
+            .update_config(&serde_json::json!({ "sensitivity": 2.0, "chunk_size": 0 }))
+            .unwrap_err();
+        assert!(error.to_string().contains("chunk_size"), "{}", error);
+        assert_eq!(controller.config(), VoiceConfig::default());
+
+        let applied = controller.update_config(&serde_json::json!({ "sensitivity": 2.0 })).unwrap();
+        let config = applied.config;
+        assert_eq!(config.sensitivity, 2.0);
+        assert_eq!(controller.config(), config);
+    }
+
+    #[test]
+    fn test_running_update_reports_restart_fields() {
+        use crate::voice::audio_source::FileAudioSource;
+        use crate::voice::wav::write_wav;
+
+        let dir = std::env::temp_dir().join(format!("jarvis-restart-fields-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let wav = dir.join("silence.wav");
+        write_wav(&wav, &vec![0.0; 16000], 16000).unwrap();
+        let mut controller = VoiceController::new(dir.clone());
+        controller.set_wake_word_enabled(false);
+        controller.set_audio_source(Arc::new(FileAudioSource::new(&wav, true)));
+
+        // Stopped, everything takes effect on the next start
+        let applied = controller.update_config(&serde_json::json!({ "chunk_size": 640 })).unwrap();
+        assert!(applied.restart_required.is_empty());
+
+        controller.start().unwrap();
+        let applied = controller.update_config(&serde_json::json!({ "sensitivity": 2.0 })).unwrap();
+        assert!(applied.restart_required.is_empty());
+        let applied = controller
+            .update_config(&serde_json::json!({ "chunk_size": 1280, "channel_mode": "left", "sensitivity": 1.5 }))
+            .unwrap();
+        controller.stop();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(applied.restart_required, vec!["chunk_size", "channel_mode"]);
+        assert_eq!(controller.config(), applied.config);
+    }
+
+    #[test]
+    fn test_clearing_profile_restores_previous_config() {
+        let models_dir = std::env::temp_dir().join(format!("jarvis-profile-restore-{}", std::process::id()));
+        std::fs::create_dir_all(models_dir.join("profiles").join("alice")).unwrap();
+        let controller = VoiceController::new(models_dir.clone());
+        controller.update_config(&serde_json::json!({ "listening_timeout_ms": 4000 })).unwrap();
+        controller.set_sensitivity(2.0);
+        let base = controller.config();
+
+        controller.set_active_profile(Some("alice")).unwrap();
+        assert_eq!(controller.active_profile().as_deref(), Some("alice"));
+        assert_eq!(controller.config().listening_timeout_ms, VoiceConfig::default().listening_timeout_ms);
+
+        controller.set_active_profile(None).unwrap();
+        let _ = std::fs::remove_dir_all(&models_dir);
+        assert_eq!(controller.config(), base);
+    }
+
+    #[test]
+    fn test_wake_word_model_selected_from_manifest() {
+        use crate::voice::model_manifest::MANIFEST_FILE;
+
+        let models_dir = std::env::temp_dir().join(format!("jarvis-select-model-{}", std::process::id()));
+        std::fs::create_dir_all(&models_dir).unwrap();
+        let manifest = r#"{ "wake_words": [{ "name": "computer", "classifier": "computer.onnx", "threshold": 0.7 }] }"#;
+        std::fs::write(models_dir.join(MANIFEST_FILE), manifest).unwrap();
+        let controller = VoiceController::new(models_dir.clone());
+
+        assert!(controller.set_wake_word_model("no_such_wake_word").is_err());
+        assert_eq!(controller.config(), VoiceConfig::default());
+
+        let config = controller.set_wake_word_model("computer").unwrap().config;
+        let _ = std::fs::remove_dir_all(&models_dir);
+        assert_eq!(config.model_files.wakeword, "computer.onnx");
+        assert_eq!(config.wake_word_threshold, 0.7);
+        assert_eq!(controller.config(), config);
+    }
+}
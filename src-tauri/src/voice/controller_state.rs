@@ -0,0 +1,100 @@
+//! State shared between the voice controller, its threads and the commands
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use super::config::VoiceConfig;
+use super::cpu_usage::CpuUsageTracker;
+use super::event_history::EventHistory;
+use super::events::SUBSCRIBER_CAPACITY;
+use super::inference_cancel::InferenceCanceller;
+use super::priority::AudioPriorityStatus;
+use super::session_recording::SessionRecorder;
+use super::state_machine::{VoiceState, VoiceStateMachine};
+use super::watchdog::HealthSignals;
+use super::VoiceFrontendEvent;
+
+/// Shared state for the voice controller
+pub struct VoiceControllerState {
+    pub state_machine: VoiceStateMachine,
+    pub config: VoiceConfig,
+    /// Config in effect before a profile was activated, restored when it is cleared
+    pub base_config: Option<VoiceConfig>,
+    pub is_running: bool,
+    pub wake_word_enabled: bool,
+    /// External presence signal; wake word scanning pauses while nobody is present
+    pub user_present: bool,
+    /// False outside the configured listening schedule
+    pub schedule_active: bool,
+    pub input_device: Option<String>,
+    pub output_device: Option<String>,
+    /// Aborts in-flight inference on the processing thread during shutdown
+    pub inference_canceller: Option<InferenceCanceller>,
+    /// Active session recording, if any
+    pub session_recorder: Option<SessionRecorder>,
+    /// Processing loop timings for the CPU usage estimate
+    pub cpu_usage: CpuUsageTracker,
+    /// Last state sent in `voice-state-changed`, to suppress duplicates
+    pub last_emitted_state: Option<VoiceState>,
+    /// Bytes allocated for pre-roll on the processing thread
+    pub preroll_bytes: Arc<AtomicUsize>,
+    /// Set when sensitivity or threshold change so the loop re-reads them
+    pub tuning_dirty: Arc<AtomicBool>,
+    /// Outcome of raising the audio thread priorities
+    pub audio_priority: AudioPriorityStatus,
+    /// Health signals of the current processing loop, watched by the watchdog
+    pub health: Arc<HealthSignals>,
+    /// Stops the TTS audio currently playing from Rust, if any
+    pub tts_cancel: Option<Arc<AtomicBool>>,
+    /// Processing loops currently running; more than one means a stop didn't finish
+    pub processing_loops: Arc<AtomicUsize>,
+    /// Chunks dropped this session because processing fell behind capture
+    pub dropped_audio_chunks: u64,
+    /// Publishes events to Rust subscribers alongside the frontend
+    pub events_tx: broadcast::Sender<VoiceFrontendEvent>,
+    /// Recent events for `get_event_history`
+    pub event_history: EventHistory,
+}
+
+impl VoiceControllerState {
+    pub fn new() -> Self {
+        Self {
+            state_machine: VoiceStateMachine::new(),
+            config: VoiceConfig::default(),
+            base_config: None,
+            is_running: false,
+            wake_word_enabled: true,
+            user_present: true,
+            schedule_active: true,
+            input_device: None,
+            output_device: None,
+            inference_canceller: None,
+            session_recorder: None,
+            cpu_usage: CpuUsageTracker::default(),
+            last_emitted_state: None,
+            preroll_bytes: Arc::new(AtomicUsize::new(0)),
+            tuning_dirty: Arc::new(AtomicBool::new(false)),
+            audio_priority: AudioPriorityStatus::default(),
+            health: Arc::new(HealthSignals::new()),
+            tts_cancel: None,
+            processing_loops: Arc::new(AtomicUsize::new(0)),
+            dropped_audio_chunks: 0,
+            events_tx: broadcast::channel(SUBSCRIBER_CAPACITY).0,
+            event_history: EventHistory::new(VoiceConfig::default().event_history_capacity),
+        }
+    }
+
+    /// Stop any TTS audio playing from Rust without completing speech
+    pub fn stop_tts_playback(&mut self) {
+        if let Some(cancel) = self.tts_cancel.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Default for VoiceControllerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
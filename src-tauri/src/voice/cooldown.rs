@@ -0,0 +1,65 @@
+//! Refractory period after a wake word detection
+//!
+//! The sliding mel and embedding buffers stay full of the wake phrase for
+//! several chunks, so the classifier keeps scoring high right after it fires.
+
+use std::time::{Duration, Instant};
+
+/// Suppresses detections until the cooldown since the last accepted one elapses
+#[derive(Debug, Clone)]
+pub struct DetectionCooldown {
+    cooldown: Duration,
+    last_detection: Option<Instant>,
+}
+
+impl DetectionCooldown {
+    pub fn new(cooldown_ms: u64) -> Self {
+        Self {
+            cooldown: Duration::from_millis(cooldown_ms),
+            last_detection: None,
+        }
+    }
+
+    /// Change the cooldown, keeping the last detection time
+    pub fn set_cooldown_ms(&mut self, cooldown_ms: u64) {
+        self.cooldown = Duration::from_millis(cooldown_ms);
+    }
+
+    /// Accept a detection at `now` unless still cooling down from the previous one
+    pub fn try_trigger(&mut self, now: Instant) -> bool {
+        if let Some(last) = self.last_detection {
+            if now.saturating_duration_since(last) < self.cooldown {
+                return false;
+            }
+        }
+        self.last_detection = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::state_machine::{VoiceEvent, VoiceState, VoiceStateMachine};
+
+    #[test]
+    fn test_second_detection_within_cooldown_is_ignored() {
+        let mut cooldown = DetectionCooldown::new(1500);
+        let mut sm = VoiceStateMachine::new();
+        let start = Instant::now();
+        let mut transitions = 0;
+
+        // Second high score lands after a quick return to Idle
+        for offset_ms in [0, 400] {
+            if cooldown.try_trigger(start + Duration::from_millis(offset_ms))
+                && sm.transition(VoiceEvent::WakeWordDetected).new_state == VoiceState::Listening
+            {
+                transitions += 1;
+            }
+            sm.transition(VoiceEvent::Cancel);
+        }
+        assert_eq!(transitions, 1);
+
+        assert!(cooldown.try_trigger(start + Duration::from_millis(1500)));
+    }
+}
@@ -0,0 +1,138 @@
+//! Estimated CPU usage of the voice processing loop
+//!
+//! Times how much wall-clock the processing thread spends on each chunk,
+//! split into wake word inference and the rest of the capture path (filtering,
+//! level metering, VAD), over a sliding window. This is an estimate of the
+//! voice subsystem's share of one core, not an OS-accurate measurement.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Length of the sliding window used for the estimate
+pub const CPU_USAGE_WINDOW: Duration = Duration::from_secs(5);
+
+/// CPU usage estimate, as percentages of wall-clock time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct CpuUsage {
+    pub inference_pct: f32,
+    pub capture_pct: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ChunkTiming {
+    at: Instant,
+    inference: Duration,
+    capture: Duration,
+}
+
+/// Sliding window of per-chunk processing times
+#[derive(Debug)]
+pub struct CpuUsageTracker {
+    window: Duration,
+    timings: VecDeque<ChunkTiming>,
+}
+
+impl CpuUsageTracker {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            timings: VecDeque::new(),
+        }
+    }
+
+    /// Record the time spent processing a chunk that started at `at`
+    pub fn record(&mut self, at: Instant, inference: Duration, capture: Duration) {
+        self.timings.push_back(ChunkTiming { at, inference, capture });
+        self.evict(at);
+    }
+
+    /// Estimate usage over the window ending at `now`
+    pub fn usage(&self, now: Instant) -> CpuUsage {
+        let Some(oldest) = self.timings.iter().find(|t| now.duration_since(t.at) <= self.window) else {
+            return CpuUsage::default();
+        };
+
+        let mut inference = Duration::ZERO;
+        let mut capture = Duration::ZERO;
+        for timing in self.timings.iter().filter(|t| now.duration_since(t.at) <= self.window) {
+            inference += timing.inference;
+            capture += timing.capture;
+        }
+
+        // Until the window fills, divide by the time actually observed
+        let elapsed = now.duration_since(oldest.at).max(inference + capture);
+        if elapsed.is_zero() {
+            return CpuUsage::default();
+        }
+
+        CpuUsage {
+            inference_pct: percent(inference, elapsed),
+            capture_pct: percent(capture, elapsed),
+        }
+    }
+
+    /// Drop all recorded timings
+    pub fn clear(&mut self) {
+        self.timings.clear();
+    }
+
+    fn evict(&mut self, now: Instant) {
+        while let Some(front) = self.timings.front() {
+            if now.duration_since(front.at) > self.window {
+                self.timings.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl Default for CpuUsageTracker {
+    fn default() -> Self {
+        Self::new(CPU_USAGE_WINDOW)
+    }
+}
+
+fn percent(part: Duration, total: Duration) -> f32 {
+    (part.as_secs_f64() / total.as_secs_f64() * 100.0) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn test_usage_over_window() {
+        let mut tracker = CpuUsageTracker::new(Duration::from_secs(1));
+        let start = Instant::now();
+
+        // 80ms chunks, each spending 8ms in inference and 4ms elsewhere
+        for i in 0..10 {
+            tracker.record(start + 80 * MS * i, 8 * MS, 4 * MS);
+        }
+
+        let usage = tracker.usage(start + 800 * MS);
+        assert!((usage.inference_pct - 10.0).abs() < 0.5, "{:?}", usage);
+        assert!((usage.capture_pct - 5.0).abs() < 0.5, "{:?}", usage);
+    }
+
+    #[test]
+    fn test_old_timings_leave_window() {
+        let mut tracker = CpuUsageTracker::new(Duration::from_secs(1));
+        let start = Instant::now();
+
+        tracker.record(start, 500 * MS, Duration::ZERO);
+        tracker.record(start + 2000 * MS, Duration::ZERO, Duration::ZERO);
+
+        assert_eq!(tracker.usage(start + 2000 * MS).inference_pct, 0.0);
+    }
+
+    #[test]
+    fn test_empty_tracker() {
+        let tracker = CpuUsageTracker::default();
+        assert_eq!(tracker.usage(Instant::now()), CpuUsage::default());
+    }
+}
@@ -0,0 +1,121 @@
+//! Device monitor - reports settled changes to the audio device lists
+//!
+//! Device enumeration churns on some systems (Bluetooth flapping, virtual
+//! devices appearing and vanishing), so a new list is only reported once it
+//! has stayed the same for the configured debounce interval.
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::audio_capture::{list_input_devices, list_output_devices, AudioDeviceInfo};
+use super::controller_state::VoiceControllerState;
+use super::events::{emit_event, EventSink};
+
+/// How often the device lists are enumerated
+pub const DEVICE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Input and output devices as reported in `voice-devices-changed`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeviceLists {
+    pub inputs: Vec<AudioDeviceInfo>,
+    pub outputs: Vec<AudioDeviceInfo>,
+}
+
+impl DeviceLists {
+    pub fn enumerate() -> Self {
+        Self {
+            inputs: list_input_devices(),
+            outputs: list_output_devices(),
+        }
+    }
+}
+
+/// Reports a value once it differs from the last reported one and has been stable for `interval`
+#[derive(Debug, Clone)]
+pub struct Debouncer<T> {
+    settled: T,
+    candidate: Option<(T, Instant)>,
+    interval: Duration,
+}
+
+impl<T: Clone + PartialEq> Debouncer<T> {
+    pub fn new(initial: T, interval: Duration) -> Self {
+        Self {
+            settled: initial,
+            candidate: None,
+            interval,
+        }
+    }
+
+    /// Record an observation at `now`, returning the value if it just settled
+    pub fn observe(&mut self, value: T, now: Instant) -> Option<T> {
+        if value == self.settled {
+            self.candidate = None;
+            return None;
+        }
+
+        match self.candidate {
+            Some((ref candidate, since)) if *candidate == value => {
+                if now.saturating_duration_since(since) < self.interval {
+                    return None;
+                }
+            }
+            _ => {
+                self.candidate = Some((value, now));
+                return None;
+            }
+        }
+
+        self.candidate = None;
+        self.settled = value.clone();
+        Some(value)
+    }
+}
+
+/// Poll the device lists on a background thread until `stop` is set
+pub fn spawn_device_monitor(
+    sink: EventSink,
+    state: Arc<RwLock<VoiceControllerState>>,
+    debounce: Duration,
+    stop: Arc<AtomicBool>,
+) {
+    thread::spawn(move || {
+        let mut debouncer = Debouncer::new(DeviceLists::enumerate(), debounce);
+        while !stop.load(Ordering::SeqCst) {
+            thread::sleep(DEVICE_POLL_INTERVAL);
+            if let Some(devices) = debouncer.observe(DeviceLists::enumerate(), Instant::now()) {
+                log::info!(
+                    "Audio devices changed: {} inputs, {} outputs",
+                    devices.inputs.len(),
+                    devices.outputs.len()
+                );
+                emit_event(&sink, &state, "voice-devices-changed", devices);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flapping_list_is_reported_once_settled() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut debouncer = Debouncer::new(vec!["mic"], Duration::from_millis(1000));
+
+        // Headset flaps in and out faster than the debounce
+        assert_eq!(debouncer.observe(vec!["mic", "headset"], at(0)), None);
+        assert_eq!(debouncer.observe(vec!["mic"], at(500)), None);
+        assert_eq!(debouncer.observe(vec!["mic", "headset"], at(1000)), None);
+        assert_eq!(debouncer.observe(vec!["mic", "headset"], at(1500)), None);
+
+        assert_eq!(debouncer.observe(vec!["mic", "headset"], at(2000)), Some(vec!["mic", "headset"]));
+        assert_eq!(debouncer.observe(vec!["mic", "headset"], at(4000)), None);
+    }
+}
@@ -0,0 +1,124 @@
+//! Saved audio device selection
+//!
+//! The chosen input and output devices are kept in a small JSON file in the
+//! app config directory so they apply as soon as the voice system starts,
+//! including after an app restart.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use super::audio_capture::AudioDeviceInfo;
+use super::controller::VoiceController;
+
+/// File inside the app config directory holding the device selection
+pub const DEVICE_PREFS_FILE: &str = "voice_devices.json";
+
+/// Preferred audio devices (`None` = system default)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DevicePreferences {
+    pub input_device: Option<String>,
+    pub output_device: Option<String>,
+}
+
+impl DevicePreferences {
+    /// Read saved preferences, falling back to defaults if missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::warn!("Ignoring invalid device preferences {:?}: {}", path, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Write the preferences, creating the parent directory if needed
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Select the preferred devices on a controller before it starts
+    pub fn apply(&self, controller: &VoiceController) {
+        controller.set_input_device(self.input_device.clone());
+        controller.set_output_device(self.output_device.clone());
+    }
+}
+
+/// Available devices together with the current selection, taken at one point in time
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioConfigSnapshot {
+    pub inputs: Vec<AudioDeviceInfo>,
+    pub outputs: Vec<AudioDeviceInfo>,
+    pub current_input: Option<String>,
+    pub current_output: Option<String>,
+}
+
+impl AudioConfigSnapshot {
+    pub fn new(inputs: Vec<AudioDeviceInfo>, outputs: Vec<AudioDeviceInfo>, selection: DevicePreferences) -> Self {
+        Self {
+            inputs,
+            outputs,
+            current_input: selection.input_device,
+            current_output: selection.output_device,
+        }
+    }
+}
+
+/// Location of the saved device preferences
+pub fn device_prefs_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|dir| dir.join(DEVICE_PREFS_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("jarvis-prefs-{}", std::process::id()))
+            .join(DEVICE_PREFS_FILE);
+        let prefs = DevicePreferences {
+            input_device: Some("USB Mic".to_string()),
+            output_device: None,
+        };
+
+        assert_eq!(DevicePreferences::load(&path), DevicePreferences::default());
+        prefs.save(&path).unwrap();
+        assert_eq!(DevicePreferences::load(&path), prefs);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_preference_set_before_start_is_applied() {
+        let prefs = DevicePreferences {
+            input_device: Some("USB Mic".to_string()),
+            output_device: Some("Speakers".to_string()),
+        };
+        let controller = VoiceController::new(PathBuf::from("resources/models"));
+
+        prefs.apply(&controller);
+
+        assert_eq!(controller.get_input_device().as_deref(), Some("USB Mic"));
+        assert_eq!(controller.get_output_device().as_deref(), Some("Speakers"));
+    }
+
+    #[test]
+    fn test_snapshot_reflects_selection() {
+        let controller = VoiceController::new(PathBuf::from("resources/models"));
+        controller.set_input_device(Some("USB Mic".to_string()));
+
+        let snapshot = AudioConfigSnapshot::new(Vec::new(), Vec::new(), controller.device_selection());
+
+        assert_eq!(snapshot.current_input.as_deref(), Some("USB Mic"));
+        assert_eq!(snapshot.current_output, None);
+    }
+}
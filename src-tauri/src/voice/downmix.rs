@@ -0,0 +1,182 @@
+//! Downmixing multi-channel capture to mono
+//!
+//! Averaging suits arrays of microphones, but an audio interface with the mic
+//! on one input would average in silent channels and lose level, so a single
+//! channel can be picked instead.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How multi-channel input is reduced to the mono signal the pipeline uses
+///
+/// Serialized as a plain string, `"mono"`, `"mid"`, `"left"`, `"right"` or
+/// `"channel:N"` for a zero-based channel index, so the config stays flat.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum ChannelMode {
+    /// Plain average of all channels
+    #[default]
+    Mono,
+    /// Mid channel of a mid/side decomposition of the first two channels, `mid = (L + R) / 2`
+    ///
+    /// Any further channels are ignored, so on a stereo pair inside a larger
+    /// interface it differs from the plain average. Mono input passes through.
+    Mid,
+    /// First channel only
+    Left,
+    /// Second channel only
+    Right,
+    /// A single channel by zero-based index
+    Index(usize),
+}
+
+impl ChannelMode {
+    /// Zero-based channel this mode takes on its own, if any
+    fn channel(self) -> Option<usize> {
+        match self {
+            ChannelMode::Left => Some(0),
+            ChannelMode::Right => Some(1),
+            ChannelMode::Index(index) => Some(index),
+            ChannelMode::Mono | ChannelMode::Mid => None,
+        }
+    }
+}
+
+impl fmt::Display for ChannelMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChannelMode::Mono => write!(f, "mono"),
+            ChannelMode::Mid => write!(f, "mid"),
+            ChannelMode::Left => write!(f, "left"),
+            ChannelMode::Right => write!(f, "right"),
+            ChannelMode::Index(index) => write!(f, "channel:{}", index),
+        }
+    }
+}
+
+impl FromStr for ChannelMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mono" => Ok(ChannelMode::Mono),
+            "mid" => Ok(ChannelMode::Mid),
+            "left" => Ok(ChannelMode::Left),
+            "right" => Ok(ChannelMode::Right),
+            _ => s
+                .strip_prefix("channel:")
+                .and_then(|index| index.parse().ok())
+                .map(ChannelMode::Index)
+                .ok_or_else(|| format!("unknown channel mode '{}'", s)),
+        }
+    }
+}
+
+impl From<ChannelMode> for String {
+    fn from(mode: ChannelMode) -> Self {
+        mode.to_string()
+    }
+}
+
+impl TryFrom<String> for ChannelMode {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Reduce one interleaved frame to a single sample
+pub fn downmix_frame(frame: &[f32], mode: ChannelMode) -> f32 {
+    if let Some(&sample) = mode.channel().and_then(|channel| frame.get(channel)) {
+        return sample;
+    }
+    match (mode, frame) {
+        (ChannelMode::Mid, [left, right, ..]) => (left + right) * 0.5,
+        _ if frame.is_empty() => 0.0,
+        _ => frame.iter().sum::<f32>() / frame.len() as f32,
+    }
+}
+
+/// Check a mode against the device's channel count, logging when it falls back
+pub fn validate_channel_mode(mode: ChannelMode, channels: usize) -> ChannelMode {
+    if mode == ChannelMode::Mid && channels < 2 {
+        log::warn!("Mid downmix needs 2 channels, device has {}; using plain average", channels);
+        return ChannelMode::Mono;
+    }
+    if let Some(channel) = mode.channel().filter(|&channel| channel >= channels) {
+        log::warn!("Channel {} selected, device has {}; using plain average", channel, channels);
+        return ChannelMode::Mono;
+    }
+    mode
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mid_of_stereo_signal() {
+        // Speech-like tone on the left, quieter copy plus offset on the right
+        let frames: Vec<[f32; 2]> = (0..64)
+            .map(|i| {
+                let s = (i as f32 * 0.2).sin();
+                [s, 0.5 * s + 0.1]
+            })
+            .collect();
+
+        for frame in &frames {
+            let mid = downmix_frame(frame, ChannelMode::Mid);
+            assert!((mid - (frame[0] + frame[1]) / 2.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_mid_ignores_extra_channels() {
+        assert_eq!(validate_channel_mode(ChannelMode::Mid, 4), ChannelMode::Mid);
+        assert_eq!(validate_channel_mode(ChannelMode::Mid, 1), ChannelMode::Mono);
+        // A stereo pair on the first two inputs of a 3-channel interface
+        let frame = [0.3, 0.6, 0.9];
+        assert!((downmix_frame(&frame, ChannelMode::Mid) - 0.45).abs() < 1e-6);
+        assert!((downmix_frame(&frame, ChannelMode::Mono) - 0.6).abs() < 1e-6);
+        assert_eq!(downmix_frame(&[0.25], ChannelMode::Mid), 0.25);
+    }
+
+    #[test]
+    fn test_channel_selection_on_stereo_buffer() {
+        // Mic on the first input, silence on the second
+        let interleaved = [0.4, 0.0, -0.2, 0.0, 0.6, 0.0];
+        let mono = |mode| -> Vec<f32> {
+            interleaved.chunks(2).map(|frame| downmix_frame(frame, mode)).collect()
+        };
+
+        assert_eq!(mono(ChannelMode::Mono), vec![0.2, -0.1, 0.3]);
+        assert_eq!(mono(ChannelMode::Left), vec![0.4, -0.2, 0.6]);
+        assert_eq!(mono(ChannelMode::Right), vec![0.0, 0.0, 0.0]);
+        assert_eq!(mono(ChannelMode::Index(0)), mono(ChannelMode::Left));
+    }
+
+    #[test]
+    fn test_modes_serialize_as_strings() {
+        for (mode, text) in [
+            (ChannelMode::Mono, "mono"),
+            (ChannelMode::Mid, "mid"),
+            (ChannelMode::Right, "right"),
+            (ChannelMode::Index(2), "channel:2"),
+        ] {
+            assert_eq!(serde_json::to_value(mode).unwrap(), serde_json::json!(text));
+            assert_eq!(serde_json::from_value::<ChannelMode>(serde_json::json!(text)).unwrap(), mode);
+        }
+        assert!(serde_json::from_value::<ChannelMode>(serde_json::json!("channel:x")).is_err());
+        assert!(serde_json::from_value::<ChannelMode>(serde_json::json!({ "index": 2 })).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_channel_falls_back() {
+        assert_eq!(validate_channel_mode(ChannelMode::Index(1), 2), ChannelMode::Index(1));
+        assert_eq!(validate_channel_mode(ChannelMode::Index(3), 2), ChannelMode::Mono);
+        assert_eq!(validate_channel_mode(ChannelMode::Right, 1), ChannelMode::Mono);
+        assert_eq!(validate_channel_mode(ChannelMode::Left, 1), ChannelMode::Left);
+    }
+}
@@ -0,0 +1,67 @@
+//! Level measurements shared by the processing loop, VAD and diagnostics
+
+/// dBFS reported for digital silence
+pub const DBFS_FLOOR: f32 = -100.0;
+
+/// Calculate RMS of audio samples
+pub fn calculate_rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum_squares: f32 = samples.iter().map(|&s| s * s).sum();
+    (sum_squares / samples.len() as f32).sqrt()
+}
+
+/// Calculate peak absolute amplitude of audio samples
+pub fn calculate_peak(samples: &[f32]) -> f32 {
+    samples.iter().fold(0.0_f32, |max, s| max.max(s.abs()))
+}
+
+/// Convert a linear level to dBFS, floored at [`DBFS_FLOOR`] for silence
+pub fn calculate_dbfs(level: f32) -> f32 {
+    if level > 0.0 {
+        (20.0 * level.log10()).max(DBFS_FLOOR)
+    } else {
+        DBFS_FLOOR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rms_calculation() {
+        let samples = vec![1.0, -1.0, 1.0, -1.0];
+        let rms = calculate_rms(&samples);
+        assert!((rms - 1.0).abs() < 0.001);
+    }
+
+    fn sine(amplitude: f32) -> Vec<f32> {
+        (0..1600).map(|i| (i as f32 * 2.0 * std::f32::consts::PI / 16.0).sin() * amplitude).collect()
+    }
+
+    #[test]
+    fn test_full_scale_sine_levels() {
+        let samples = sine(1.0);
+        assert!((calculate_peak(&samples) - 1.0).abs() < 1e-4);
+        assert!(calculate_dbfs(calculate_peak(&samples)).abs() < 0.01);
+        // A sine's RMS sits 3 dB below its peak
+        assert!((calculate_dbfs(calculate_rms(&samples)) - -3.01).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_half_scale_levels() {
+        let samples = sine(0.5);
+        assert!((calculate_peak(&samples) - 0.5).abs() < 1e-4);
+        assert!((calculate_dbfs(calculate_peak(&samples)) - -6.02).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_silence_hits_floor() {
+        let samples = vec![0.0; 1280];
+        assert_eq!(calculate_peak(&samples), 0.0);
+        assert_eq!(calculate_dbfs(calculate_rms(&samples)), DBFS_FLOOR);
+        assert_eq!(calculate_dbfs(1e-9), DBFS_FLOOR);
+    }
+}
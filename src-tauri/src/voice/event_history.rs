@@ -0,0 +1,99 @@
+//! Recent voice events kept in memory for inspection from the UI
+//!
+//! Unlike the debug log, the history holds the typed [`VoiceFrontendEvent`]s
+//! with their timestamps, so a missed wake word can be examined after the
+//! fact. Audio levels arrive many times a second and would push everything
+//! else out, so they are left out.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+
+use super::VoiceFrontendEvent;
+
+/// An event and when it was emitted
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventRecord {
+    /// Unix time in milliseconds
+    pub timestamp_ms: f64,
+    pub event: VoiceFrontendEvent,
+}
+
+/// Ring buffer of the most recent events
+#[derive(Debug, Clone)]
+pub struct EventHistory {
+    records: VecDeque<EventRecord>,
+    capacity: usize,
+}
+
+impl EventHistory {
+    /// Keep up to `capacity` events (0 disables the history)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Whether `event` is kept in the history
+    pub fn accepts(event: &VoiceFrontendEvent) -> bool {
+        !matches!(event, VoiceFrontendEvent::AudioLevel { .. })
+    }
+
+    /// Record `event`, dropping the oldest one when full
+    pub fn push(&mut self, event: VoiceFrontendEvent, timestamp_ms: f64) {
+        if !self.is_enabled() || !Self::accepts(&event) {
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(EventRecord { timestamp_ms, event });
+    }
+
+    /// Change the capacity, dropping the oldest events that no longer fit
+    pub fn set_capacity(&mut self, capacity: usize) {
+        while self.records.len() > capacity {
+            self.records.pop_front();
+        }
+        self.capacity = capacity;
+    }
+
+    /// Recorded events, oldest first
+    pub fn records(&self) -> Vec<EventRecord> {
+        self.records.iter().cloned().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(message: &str) -> VoiceFrontendEvent {
+        VoiceFrontendEvent::Error { message: message.to_string() }
+    }
+
+    #[test]
+    fn test_shrinking_keeps_newest_and_skips_levels() {
+        let mut history = EventHistory::new(4);
+        for (i, message) in ["a", "b", "c"].into_iter().enumerate() {
+            history.push(error(message), i as f64);
+        }
+        history.push(VoiceFrontendEvent::AudioLevel { rms: 0.1, peak: 0.2, dbfs: -14.0 }, 3.0);
+
+        history.set_capacity(2);
+        let events: Vec<_> = history.records().into_iter().map(|record| record.event).collect();
+        assert_eq!(events, vec![error("b"), error("c")]);
+
+        history.set_capacity(0);
+        history.push(error("d"), 4.0);
+        assert!(history.records().is_empty());
+    }
+}
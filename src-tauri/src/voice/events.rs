@@ -0,0 +1,304 @@
+//! Event emission to the frontend
+//!
+//! All voice events go through [`emit_event`] so active session recordings
+//! capture them, and state changes go through [`emit_state_changed`] so the
+//! frontend never sees the same state twice in a row.
+//!
+//! Events are delivered through a [`VoiceEventSink`], so the engine runs
+//! without Tauri; the app installs a sink that forwards to the webview. State
+//! changes, wake word hits, audio levels and errors are also published as
+//! [`VoiceFrontendEvent`]s to Rust subscribers and, except for audio levels,
+//! kept in the state's [`EventHistory`].
+
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use super::accessibility::{emit_accessibility_status, AccessibilityStatus};
+use super::controller_state::VoiceControllerState;
+use super::chunk::unix_time_ms;
+use super::event_history::EventHistory;
+use super::state_machine::{StateChange, TransitionResult, VoiceState};
+use super::VoiceFrontendEvent;
+
+/// Events buffered per subscriber before the oldest are overwritten
+pub const SUBSCRIBER_CAPACITY: usize = 256;
+
+/// Receiver for the events the voice engine emits
+pub trait VoiceEventSink: Send + Sync {
+    /// Deliver `event` with its JSON payload
+    fn emit(&self, event: &str, payload: serde_json::Value);
+}
+
+/// Where events go; `None` drops them (recordings still capture them)
+pub type EventSink = Option<Arc<dyn VoiceEventSink>>;
+
+/// Sink that keeps every event, for tests and headless use
+#[derive(Default)]
+pub struct CollectingSink {
+    events: Mutex<Vec<(String, serde_json::Value)>>,
+}
+
+impl CollectingSink {
+    /// Events received so far, oldest first
+    pub fn events(&self) -> Vec<(String, serde_json::Value)> {
+        self.events.lock().clone()
+    }
+
+    /// Payloads of the events named `event`
+    pub fn payloads(&self, event: &str) -> Vec<serde_json::Value> {
+        self.events
+            .lock()
+            .iter()
+            .filter(|(name, _)| name == event)
+            .map(|(_, payload)| payload.clone())
+            .collect()
+    }
+}
+
+impl VoiceEventSink for CollectingSink {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        self.events.lock().push((event.to_string(), payload));
+    }
+}
+
+/// Emit a voice event to the frontend, recording it when a session recording is active
+///
+/// Must not be called while holding a lock on `state`.
+pub fn emit_event<S: Serialize + Clone>(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    event: &str,
+    payload: S,
+) {
+    if state.read().session_recorder.is_some() {
+        if let Some(ref mut recorder) = state.write().session_recorder {
+            if let Err(e) = recorder.record_event(event, &payload) {
+                log::warn!("Failed to record event {}: {}", event, e);
+            }
+        }
+    }
+
+    let (events_tx, history) = {
+        let state = state.read();
+        (state.events_tx.clone(), state.event_history.is_enabled())
+    };
+    let subscribed = events_tx.receiver_count() > 0;
+    if sink.is_none() && !subscribed && !history {
+        return;
+    }
+    let value = match serde_json::to_value(payload) {
+        Ok(value) => value,
+        Err(e) => {
+            log::warn!("Failed to serialize event {}: {}", event, e);
+            return;
+        }
+    };
+    if let Some(frontend_event) = frontend_event(event, &value) {
+        if history && EventHistory::accepts(&frontend_event) {
+            let now = unix_time_ms(SystemTime::now());
+            state.write().event_history.push(frontend_event.clone(), now);
+        }
+        if subscribed {
+            // Fails only when the last receiver was dropped in the meantime
+            let _ = events_tx.send(frontend_event);
+        }
+    }
+    if let Some(ref sink) = sink {
+        sink.emit(event, value);
+    }
+}
+
+/// The typed event published to subscribers for an emitted event, if it is one
+fn frontend_event(event: &str, payload: &serde_json::Value) -> Option<VoiceFrontendEvent> {
+    let number = |key: &str| payload[key].as_f64().map(|value| value as f32);
+    match event {
+        "voice-state-changed" => serde_json::from_value(payload["state"].clone())
+            .ok()
+            .map(VoiceFrontendEvent::StateChanged),
+        "voice-wake-word" => number("score").map(|score| VoiceFrontendEvent::WakeWordDetected { score }),
+        "voice-error" => payload
+            .as_str()
+            .map(|message| VoiceFrontendEvent::Error { message: message.to_string() }),
+        "voice-audio-level" => Some(VoiceFrontendEvent::AudioLevel {
+            rms: number("rms")?,
+            peak: number("peak")?,
+            dbfs: number("dbfs")?,
+        }),
+        _ => None,
+    }
+}
+
+/// Emit `voice-state-changed`, suppressing repeats of the last emitted state
+///
+/// No-op transitions (e.g. cancelling while already idle) and the controller
+/// and processing loop reporting the same transition would otherwise cause
+/// duplicate consecutive events. Must not be called while holding a lock on `state`.
+pub fn emit_state_changed(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    new_state: VoiceState,
+) {
+    let mut state_guard = state.write();
+    let previous = state_guard.last_emitted_state;
+    if previous == Some(new_state) {
+        return;
+    }
+    state_guard.last_emitted_state = Some(new_state);
+    let entered_at_ms = state_guard.state_machine.entered_at_ms();
+    drop(state_guard);
+
+    emit_event(sink, state, "voice-state-changed", StateChange { state: new_state, entered_at_ms });
+    if let Some(status) = AccessibilityStatus::for_transition(previous, new_state) {
+        emit_accessibility_status(sink, state, status);
+    }
+}
+
+/// Report the outcome of a transition requested from outside the processing loop
+///
+/// Emits `voice-state-changed` and, in debug builds, `voice-transition-rejected`
+/// when the event wasn't valid in the current state. Must not be called while
+/// holding a lock on `state`.
+pub fn emit_transition(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    result: &TransitionResult,
+) {
+    if let Some(ref rejected) = result.rejected {
+        if cfg!(debug_assertions) {
+            emit_event(sink, state, "voice-transition-rejected", rejected.clone());
+        }
+    }
+    emit_state_changed(sink, state, result.new_state);
+}
+
+/// Emit `voice-error` with a message for the frontend
+///
+/// Must not be called while holding a lock on `state`.
+pub fn emit_error(sink: &EventSink, state: &Arc<RwLock<VoiceControllerState>>, message: String) {
+    emit_event(sink, state, "voice-error", message);
+    emit_accessibility_status(sink, state, AccessibilityStatus::Error);
+}
+
+/// Append a raw audio chunk to the active session recording
+pub fn record_session_audio(state: &Arc<RwLock<VoiceControllerState>>, samples: &[f32]) {
+    if let Some(ref mut recorder) = state.write().session_recorder {
+        if let Err(e) = recorder.record_audio(samples) {
+            log::warn!("Failed to record audio chunk: {}", e);
+        }
+    }
+}
+
+/// Emit a debug log message to the frontend
+pub fn emit_debug_log(sink: &EventSink, level: &str, message: &str) {
+    log::info!("[{}] {}", level, message);
+    if let Some(ref sink) = sink {
+        sink.emit("debug-log", serde_json::json!({
+            "level": level,
+            "message": message
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_duplicate_consecutive_state_changes() {
+        let collected = Arc::new(CollectingSink::default());
+        let sink: EventSink = Some(collected.clone());
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+
+        for new_state in [
+            VoiceState::Listening,
+            VoiceState::Listening,
+            VoiceState::Idle,
+            VoiceState::Idle,
+            VoiceState::Listening,
+        ] {
+            emit_state_changed(&sink, &state, new_state);
+        }
+
+        let emitted: Vec<serde_json::Value> = collected
+            .payloads("voice-state-changed")
+            .into_iter()
+            .map(|payload| payload["state"].clone())
+            .collect();
+        assert_eq!(emitted, vec!["listening", "idle", "listening"]);
+    }
+
+    #[test]
+    fn test_subscribers_receive_typed_events() {
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let mut events_rx = state.read().events_tx.subscribe();
+
+        emit_state_changed(&None, &state, VoiceState::Listening);
+        emit_error(&None, &state, "mic unplugged".to_string());
+        emit_event(&None, &state, "voice-vad", serde_json::json!({ "is_speech": true }));
+
+        assert_eq!(events_rx.try_recv().unwrap(), VoiceFrontendEvent::StateChanged(VoiceState::Listening));
+        assert_eq!(
+            events_rx.try_recv().unwrap(),
+            VoiceFrontendEvent::Error { message: "mic unplugged".to_string() }
+        );
+        assert!(events_rx.try_recv().is_err(), "only subscribed kinds are published");
+    }
+
+    #[test]
+    fn test_lagging_subscriber_skips_to_buffered_events() {
+        use tokio::sync::broadcast::error::TryRecvError;
+
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let mut events_rx = state.read().events_tx.subscribe();
+        for i in 0..SUBSCRIBER_CAPACITY + 10 {
+            emit_error(&None, &state, i.to_string());
+        }
+
+        assert!(matches!(events_rx.try_recv(), Err(TryRecvError::Lagged(10))));
+        assert_eq!(events_rx.try_recv().unwrap(), VoiceFrontendEvent::Error { message: "10".to_string() });
+    }
+
+    #[test]
+    fn test_history_keeps_latest_events_in_order() {
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        state.write().event_history.set_capacity(3);
+
+        emit_state_changed(&None, &state, VoiceState::Listening);
+        emit_event(&None, &state, "voice-wake-word", serde_json::json!({ "score": 0.9 }));
+        emit_event(&None, &state, "voice-audio-level", serde_json::json!({ "rms": 0.1, "peak": 0.2, "dbfs": -20.0 }));
+        emit_error(&None, &state, "first".to_string());
+        emit_error(&None, &state, "second".to_string());
+
+        let records = state.read().event_history.records();
+        let events: Vec<_> = records.iter().map(|record| record.event.clone()).collect();
+        assert_eq!(
+            events,
+            vec![
+                VoiceFrontendEvent::WakeWordDetected { score: 0.9 },
+                VoiceFrontendEvent::Error { message: "first".to_string() },
+                VoiceFrontendEvent::Error { message: "second".to_string() },
+            ]
+        );
+        assert!(records.windows(2).all(|pair| pair[0].timestamp_ms <= pair[1].timestamp_ms));
+    }
+
+    #[test]
+    fn test_accessibility_status_follows_config() {
+        let collected = Arc::new(CollectingSink::default());
+        let sink: EventSink = Some(collected.clone());
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+
+        emit_state_changed(&sink, &state, VoiceState::Listening);
+        state.write().config.accessibility_events = false;
+        emit_state_changed(&sink, &state, VoiceState::Transcribing);
+
+        let statuses: Vec<serde_json::Value> = collected
+            .payloads("voice-accessibility-status")
+            .into_iter()
+            .map(|payload| payload["message"].clone())
+            .collect();
+        assert_eq!(statuses, vec!["Now listening"]);
+    }
+}
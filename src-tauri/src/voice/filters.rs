@@ -0,0 +1,282 @@
+//! Composable audio filter chain applied to incoming chunks
+//!
+//! Filters are stateful across chunks so that a stream processed in pieces
+//! produces the same output as one processed in a single pass.
+
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+use super::config::VoiceConfig;
+
+/// A stateful audio filter operating on mono chunks in place
+pub trait AudioFilter: Send {
+    /// Process a chunk of samples in place
+    fn process(&mut self, samples: &mut [f32]);
+
+    /// Reset any internal state carried between chunks
+    fn reset(&mut self) {}
+}
+
+/// Configuration for a single filter in the chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FilterSpec {
+    /// Fixed linear gain
+    Gain { gain: f32 },
+    /// DC offset removal (pole close to 1.0, e.g. 0.995)
+    DcBlock { pole: f32 },
+    /// First-order high-pass filter
+    HighPass { cutoff_hz: f32 },
+}
+
+impl FilterSpec {
+    /// Build the filter described by this spec
+    pub fn build(&self, sample_rate: u32) -> Box<dyn AudioFilter> {
+        match *self {
+            FilterSpec::Gain { gain } => Box::new(GainFilter::new(gain)),
+            FilterSpec::DcBlock { pole } => Box::new(DcBlockFilter::new(pole)),
+            FilterSpec::HighPass { cutoff_hz } => {
+                Box::new(HighPassFilter::new(cutoff_hz, sample_rate))
+            }
+        }
+    }
+}
+
+/// Ordered chain of filters applied to each chunk
+#[derive(Default)]
+pub struct FilterChain {
+    filters: Vec<Box<dyn AudioFilter>>,
+}
+
+impl FilterChain {
+    /// Build a chain from an ordered list of specs
+    pub fn from_specs(specs: &[FilterSpec], sample_rate: u32) -> Self {
+        Self {
+            filters: specs.iter().map(|spec| spec.build(sample_rate)).collect(),
+        }
+    }
+
+    /// Build the chain configured for the processing loop
+    ///
+    /// The optional high-pass stage runs first so DC offset never reaches the
+    /// user-configured filters.
+    pub fn from_config(config: &VoiceConfig) -> Self {
+        let mut chain = Self::default();
+        if config.highpass_enabled {
+            chain.push(Box::new(HighPassFilter::new(config.highpass_cutoff_hz, config.sample_rate)));
+        }
+        for spec in &config.filter_chain {
+            chain.push(spec.build(config.sample_rate));
+        }
+        chain
+    }
+
+    /// Append a filter to the end of the chain
+    pub fn push(&mut self, filter: Box<dyn AudioFilter>) {
+        self.filters.push(filter);
+    }
+
+    /// Run a chunk through every filter in order
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for filter in &mut self.filters {
+            filter.process(samples);
+        }
+    }
+
+    /// Reset all filter state
+    pub fn reset(&mut self) {
+        for filter in &mut self.filters {
+            filter.reset();
+        }
+    }
+
+    /// Number of filters in the chain
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Check if the chain has no filters
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+}
+
+/// Fixed linear gain
+#[derive(Debug, Clone)]
+pub struct GainFilter {
+    gain: f32,
+}
+
+impl GainFilter {
+    pub fn new(gain: f32) -> Self {
+        Self { gain }
+    }
+}
+
+impl AudioFilter for GainFilter {
+    fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            *sample *= self.gain;
+        }
+    }
+}
+
+/// DC blocker: y[n] = x[n] - x[n-1] + pole * y[n-1]
+#[derive(Debug, Clone)]
+pub struct DcBlockFilter {
+    pole: f32,
+    prev_input: f32,
+    prev_output: f32,
+}
+
+impl DcBlockFilter {
+    pub fn new(pole: f32) -> Self {
+        Self {
+            pole: pole.clamp(0.0, 0.9999),
+            prev_input: 0.0,
+            prev_output: 0.0,
+        }
+    }
+}
+
+impl AudioFilter for DcBlockFilter {
+    fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            let input = *sample;
+            let output = input - self.prev_input + self.pole * self.prev_output;
+            self.prev_input = input;
+            self.prev_output = output;
+            *sample = output;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.prev_input = 0.0;
+        self.prev_output = 0.0;
+    }
+}
+
+/// First-order (RC) high-pass filter
+#[derive(Debug, Clone)]
+pub struct HighPassFilter {
+    alpha: f32,
+    prev_input: f32,
+    prev_output: f32,
+}
+
+impl HighPassFilter {
+    /// Create a high-pass filter with the given cutoff frequency
+    pub fn new(cutoff_hz: f32, sample_rate: u32) -> Self {
+        let rc = 1.0 / (2.0 * PI * cutoff_hz.max(f32::EPSILON));
+        let dt = 1.0 / sample_rate.max(1) as f32;
+        Self {
+            alpha: rc / (rc + dt),
+            prev_input: 0.0,
+            prev_output: 0.0,
+        }
+    }
+
+    /// Filter a chunk in place, carrying state into the next call
+    pub fn filter_in_place(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            let input = *sample;
+            let output = self.alpha * (self.prev_output + input - self.prev_input);
+            self.prev_input = input;
+            self.prev_output = output;
+            *sample = output;
+        }
+    }
+}
+
+impl AudioFilter for HighPassFilter {
+    fn process(&mut self, samples: &mut [f32]) {
+        self.filter_in_place(samples);
+    }
+
+    fn reset(&mut self) {
+        self.prev_input = 0.0;
+        self.prev_output = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 16000;
+
+    fn offset_tone(len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| 0.5 + 0.25 * (2.0 * PI * 1000.0 * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_gain() {
+        let mut filter = GainFilter::new(2.0);
+        let mut samples = vec![0.1, -0.2, 0.3];
+        filter.process(&mut samples);
+        assert_eq!(samples, vec![0.2, -0.4, 0.6]);
+    }
+
+    #[test]
+    fn test_chain_removes_offset_and_applies_gain() {
+        let specs = [FilterSpec::HighPass { cutoff_hz: 80.0 }, FilterSpec::Gain { gain: 2.0 }];
+        let mut chain = FilterChain::from_specs(&specs, SAMPLE_RATE);
+        assert_eq!(chain.len(), 2);
+
+        let mut samples = offset_tone(32000);
+        chain.process(&mut samples);
+
+        // Skip the filter's settling time
+        let settled = &samples[16000..];
+        let mean: f32 = settled.iter().sum::<f32>() / settled.len() as f32;
+        let peak = settled.iter().fold(0.0_f32, |max, s| max.max(s.abs()));
+        assert!(mean.abs() < 0.01, "mean was {}", mean);
+        assert!((peak - 0.5).abs() < 0.025, "peak was {}", peak);
+    }
+
+    #[test]
+    fn test_chain_state_persists_across_chunks() {
+        let specs = [FilterSpec::DcBlock { pole: 0.995 }, FilterSpec::Gain { gain: 0.5 }];
+        let input = offset_tone(4096);
+
+        let mut whole = input.clone();
+        FilterChain::from_specs(&specs, SAMPLE_RATE).process(&mut whole);
+
+        let mut chunked = input;
+        let mut chain = FilterChain::from_specs(&specs, SAMPLE_RATE);
+        for chunk in chunked.chunks_mut(1000) {
+            chain.process(chunk);
+        }
+
+        for (a, b) in whole.iter().zip(chunked.iter()) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_highpass_removes_dc_offset_across_chunks() {
+        let mut filter = HighPassFilter::new(80.0, SAMPLE_RATE);
+        let mut samples = vec![0.3; 32000];
+        for chunk in samples.chunks_mut(1280) {
+            filter.filter_in_place(chunk);
+        }
+
+        let tail = &samples[16000..];
+        let mean: f32 = tail.iter().sum::<f32>() / tail.len() as f32;
+        assert!(mean.abs() < 1e-3, "mean was {}", mean);
+    }
+
+    #[test]
+    fn test_config_chain_gates_highpass() {
+        let mut config = VoiceConfig {
+            filter_chain: vec![FilterSpec::Gain { gain: 2.0 }],
+            ..Default::default()
+        };
+        assert_eq!(FilterChain::from_config(&config).len(), 1);
+
+        config.highpass_enabled = true;
+        assert_eq!(FilterChain::from_config(&config).len(), 2);
+    }
+}
@@ -0,0 +1,52 @@
+//! Cancellation of in-flight ONNX inference
+
+use ort::session::RunOptions;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::wake_word::WakeWordError;
+
+/// Handle for aborting in-flight inference from another thread
+///
+/// Cancelling sets ONNX Runtime's terminate flag, which aborts a running
+/// `Session::run` at the next operator boundary. Operators themselves are not
+/// interruptible, so shutdown can still be delayed by the longest single
+/// operator (well under one inference on desktop CPUs). Once cancelled, every
+/// subsequent run fails fast with [`WakeWordError::Cancelled`].
+#[derive(Clone)]
+pub struct InferenceCanceller {
+    pub(super) run_options: Arc<RunOptions>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl InferenceCanceller {
+    pub(super) fn new() -> Result<Self, WakeWordError> {
+        let run_options = RunOptions::new().map_err(|e| WakeWordError::ModelLoadError(e.to_string()))?;
+        Ok(Self {
+            run_options: Arc::new(run_options),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Abort any in-flight inference and reject further runs
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Err(e) = self.run_options.terminate() {
+            log::warn!("Failed to terminate in-flight inference: {}", e);
+        }
+    }
+
+    /// Check if cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Map a session run failure, distinguishing cancellation from real errors
+    pub(super) fn run_error(&self, e: ort::Error) -> WakeWordError {
+        if self.is_cancelled() {
+            WakeWordError::Cancelled
+        } else {
+            WakeWordError::InferenceError(e.to_string())
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Single-input, single-output model run by the wake word pipeline
+//!
+//! The detector only ever feeds one f32 tensor and reads back the first
+//! output, so the ONNX session is hidden behind [`InferenceModel`]. Tests
+//! swap in fakes that return programmed values, exercising the buffering and
+//! scoring logic without the `.onnx` files.
+//!
+//! Converted models don't always output f32; [`extract_f32`] also accepts
+//! f16 and f64 outputs and converts them.
+
+use half::f16;
+use ort::session::Session;
+use ort::tensor::TensorElementType;
+use ort::value::{DynValue, Tensor, ValueType};
+
+use super::inference_cancel::InferenceCanceller;
+use super::wake_word::WakeWordError;
+
+/// Model taking one f32 tensor and returning its first output, flattened
+pub trait InferenceModel: Send {
+    fn run(&mut self, shape: &[usize], input: Vec<f32>, canceller: &InferenceCanceller) -> Result<Vec<f32>, WakeWordError>;
+}
+
+/// ONNX session for one stage of the pipeline, named in its errors
+pub struct OnnxModel {
+    session: Session,
+    stage: &'static str,
+}
+
+impl OnnxModel {
+    pub fn new(session: Session, stage: &'static str) -> Self {
+        Self { session, stage }
+    }
+}
+
+impl InferenceModel for OnnxModel {
+    fn run(&mut self, shape: &[usize], input: Vec<f32>, canceller: &InferenceCanceller) -> Result<Vec<f32>, WakeWordError> {
+        let input_tensor = Tensor::from_array((shape.to_vec(), input))
+            .map_err(|e| WakeWordError::InferenceError(e.to_string()))?;
+
+        let outputs = self
+            .session
+            .run_with_options(ort::inputs![input_tensor], &canceller.run_options)
+            .map_err(|e| canceller.run_error(e))?;
+
+        extract_f32(&outputs[0], self.stage)
+    }
+}
+
+/// Flattened contents of a float tensor output, converted to f32
+///
+/// Errors name `stage` and the actual type when the output isn't an f16, f32
+/// or f64 tensor.
+pub fn extract_f32(output: &DynValue, stage: &str) -> Result<Vec<f32>, WakeWordError> {
+    let to_error = |e: ort::Error| WakeWordError::InferenceError(format!("{} output: {}", stage, e));
+    let element_type = match output.dtype() {
+        ValueType::Tensor { ty, .. } => *ty,
+        other => {
+            return Err(WakeWordError::InferenceError(format!(
+                "{} output is a {}, expected a float tensor",
+                stage, other
+            )))
+        }
+    };
+
+    match element_type {
+        TensorElementType::Float32 => Ok(output.try_extract_tensor::<f32>().map_err(to_error)?.1.to_vec()),
+        TensorElementType::Float16 => {
+            let (_, data) = output.try_extract_tensor::<f16>().map_err(to_error)?;
+            Ok(data.iter().map(|value| value.to_f32()).collect())
+        }
+        TensorElementType::Float64 => {
+            let (_, data) = output.try_extract_tensor::<f64>().map_err(to_error)?;
+            Ok(data.iter().map(|&value| value as f32).collect())
+        }
+        other => Err(WakeWordError::InferenceError(format!(
+            "{} output has element type {}, expected f32, f16 or f64",
+            stage, other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_half_and_double_outputs_are_converted() {
+        let values = [0.5, -1.0, 0.25].map(f16::from_f32).to_vec();
+        let half = Tensor::from_array((vec![1_usize, 3], values)).unwrap().into_dyn();
+        assert_eq!(extract_f32(&half, "classifier").unwrap(), vec![0.5, -1.0, 0.25]);
+
+        let double = Tensor::from_array((vec![2_usize], vec![0.75_f64, 2.0])).unwrap().into_dyn();
+        assert_eq!(extract_f32(&double, "embedding").unwrap(), vec![0.75, 2.0]);
+    }
+
+    #[test]
+    fn test_non_float_output_names_stage_and_type() {
+        let ints = Tensor::from_array((vec![1_usize], vec![7_i64])).unwrap().into_dyn();
+        let message = extract_f32(&ints, "melspectrogram").unwrap_err().to_string();
+        assert!(message.contains("melspectrogram"), "{}", message);
+        assert!(message.contains("i64"), "{}", message);
+    }
+}
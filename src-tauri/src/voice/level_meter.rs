@@ -0,0 +1,115 @@
+//! Smoothed, rate-limited input level for the frontend meter
+//!
+//! Raw per-chunk RMS jumps around and, with small chunks, would flood the
+//! event bus. The meter keeps an exponential moving average of the RMS and the
+//! highest peak since the last report, and reports at most once per interval
+//! of capture time.
+
+use serde::Serialize;
+
+use super::dsp::{calculate_dbfs, calculate_peak, calculate_rms};
+
+/// Weight of the newest chunk in the smoothed RMS
+const LEVEL_SMOOTHING: f32 = 0.3;
+
+/// Payload of `voice-audio-level`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AudioLevel {
+    /// Smoothed RMS
+    pub rms: f32,
+    /// Highest absolute sample since the previous report
+    pub peak: f32,
+    /// `rms` in dBFS
+    pub dbfs: f32,
+}
+
+/// Limits a per-chunk event to one per `interval_ms` of capture time
+#[derive(Debug, Clone)]
+pub struct EmitThrottle {
+    interval_ms: f64,
+    last_emit_ms: Option<f64>,
+}
+
+impl EmitThrottle {
+    pub fn new(interval_ms: u64) -> Self {
+        Self {
+            interval_ms: interval_ms as f64,
+            last_emit_ms: None,
+        }
+    }
+
+    /// Whether an event for audio captured at `timestamp_ms` should go out
+    pub fn due(&mut self, timestamp_ms: f64) -> bool {
+        if self.last_emit_ms.is_some_and(|last| timestamp_ms - last < self.interval_ms) {
+            return false;
+        }
+        self.last_emit_ms = Some(timestamp_ms);
+        true
+    }
+}
+
+/// Input level meter reporting at most every `interval_ms`
+#[derive(Debug, Clone)]
+pub struct LevelMeter {
+    throttle: EmitThrottle,
+    rms: Option<f32>,
+    peak: f32,
+}
+
+impl LevelMeter {
+    pub fn new(interval_ms: u64) -> Self {
+        Self {
+            throttle: EmitThrottle::new(interval_ms),
+            rms: None,
+            peak: 0.0,
+        }
+    }
+
+    /// Fold in a chunk captured at `timestamp_ms`, returning a level when one is due
+    pub fn update(&mut self, samples: &[f32], timestamp_ms: f64) -> Option<AudioLevel> {
+        let rms = calculate_rms(samples);
+        let smoothed = self.rms.map_or(rms, |previous| previous + LEVEL_SMOOTHING * (rms - previous));
+        self.rms = Some(smoothed);
+        self.peak = self.peak.max(calculate_peak(samples));
+
+        if !self.throttle.due(timestamp_ms) {
+            return None;
+        }
+        let level = AudioLevel {
+            rms: smoothed,
+            peak: self.peak,
+            dbfs: calculate_dbfs(smoothed),
+        };
+        self.peak = 0.0;
+        Some(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rapid_chunks_are_throttled() {
+        let mut meter = LevelMeter::new(50);
+        // One second of 10ms chunks
+        let emitted = (0..100)
+            .filter_map(|i| meter.update(&[0.1; 160], i as f64 * 10.0))
+            .count();
+        assert_eq!(emitted, 20);
+    }
+
+    #[test]
+    fn test_level_is_smoothed_and_keeps_peaks() {
+        let mut meter = LevelMeter::new(0);
+        assert!((meter.update(&[0.1; 160], 0.0).unwrap().rms - 0.1).abs() < 1e-6);
+
+        let level = meter.update(&[0.5; 160], 10.0).unwrap();
+        assert!(level.rms > 0.1 && level.rms < 0.5, "rms {}", level.rms);
+
+        let mut meter = LevelMeter::new(50);
+        meter.update(&[0.1; 160], 0.0);
+        meter.update(&[0.9; 160], 10.0);
+        assert_eq!(meter.update(&[0.1; 160], 50.0).unwrap().peak, 0.9);
+    }
+}
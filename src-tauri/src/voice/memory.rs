@@ -0,0 +1,46 @@
+//! Memory retained by the voice subsystem
+
+use serde::Serialize;
+use std::mem::size_of;
+
+/// Sizes of the buffers the voice subsystem keeps between chunks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MemoryReport {
+    /// Pre-roll audio kept on the processing thread
+    pub rolling_buffer_bytes: usize,
+    /// Audio captured for the current or most recent utterance
+    pub last_capture_bytes: usize,
+    /// In-memory total of the buffers above
+    pub total_retained_bytes: usize,
+    /// Bytes the active session recording has written to disk, not counted in the total
+    pub session_recording_disk_bytes: usize,
+}
+
+impl MemoryReport {
+    pub fn new(rolling_buffer_bytes: usize, last_capture_bytes: usize, session_recording_disk_bytes: usize) -> Self {
+        Self {
+            rolling_buffer_bytes,
+            last_capture_bytes,
+            total_retained_bytes: rolling_buffer_bytes + last_capture_bytes,
+            session_recording_disk_bytes,
+        }
+    }
+}
+
+/// Bytes held by `capacity` f32 samples
+pub fn sample_bytes(capacity: usize) -> usize {
+    capacity * size_of::<f32>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_total_sums_buffers() {
+        let report = MemoryReport::new(sample_bytes(4800), sample_bytes(16000), 1024);
+        assert_eq!(report.rolling_buffer_bytes, 19_200);
+        assert_eq!(report.total_retained_bytes, 19_200 + 64_000);
+        assert_eq!(report.session_recording_disk_bytes, 1024);
+    }
+}
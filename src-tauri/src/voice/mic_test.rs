@@ -0,0 +1,146 @@
+//! One-shot microphone check for onboarding
+//!
+//! Captures a few seconds from an input device outside any voice session and
+//! reports levels, so the UI can tell the user whether the mic is too quiet,
+//! clipping or working.
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::error::TryRecvError;
+
+use super::audio_capture::AudioCaptureError;
+use super::controller_state::VoiceControllerState;
+use super::audio_source::AudioSource;
+use super::capture_quality::CLIP_LEVEL;
+use super::chunk::{audio_channel, AudioChunk};
+use super::config::VoiceConfig;
+use super::dsp::{calculate_peak, calculate_rms};
+
+/// Longest test the caller can ask for
+pub const MAX_TEST_SECONDS: f32 = 10.0;
+
+/// Peak below which the input counts as silent (-60 dBFS)
+const SILENT_PEAK: f32 = 0.001;
+
+/// Extra time allowed for the device to start delivering audio
+const START_GRACE: Duration = Duration::from_secs(2);
+
+/// How often the collector checks for new chunks
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Levels heard during a microphone test
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MicTestReport {
+    /// Highest absolute sample
+    pub peak: f32,
+    /// Mean of the per-chunk RMS levels
+    pub mean_rms: f32,
+    /// Samples at full scale
+    pub clipped_samples: usize,
+    /// Nothing above the noise floor was heard (or no audio arrived at all)
+    pub silent: bool,
+}
+
+impl MicTestReport {
+    pub fn from_chunks(chunks: &[AudioChunk]) -> Self {
+        let peak = chunks.iter().map(|chunk| calculate_peak(&chunk.samples)).fold(0.0, f32::max);
+        let mean_rms = if chunks.is_empty() {
+            0.0
+        } else {
+            chunks.iter().map(|chunk| calculate_rms(&chunk.samples)).sum::<f32>() / chunks.len() as f32
+        };
+        let clipped_samples = chunks
+            .iter()
+            .flat_map(|chunk| &chunk.samples)
+            .filter(|sample| sample.abs() >= CLIP_LEVEL)
+            .count();
+
+        Self {
+            peak,
+            mean_rms,
+            clipped_samples,
+            silent: peak < SILENT_PEAK,
+        }
+    }
+}
+
+/// Capture about `seconds` of audio from `source` and report its levels
+///
+/// Blocks until the audio arrives, the source runs dry, or the device fails to
+/// deliver within a grace period. Uses its own stream, so it must not be run
+/// while a voice session holds the same device.
+pub fn test_microphone(
+    source: &dyn AudioSource,
+    config: &VoiceConfig,
+    input_device: Option<String>,
+    seconds: f32,
+) -> Result<MicTestReport, AudioCaptureError> {
+    let seconds = seconds.clamp(0.1, MAX_TEST_SECONDS);
+    let wanted = (seconds * config.sample_rate as f32) as usize;
+    let (audio_tx, mut audio_rx) = audio_channel(config.audio_queue_capacity);
+    let stop = Arc::new(AtomicBool::new(false));
+    let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+    source.start(None, state, config.clone(), input_device, audio_tx, stop.clone())?;
+
+    let deadline = Instant::now() + Duration::from_secs_f32(seconds) + START_GRACE;
+    let mut chunks = Vec::new();
+    let mut received = 0;
+    while received < wanted && Instant::now() < deadline {
+        match audio_rx.try_recv() {
+            Ok(chunk) => {
+                received += chunk.samples.len();
+                chunks.push(chunk);
+            }
+            Err(TryRecvError::Empty) => thread::sleep(POLL_INTERVAL),
+            Err(TryRecvError::Disconnected) => break,
+        }
+    }
+    stop.store(true, Ordering::SeqCst);
+
+    Ok(MicTestReport::from_chunks(&chunks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::audio_source::FileAudioSource;
+    use crate::voice::wav::write_wav;
+
+    fn test_file(name: &str, samples: &[f32]) -> Result<MicTestReport, AudioCaptureError> {
+        let path = std::env::temp_dir().join(format!("jarvis-mic-{}-{}.wav", name, std::process::id()));
+        write_wav(&path, samples, 16000).unwrap();
+        let report = test_microphone(&FileAudioSource::new(&path, false), &VoiceConfig::default(), None, 3.0);
+        let _ = std::fs::remove_file(&path);
+        report
+    }
+
+    #[test]
+    fn test_reports_levels_of_captured_audio() {
+        // 4 seconds at half scale, starting with a clipped burst
+        let mut samples: Vec<f32> = (0..64000).map(|i| if i % 2 == 0 { 0.5 } else { -0.5 }).collect();
+        samples[..100].fill(1.0);
+
+        let report = test_file("levels", &samples).unwrap();
+        assert!((report.peak - 1.0).abs() < 1e-3);
+        assert!((report.mean_rms - 0.5).abs() < 0.01, "mean rms {}", report.mean_rms);
+        assert_eq!(report.clipped_samples, 100);
+        assert!(!report.silent);
+    }
+
+    #[test]
+    fn test_silent_input_is_flagged() {
+        let report = test_file("silent", &[0.0; 16000]).unwrap();
+        assert!(report.silent);
+        assert_eq!(report.clipped_samples, 0);
+    }
+
+    #[test]
+    fn test_missing_file_is_an_error() {
+        let source = FileAudioSource::new("does-not-exist.wav", false);
+        assert!(test_microphone(&source, &VoiceConfig::default(), None, 1.0).is_err());
+    }
+}
@@ -1,24 +1,78 @@
 //! Voice module - wake word detection, audio capture, and state management
 
+pub mod accessibility;
+pub mod adaptive_threshold;
+pub mod agc;
 pub mod audio_capture;
 pub mod audio_processing;
+pub mod audio_source;
+pub mod barge_in;
 pub mod buffer;
+pub mod capture_quality;
+pub mod capture_thread;
+pub mod chunk;
+pub mod classifiers;
+pub mod command_words;
 pub mod config;
+pub mod config_schema;
+pub mod config_store;
+pub mod config_validation;
+pub mod control;
 pub mod controller;
+pub mod controller_state;
+pub mod cooldown;
+pub mod cpu_usage;
+pub mod device_monitor;
+pub mod device_prefs;
+pub mod downmix;
+pub mod dsp;
+pub mod event_history;
+pub mod events;
+pub mod filters;
+pub mod inference_cancel;
+pub mod inference_model;
+pub mod level_meter;
+pub mod memory;
+pub mod model_manifest;
+pub mod mic_test;
+pub mod model_shapes;
+pub mod noise_calibration;
+pub mod noise_profile;
+pub mod playback;
+pub mod priority;
+pub mod profiles;
+pub mod resample;
+pub mod score_history;
+pub mod schedule;
+pub mod session_recording;
+pub mod silence_trim;
+pub mod silero_vad;
+pub mod simulation;
+pub mod state_handlers;
 pub mod state_machine;
+pub mod states;
+pub mod stt;
+pub mod tts_playback;
 pub mod vad;
+pub mod wake_phrases;
 pub mod wake_word;
+pub mod wake_word_labels;
+pub mod wake_word_models;
+pub mod wake_word_stats;
+pub mod watchdog;
+pub mod wav;
 
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 use thiserror::Error;
 
 pub use audio_capture::{list_input_devices, list_output_devices, AudioCapture, AudioDeviceInfo};
-pub use config::VoiceConfig;
+pub use config::{ModelFiles, VoiceConfig};
 pub use controller::VoiceController;
 pub use state_machine::{VoiceEvent, VoiceState, VoiceStateMachine};
 
 use audio_capture::AudioCaptureError;
+use profiles::ProfileError;
 use wake_word::WakeWordError;
 
 #[derive(Error, Debug)]
@@ -29,12 +83,24 @@ pub enum VoiceError {
     WakeWord(#[from] WakeWordError),
     #[error("Voice system not initialized")]
     NotInitialized,
+    #[error("No audio input device available")]
+    NoInputDevice,
     #[error("Models not found at: {0}")]
     ModelsNotFound(String),
+    #[error("Wake word model not found at: {0}")]
+    WakeWordModelMissing(String),
+    #[error("TTS audio can only be played while speaking")]
+    NotSpeaking,
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Profile error: {0}")]
+    Profile(#[from] ProfileError),
 }
 
-/// Events emitted to the frontend
-#[derive(Clone, serde::Serialize)]
+/// Events emitted to the frontend, also published to [`VoiceController::subscribe`] receivers
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 #[serde(tag = "type", content = "payload")]
 pub enum VoiceFrontendEvent {
     /// Voice state changed
@@ -44,15 +110,18 @@ pub enum VoiceFrontendEvent {
     /// Error occurred
     Error { message: String },
     /// Audio level update (for visualization)
-    AudioLevel { rms: f32 },
+    AudioLevel { rms: f32, peak: f32, dbfs: f32 },
 }
 
 /// Get the models directory from app handle
-pub fn get_models_dir(app: &AppHandle) -> PathBuf {
+///
+/// Candidate directories are recognized by the presence of the configured
+/// melspectrogram model.
+pub fn get_models_dir(app: &AppHandle, model_files: &ModelFiles) -> PathBuf {
     // First try the resource directory (for production builds)
     if let Ok(resource_dir) = app.path().resource_dir() {
         let models_path = resource_dir.join("models");
-        if models_path.exists() && models_path.join("melspectrogram.onnx").exists() {
+        if models_path.exists() && model_files.melspec_path(&models_path).exists() {
             return models_path;
         }
     }
@@ -66,7 +135,7 @@ pub fn get_models_dir(app: &AppHandle) -> PathBuf {
                 // target -> src-tauri
                 if let Some(src_tauri) = target_parent.parent() {
                     let dev_models = src_tauri.join("resources").join("models");
-                    if dev_models.exists() && dev_models.join("melspectrogram.onnx").exists() {
+                    if dev_models.exists() && model_files.melspec_path(&dev_models).exists() {
                         return dev_models;
                     }
                 }
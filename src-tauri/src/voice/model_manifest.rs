@@ -0,0 +1,191 @@
+//! Named wake word model bundles listed in `manifest.json`
+//!
+//! The manifest in the models directory lists each available wake word with
+//! its classifier and, optionally, its own melspectrogram and embedding
+//! models and threshold. Without a manifest, the default filenames in
+//! [`ModelFiles`] are the only bundle.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
+
+use super::config::{ModelFiles, VoiceConfig};
+use super::wake_word_models::primary_wake_word_name;
+
+/// Manifest filename inside the models directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Error, Debug)]
+pub enum ManifestError {
+    #[error("Invalid model manifest: {0}")]
+    Invalid(String),
+    #[error("Unknown wake word model: {0}")]
+    UnknownModel(String),
+}
+
+/// One wake word bundle in the manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Name shown to the user and used to select the bundle
+    pub name: String,
+    /// Melspectrogram model (`None` = the shared default)
+    #[serde(default)]
+    pub melspec: Option<String>,
+    /// Embedding model (`None` = the shared default)
+    #[serde(default)]
+    pub embedding: Option<String>,
+    /// Wake word classifier
+    pub classifier: String,
+    /// Base detection threshold (`None` = keep the config's)
+    #[serde(default)]
+    pub threshold: Option<f32>,
+}
+
+impl ManifestEntry {
+    /// Point `config` at this bundle's models and threshold
+    pub fn apply(&self, config: &mut VoiceConfig) {
+        let defaults = ModelFiles::default();
+        config.model_files = ModelFiles {
+            melspec: self.melspec.clone().unwrap_or(defaults.melspec),
+            embedding: self.embedding.clone().unwrap_or(defaults.embedding),
+            wakeword: self.classifier.clone(),
+        };
+        if let Some(threshold) = self.threshold {
+            config.wake_word_threshold = threshold;
+        }
+    }
+}
+
+/// Available wake word bundles
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelManifest {
+    pub wake_words: Vec<ManifestEntry>,
+}
+
+impl ModelManifest {
+    pub fn parse(json: &str) -> Result<Self, ManifestError> {
+        serde_json::from_str(json).map_err(|e| ManifestError::Invalid(e.to_string()))
+    }
+
+    /// Read the manifest from `models_dir`, or `None` if there isn't one
+    pub fn load(models_dir: &Path) -> Result<Option<Self>, ManifestError> {
+        let path = models_dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = std::fs::read_to_string(&path).map_err(|e| ManifestError::Invalid(e.to_string()))?;
+        Self::parse(&json).map(Some)
+    }
+
+    /// Names of the listed wake words, in manifest order
+    pub fn names(&self) -> Vec<String> {
+        self.wake_words.iter().map(|entry| entry.name.clone()).collect()
+    }
+
+    /// Look up a wake word bundle by name
+    pub fn entry(&self, name: &str) -> Result<&ManifestEntry, ManifestError> {
+        self.wake_words
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| ManifestError::UnknownModel(name.to_string()))
+    }
+}
+
+/// Names of the wake words available in `models_dir`
+///
+/// Without a readable manifest this is just the default classifier's name.
+pub fn list_wake_word_models(models_dir: &Path) -> Vec<String> {
+    match ModelManifest::load(models_dir) {
+        Ok(Some(manifest)) => manifest.names(),
+        Ok(None) => vec![primary_wake_word_name(&ModelFiles::default())],
+        Err(e) => {
+            log::warn!("Ignoring model manifest: {}", e);
+            vec![primary_wake_word_name(&ModelFiles::default())]
+        }
+    }
+}
+
+/// `config` switched to the named wake word bundle
+///
+/// Without a manifest, only the default classifier's name is accepted and the
+/// config is returned unchanged.
+pub fn config_for_model(models_dir: &Path, name: &str, mut config: VoiceConfig) -> Result<VoiceConfig, ManifestError> {
+    match ModelManifest::load(models_dir)? {
+        Some(manifest) => manifest.entry(name)?.apply(&mut config),
+        None if name == primary_wake_word_name(&ModelFiles::default()) => {}
+        None => return Err(ManifestError::UnknownModel(name.to_string())),
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const SAMPLE: &str = r#"{
+        "wake_words": [
+            { "name": "hey_jarvis", "classifier": "hey_jarvis.onnx" },
+            {
+                "name": "computer",
+                "melspec": "computer/melspectrogram.onnx",
+                "embedding": "computer/embedding_model.onnx",
+                "classifier": "computer/computer.onnx",
+                "threshold": 0.7
+            }
+        ]
+    }"#;
+
+    fn temp_models_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jarvis-manifest-{}-{}", label, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_sample_manifest() {
+        let manifest = ModelManifest::parse(SAMPLE).unwrap();
+        assert_eq!(manifest.names(), vec!["hey_jarvis", "computer"]);
+
+        let mut config = VoiceConfig::default();
+        manifest.entry("computer").unwrap().apply(&mut config);
+        assert_eq!(config.model_files.melspec, "computer/melspectrogram.onnx");
+        assert_eq!(config.model_files.wakeword, "computer/computer.onnx");
+        assert_eq!(config.wake_word_threshold, 0.7);
+
+        let mut config = VoiceConfig::default();
+        manifest.entry("hey_jarvis").unwrap().apply(&mut config);
+        assert_eq!(config.model_files, ModelFiles::default());
+        assert_eq!(config.wake_word_threshold, VoiceConfig::default().wake_word_threshold);
+    }
+
+    #[test]
+    fn test_unknown_name_is_an_error() {
+        let manifest = ModelManifest::parse(SAMPLE).unwrap();
+        assert!(matches!(manifest.entry("alexa"), Err(ManifestError::UnknownModel(name)) if name == "alexa"));
+        assert!(matches!(ModelManifest::parse("{}"), Err(ManifestError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_select_model_from_directory() {
+        let models_dir = temp_models_dir("select");
+        std::fs::write(models_dir.join(MANIFEST_FILE), SAMPLE).unwrap();
+
+        assert_eq!(list_wake_word_models(&models_dir), vec!["hey_jarvis", "computer"]);
+        let config = config_for_model(&models_dir, "computer", VoiceConfig::default()).unwrap();
+        assert_eq!(config.model_files.wakeword_path(&models_dir), models_dir.join("computer/computer.onnx"));
+        let _ = std::fs::remove_dir_all(&models_dir);
+    }
+
+    #[test]
+    fn test_without_manifest_falls_back_to_defaults() {
+        let models_dir = temp_models_dir("fallback");
+
+        assert_eq!(list_wake_word_models(&models_dir), vec!["hey_jarvis"]);
+        let config = config_for_model(&models_dir, "hey_jarvis", VoiceConfig::default()).unwrap();
+        assert_eq!(config.model_files, ModelFiles::default());
+        assert!(config_for_model(&models_dir, "computer", VoiceConfig::default()).is_err());
+        let _ = std::fs::remove_dir_all(&models_dir);
+    }
+}
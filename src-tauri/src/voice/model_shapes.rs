@@ -0,0 +1,74 @@
+//! Shape checks between the melspectrogram and embedding models
+//!
+//! Custom OpenWakeWord models may be trained with a different feature size.
+//! Reading it from the model metadata, and refusing incompatible pairs, avoids
+//! silently truncating features into garbage scores.
+
+use ort::session::Session;
+
+use super::wake_word::WakeWordError;
+
+/// Declared shape of a session's first input (`-1` for dynamic dimensions)
+pub fn first_input_shape(session: &Session) -> Option<Vec<i64>> {
+    let input = session.inputs().first()?;
+    input.dtype().tensor_shape().map(|shape| shape.to_vec())
+}
+
+/// Declared shape of a session's first output (`-1` for dynamic dimensions)
+pub fn first_output_shape(session: &Session) -> Option<Vec<i64>> {
+    let output = session.outputs().first()?;
+    output.dtype().tensor_shape().map(|shape| shape.to_vec())
+}
+
+/// Number of mel bands from the melspectrogram output shape `[.., frames, bands]`
+pub fn mel_bands_from_output(shape: &[i64]) -> Result<usize, WakeWordError> {
+    match shape.last() {
+        Some(&bands) if bands > 0 => Ok(bands as usize),
+        _ => Err(WakeWordError::ModelLoadError(format!(
+            "melspectrogram output shape {:?} has no fixed mel band dimension",
+            shape
+        ))),
+    }
+}
+
+/// Check the embedding input `[batch, frames, bands, ..]` accepts the configured features
+pub fn validate_embedding_input(shape: &[i64], mel_frame_count: usize, mel_bands: usize) -> Result<(), WakeWordError> {
+    if let Some(&frames) = shape.get(1) {
+        if frames > 0 && frames as usize != mel_frame_count {
+            return Err(WakeWordError::ModelLoadError(format!(
+                "embedding model expects {} frames (input shape {:?}) but mel_frame_count is {}",
+                frames, shape, mel_frame_count
+            )));
+        }
+    }
+    if let Some(&bands) = shape.get(2) {
+        if bands > 0 && bands as usize != mel_bands {
+            return Err(WakeWordError::ModelLoadError(format!(
+                "embedding model expects {} mel bands (input shape {:?}) but the melspectrogram model produces {}",
+                bands, shape, mel_bands
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mel_bands_from_output() {
+        assert_eq!(mel_bands_from_output(&[1, 1, -1, 32]).unwrap(), 32);
+        assert!(mel_bands_from_output(&[1, 1, -1, -1]).is_err());
+    }
+
+    #[test]
+    fn test_embedding_input_mismatch() {
+        assert!(validate_embedding_input(&[-1, 76, 32, 1], 76, 32).is_ok());
+        assert!(validate_embedding_input(&[-1, -1, 32, 1], 50, 32).is_ok());
+
+        let err = validate_embedding_input(&[-1, 76, 32, 1], 76, 40).unwrap_err();
+        assert!(err.to_string().contains("32 mel bands"), "{}", err);
+        assert!(validate_embedding_input(&[-1, 76, 32, 1], 60, 32).is_err());
+    }
+}
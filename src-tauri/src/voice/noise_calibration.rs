@@ -0,0 +1,126 @@
+//! Noise floor calibration for the energy VAD
+//!
+//! Records a couple of seconds of ambient audio while the user stays quiet and
+//! sets `silence_threshold` a fixed margin above the measured floor, so the
+//! threshold doesn't need hand-tuning for every room.
+
+use serde::Serialize;
+use thiserror::Error;
+
+/// Multiple of the noise floor RMS used as the silence threshold
+pub const CALIBRATION_MARGIN: f32 = 2.5;
+
+/// Peak above which the recording is assumed to contain speech or a bump
+pub const CALIBRATION_MAX_PEAK: f32 = 0.3;
+
+/// Lowest threshold calibration sets, so a near-silent room still needs real speech
+const MIN_CALIBRATED_THRESHOLD: f32 = 0.002;
+
+/// Range requested calibration durations are clamped to, in ms
+const CALIBRATION_DURATION_MS: (u64, u64) = (100, 10_000);
+
+/// Payload of `voice-calibration-complete`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct NoiseCalibration {
+    /// RMS of the ambient recording
+    pub noise_floor: f32,
+    /// Highest absolute sample in the recording
+    pub peak: f32,
+    /// `silence_threshold` derived from the floor
+    pub silence_threshold: f32,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CalibrationError {
+    #[error("Too loud to calibrate (peak {0:.2}); stay quiet and try again")]
+    TooLoud(f32),
+}
+
+/// Accumulates ambient audio until the requested duration is reached
+#[derive(Debug, Clone)]
+pub struct NoiseCalibrator {
+    target_samples: usize,
+    samples: usize,
+    sum_squares: f64,
+    peak: f32,
+}
+
+impl NoiseCalibrator {
+    /// Calibrate over `duration_ms`, clamped to 0.1-10s
+    pub fn new(duration_ms: u64, sample_rate: u32) -> Self {
+        let (min_ms, max_ms) = CALIBRATION_DURATION_MS;
+        let duration_ms = duration_ms.clamp(min_ms, max_ms);
+        Self {
+            target_samples: ((duration_ms * sample_rate as u64 / 1000) as usize).max(1),
+            samples: 0,
+            sum_squares: 0.0,
+            peak: 0.0,
+        }
+    }
+
+    /// Add a chunk, returning the outcome once the requested duration is recorded
+    pub fn push(&mut self, samples: &[f32]) -> Option<Result<NoiseCalibration, CalibrationError>> {
+        for &sample in samples {
+            self.sum_squares += (sample as f64) * (sample as f64);
+            self.peak = self.peak.max(sample.abs());
+        }
+        self.samples += samples.len();
+        if self.samples < self.target_samples {
+            return None;
+        }
+        if self.peak > CALIBRATION_MAX_PEAK {
+            return Some(Err(CalibrationError::TooLoud(self.peak)));
+        }
+
+        let noise_floor = (self.sum_squares / self.samples as f64).sqrt() as f32;
+        Some(Ok(NoiseCalibration {
+            noise_floor,
+            peak: self.peak,
+            silence_threshold: (noise_floor * CALIBRATION_MARGIN).clamp(MIN_CALIBRATED_THRESHOLD, 1.0),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold_is_margin_above_floor() {
+        let mut calibrator = NoiseCalibrator::new(2000, 16000);
+        // Steady hum at RMS 0.01
+        let ambient: Vec<f32> = (0..1280).map(|i| if i % 2 == 0 { 0.01 } else { -0.01 }).collect();
+
+        let mut result = None;
+        for _ in 0..25 {
+            assert!(result.is_none(), "finished early");
+            result = calibrator.push(&ambient);
+        }
+
+        let calibration = result.unwrap().unwrap();
+        assert!((calibration.noise_floor - 0.01).abs() < 1e-6);
+        assert!((calibration.silence_threshold - 0.025).abs() < 1e-6);
+        assert_eq!(calibration.peak, 0.01);
+    }
+
+    #[test]
+    fn test_speech_during_calibration_is_rejected() {
+        let mut calibrator = NoiseCalibrator::new(100, 16000);
+        calibrator.push(&[0.01; 800]);
+        let result = calibrator.push(&[0.6; 800]).unwrap();
+        assert_eq!(result, Err(CalibrationError::TooLoud(0.6)));
+    }
+
+    #[test]
+    fn test_silent_room_uses_minimum_threshold() {
+        let mut calibrator = NoiseCalibrator::new(100, 16000);
+        let calibration = calibrator.push(&[0.0; 1600]).unwrap().unwrap();
+        assert_eq!(calibration.silence_threshold, MIN_CALIBRATED_THRESHOLD);
+    }
+
+    #[test]
+    fn test_duration_is_clamped() {
+        assert_eq!(NoiseCalibrator::new(0, 16000).target_samples, 1600);
+        assert_eq!(NoiseCalibrator::new(u64::MAX / 16000, 16000).target_samples, 160_000);
+    }
+}
@@ -0,0 +1,289 @@
+//! Spectral subtraction of a recorded ambient noise profile
+//!
+//! A few seconds of steady-state noise (fans, air conditioning) are averaged
+//! into a magnitude spectrum. Incoming audio is then processed in 50%
+//! overlapping frames, subtracting that spectrum from each frame's magnitude
+//! while keeping its phase. Output lags the input by one frame.
+
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use super::config::VoiceConfig;
+use super::filters::AudioFilter;
+
+/// Analysis frame length in samples (32ms at 16kHz)
+pub const NOISE_FRAME_SIZE: usize = 512;
+
+/// Multiple of the noise spectrum removed from each frame
+const OVER_SUBTRACTION: f32 = 1.5;
+
+/// Fraction of each bin's magnitude always kept, to limit musical noise
+const SPECTRAL_FLOOR: f32 = 0.05;
+
+/// Longest noise profile recording, bounding the samples held until it completes
+const MAX_PROFILE_MS: u64 = 10_000;
+
+/// Average magnitude spectrum of ambient noise
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoiseProfile {
+    pub sample_rate: u32,
+    pub frame_size: usize,
+    /// Mean magnitude of each of the `frame_size / 2 + 1` frequency bins
+    pub magnitudes: Vec<f32>,
+}
+
+impl NoiseProfile {
+    /// Average the spectrum of a noise recording, or `None` if it is shorter than one frame
+    pub fn from_samples(samples: &[f32], sample_rate: u32) -> Option<Self> {
+        let mut stft = Stft::new(NOISE_FRAME_SIZE);
+        let hop = NOISE_FRAME_SIZE / 2;
+        let mut magnitudes = vec![0.0; NOISE_FRAME_SIZE / 2 + 1];
+        let mut frames = 0;
+
+        for start in (0..samples.len().saturating_sub(NOISE_FRAME_SIZE - 1)).step_by(hop) {
+            let spectrum = stft.analyze(&samples[start..start + NOISE_FRAME_SIZE]);
+            for (sum, bin) in magnitudes.iter_mut().zip(spectrum.iter()) {
+                *sum += bin.norm();
+            }
+            frames += 1;
+        }
+        if frames == 0 {
+            return None;
+        }
+
+        for magnitude in magnitudes.iter_mut() {
+            *magnitude /= frames as f32;
+        }
+        Some(Self {
+            sample_rate,
+            frame_size: NOISE_FRAME_SIZE,
+            magnitudes,
+        })
+    }
+}
+
+/// Collects ambient audio until enough has been heard to build a profile
+#[derive(Debug)]
+pub struct NoiseProfileRecorder {
+    sample_rate: u32,
+    target_samples: usize,
+    samples: Vec<f32>,
+}
+
+impl NoiseProfileRecorder {
+    /// Record `duration_ms` of audio, at most 10s
+    pub fn new(duration_ms: u64, sample_rate: u32) -> Self {
+        let target_samples = (duration_ms.min(MAX_PROFILE_MS) * sample_rate as u64 / 1000) as usize;
+        Self {
+            sample_rate,
+            target_samples: target_samples.max(NOISE_FRAME_SIZE),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Add a chunk, returning the profile once the requested duration is recorded
+    pub fn push(&mut self, samples: &[f32]) -> Option<NoiseProfile> {
+        self.samples.extend_from_slice(samples);
+        if self.samples.len() < self.target_samples {
+            return None;
+        }
+        NoiseProfile::from_samples(&self.samples, self.sample_rate)
+    }
+}
+
+/// Streaming spectral subtraction filter
+pub struct SpectralSubtractor {
+    stft: Stft,
+    noise: Vec<f32>,
+    /// Samples waiting to fill the next analysis frame
+    input: Vec<f32>,
+    /// Overlap-add accumulator for the frames in flight
+    overlap: Vec<f32>,
+    /// Reconstructed samples ready to be returned
+    output: VecDeque<f32>,
+}
+
+impl SpectralSubtractor {
+    pub fn new(profile: &NoiseProfile) -> Self {
+        let frame_size = profile.frame_size;
+        Self {
+            stft: Stft::new(frame_size),
+            noise: profile.magnitudes.clone(),
+            input: vec![0.0; frame_size / 2],
+            overlap: vec![0.0; frame_size],
+            output: VecDeque::from(vec![0.0; frame_size / 2]),
+        }
+    }
+
+    /// Build the filter when noise suppression is enabled and a matching profile is stored
+    pub fn from_config(config: &VoiceConfig) -> Option<Self> {
+        if !config.noise_suppression {
+            return None;
+        }
+        config
+            .noise_profile
+            .as_ref()
+            .filter(|profile| profile.sample_rate == config.sample_rate)
+            .map(Self::new)
+    }
+
+    /// Subtract the noise spectrum from one full frame and overlap-add it into the output
+    fn process_frame(&mut self) {
+        let frame_size = self.overlap.len();
+        let hop = frame_size / 2;
+
+        let spectrum = self.stft.analyze(&self.input[..frame_size]);
+        for (bin, &noise) in spectrum.iter_mut().zip(&self.noise) {
+            let magnitude = bin.norm();
+            if magnitude > 0.0 {
+                let target = (magnitude - OVER_SUBTRACTION * noise).max(SPECTRAL_FLOOR * magnitude);
+                *bin *= target / magnitude;
+            }
+        }
+
+        for (sum, &sample) in self.overlap.iter_mut().zip(self.stft.synthesize()) {
+            *sum += sample;
+        }
+        self.output.extend(self.overlap.drain(..hop));
+        self.overlap.resize(frame_size, 0.0);
+        self.input.drain(..hop);
+    }
+}
+
+impl AudioFilter for SpectralSubtractor {
+    fn process(&mut self, samples: &mut [f32]) {
+        self.input.extend_from_slice(samples);
+        while self.input.len() >= self.overlap.len() {
+            self.process_frame();
+        }
+        for sample in samples.iter_mut() {
+            *sample = self.output.pop_front().unwrap_or(0.0);
+        }
+    }
+
+    fn reset(&mut self) {
+        let frame_size = self.overlap.len();
+        self.input = vec![0.0; frame_size / 2];
+        self.overlap = vec![0.0; frame_size];
+        self.output = VecDeque::from(vec![0.0; frame_size / 2]);
+    }
+}
+
+/// Windowed real FFT with reusable buffers
+///
+/// Uses a periodic Hann window, which sums to one at 50% overlap so unmodified
+/// frames reconstruct the input exactly.
+struct Stft {
+    window: Vec<f32>,
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+    frame: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+}
+
+impl Stft {
+    fn new(frame_size: usize) -> Self {
+        let mut planner = RealFftPlanner::<f32>::new();
+        let forward = planner.plan_fft_forward(frame_size);
+        let inverse = planner.plan_fft_inverse(frame_size);
+        Self {
+            window: (0..frame_size)
+                .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / frame_size as f32).cos())
+                .collect(),
+            frame: forward.make_input_vec(),
+            spectrum: forward.make_output_vec(),
+            forward,
+            inverse,
+        }
+    }
+
+    /// Spectrum of one windowed frame of `frame_size` samples
+    fn analyze(&mut self, samples: &[f32]) -> &mut [Complex<f32>] {
+        for ((out, &sample), &weight) in self.frame.iter_mut().zip(samples).zip(&self.window) {
+            *out = sample * weight;
+        }
+        self.forward
+            .process(&mut self.frame, &mut self.spectrum)
+            .expect("FFT buffers match the plan");
+        &mut self.spectrum
+    }
+
+    /// Time-domain frame of the current (possibly modified) spectrum
+    fn synthesize(&mut self) -> &[f32] {
+        // The DC and Nyquist bins of a real signal have no imaginary part
+        let last = self.spectrum.len() - 1;
+        self.spectrum[0].im = 0.0;
+        self.spectrum[last].im = 0.0;
+        self.inverse
+            .process(&mut self.spectrum, &mut self.frame)
+            .expect("FFT buffers match the plan");
+
+        let scale = 1.0 / self.frame.len() as f32;
+        for sample in self.frame.iter_mut() {
+            *sample *= scale;
+        }
+        &self.frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::dsp::calculate_rms;
+
+    const SAMPLE_RATE: u32 = 16000;
+
+    /// Deterministic white noise
+    fn noise(len: usize, seed: u32) -> Vec<f32> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 8) as f32 / (1 << 24) as f32 * 0.2 - 0.1
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_silent_profile_passes_audio_through() {
+        let profile = NoiseProfile::from_samples(&vec![0.0; 4000], SAMPLE_RATE).unwrap();
+        let mut filter = SpectralSubtractor::new(&profile);
+        let input = noise(6400, 1);
+
+        let mut output = Vec::new();
+        for chunk in input.chunks(1280) {
+            let mut chunk = chunk.to_vec();
+            filter.process(&mut chunk);
+            output.extend(chunk);
+        }
+
+        // Output lags by one frame but is otherwise unchanged
+        let lag = NOISE_FRAME_SIZE;
+        for (out, expected) in output[lag..].iter().zip(&input) {
+            assert!((out - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_recorded_noise_is_suppressed() {
+        let mut recorder = NoiseProfileRecorder::new(2000, SAMPLE_RATE);
+        let recording = noise(32000, 7);
+        let profile = recording
+            .chunks(1280)
+            .find_map(|chunk| recorder.push(chunk))
+            .unwrap();
+
+        let mut filter = SpectralSubtractor::new(&profile);
+        let mut input = noise(32000, 42);
+        let before = calculate_rms(&input);
+        filter.process(&mut input);
+        let after = calculate_rms(&input[NOISE_FRAME_SIZE..]);
+
+        assert!(after < before * 0.5, "rms {} -> {}", before, after);
+    }
+}
@@ -0,0 +1,236 @@
+//! Playback of short audio clips and TTS audio on the output device
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, FromSample, SampleFormat, SizedSample, StreamConfig};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PlaybackError {
+    #[error("No output device available")]
+    NoOutputDevice,
+    #[error("Device not found: {0}")]
+    DeviceNotFound(String),
+    #[error("Failed to decode clip: {0}")]
+    Decode(String),
+    #[error("Failed to play clip: {0}")]
+    Stream(String),
+}
+
+/// Decode a WAV file into mono f32 samples and its sample rate
+pub fn read_wav_mono(path: &Path) -> Result<(Vec<f32>, u32), PlaybackError> {
+    let mut reader = hound::WavReader::open(path).map_err(|e| PlaybackError::Decode(e.to_string()))?;
+    let spec = reader.spec();
+    let channels = spec.channels.max(1) as usize;
+
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .collect::<Result<_, _>>()
+            .map_err(|e| PlaybackError::Decode(e.to_string()))?,
+        hound::SampleFormat::Int => {
+            let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()
+                .map_err(|e| PlaybackError::Decode(e.to_string()))?
+        }
+    };
+
+    let mono = interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+    Ok((mono, spec.sample_rate))
+}
+
+/// Linearly resample mono samples between rates (adequate for preview clips)
+pub fn resample_linear(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = from_rate as f64 / to_rate as f64;
+    let out_len = (samples.len() as f64 / ratio).round() as usize;
+    (0..out_len)
+        .map(|i| {
+            let pos = i as f64 * ratio;
+            let idx = pos.floor() as usize;
+            let frac = (pos - idx as f64) as f32;
+            let a = samples[idx.min(samples.len() - 1)];
+            let b = samples[(idx + 1).min(samples.len() - 1)];
+            a + (b - a) * frac
+        })
+        .collect()
+}
+
+fn find_output_device(device_name: Option<&str>) -> Result<Device, PlaybackError> {
+    let host = cpal::default_host();
+    match device_name {
+        Some(name) => host
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().map(|n| n == name).unwrap_or(false)))
+            .ok_or_else(|| PlaybackError::DeviceNotFound(name.to_string())),
+        None => host.default_output_device().ok_or(PlaybackError::NoOutputDevice),
+    }
+}
+
+/// The named device, or the default if it has disappeared
+fn output_device_or_default(device_name: Option<&str>) -> Result<Device, PlaybackError> {
+    match find_output_device(device_name) {
+        Err(PlaybackError::DeviceNotFound(name)) => {
+            log::warn!("Output device '{}' not found, using default", name);
+            find_output_device(None)
+        }
+        result => result,
+    }
+}
+
+/// Play mono samples on the given output device (or the default), blocking until drained
+///
+/// Falls back to the default device if the named one is no longer present.
+pub fn play_samples(device_name: Option<&str>, samples: &[f32], sample_rate: u32) -> Result<(), PlaybackError> {
+    play_samples_until(device_name, samples, sample_rate, &AtomicBool::new(false))
+}
+
+/// Like [`play_samples`], but returns early once `cancel` is set
+pub fn play_samples_until(
+    device_name: Option<&str>,
+    samples: &[f32],
+    sample_rate: u32,
+    cancel: &AtomicBool,
+) -> Result<(), PlaybackError> {
+    let device = output_device_or_default(device_name)?;
+    play_blocking(&device, samples, sample_rate, cancel)
+}
+
+/// Play a WAV clip on the given output device (or the default) without blocking
+///
+/// The clip is decoded up front so decode errors are reported to the caller;
+/// stream errors happen on the playback thread and are only logged.
+pub fn play_wav(path: &Path, device_name: Option<&str>) -> Result<(), PlaybackError> {
+    let (samples, sample_rate) = read_wav_mono(path)?;
+    let device = find_output_device(device_name)?;
+
+    thread::spawn(move || {
+        if let Err(e) = play_blocking(&device, &samples, sample_rate, &AtomicBool::new(false)) {
+            log::error!("Clip playback failed: {}", e);
+        }
+    });
+    Ok(())
+}
+
+fn play_blocking(
+    device: &Device,
+    samples: &[f32],
+    sample_rate: u32,
+    cancel: &AtomicBool,
+) -> Result<(), PlaybackError> {
+    let supported = device
+        .default_output_config()
+        .map_err(|e| PlaybackError::Stream(e.to_string()))?;
+
+    let config = supported.config();
+    let output_rate = config.sample_rate.0;
+    let clip = Arc::new(resample_linear(samples, sample_rate, output_rate));
+    let position = Arc::new(AtomicUsize::new(0));
+
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_output_stream::<f32>(device, &config, clip.clone(), position.clone())?,
+        SampleFormat::I16 => build_output_stream::<i16>(device, &config, clip.clone(), position.clone())?,
+        SampleFormat::U16 => build_output_stream::<u16>(device, &config, clip.clone(), position.clone())?,
+        _ => return Err(PlaybackError::Stream("Unsupported sample format".to_string())),
+    };
+    stream.play().map_err(|e| PlaybackError::Stream(e.to_string()))?;
+
+    // Keep the stream alive until the clip is consumed, giving up if the
+    // device stops pulling samples
+    let duration = Duration::from_secs_f64(clip.len() as f64 / output_rate as f64);
+    let deadline = Instant::now() + duration + Duration::from_secs(1);
+    while position.load(Ordering::Relaxed) < clip.len() && Instant::now() < deadline {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    // Let the device play out what it has buffered
+    thread::sleep(Duration::from_millis(200));
+    Ok(())
+}
+
+/// Build a stream that plays `clip` from `position` in the device's sample type
+fn build_output_stream<T>(
+    device: &Device,
+    config: &StreamConfig,
+    clip: Arc<Vec<f32>>,
+    position: Arc<AtomicUsize>,
+) -> Result<cpal::Stream, PlaybackError>
+where
+    T: SizedSample + FromSample<f32> + Send + 'static,
+{
+    let channels = config.channels as usize;
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| fill_output(data, channels, &clip, &position),
+            |err| log::error!("Playback error: {}", err),
+            None,
+        )
+        .map_err(|e| PlaybackError::Stream(e.to_string()))
+}
+
+/// Write the next frames of `clip` to every channel of `data`, padding with silence once it ends
+fn fill_output<T: FromSample<f32>>(data: &mut [T], channels: usize, clip: &[f32], position: &AtomicUsize) {
+    for frame in data.chunks_mut(channels) {
+        let index = position.fetch_add(1, Ordering::Relaxed);
+        let value = clip.get(index).copied().unwrap_or(0.0);
+        frame.fill_with(|| T::from_sample_(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_linear() {
+        let samples: Vec<f32> = (0..160).map(|i| i as f32).collect();
+        let resampled = resample_linear(&samples, 16000, 48000);
+        assert_eq!(resampled.len(), 480);
+        assert!((resampled[3] - 1.0).abs() < 1e-4);
+        assert_eq!(resample_linear(&samples, 16000, 16000), samples);
+    }
+
+    #[test]
+    fn test_output_is_converted_to_the_device_format() {
+        let clip = [0.5, -1.0];
+        let position = AtomicUsize::new(0);
+        let mut stereo = [0_i16; 6];
+        fill_output(&mut stereo, 2, &clip, &position);
+        assert_eq!(stereo, [16384, 16384, -32768, -32768, 0, 0]);
+
+        let position = AtomicUsize::new(1);
+        let mut mono = [0_u16; 2];
+        fill_output(&mut mono, 1, &clip, &position);
+        assert_eq!(mono, [0, 32768]);
+
+        let position = AtomicUsize::new(0);
+        let mut float = [0.0_f32; 2];
+        fill_output(&mut float, 1, &clip, &position);
+        assert_eq!(float, clip);
+    }
+
+    #[test]
+    #[ignore = "plays 10ms of silence on the default output device"]
+    fn test_play_samples_on_output_device() {
+        let samples = vec![0.0; 160];
+        // Either plays or reports a missing/unusable device, falling back from an unknown name
+        let _ = play_samples(None, &samples, 16000);
+        let _ = play_samples(Some("No Such Output Device"), &samples, 16000);
+    }
+}
@@ -0,0 +1,31 @@
+//! Scheduling priority for the audio threads
+//!
+//! Raising priority usually needs extra privileges (e.g. rtkit or
+//! `CAP_SYS_NICE` on Linux); without them the threads keep running at
+//! normal priority and the status records that elevation failed.
+
+use serde::Serialize;
+use thread_priority::{set_current_thread_priority, ThreadPriority};
+
+/// Whether high priority was requested and which threads obtained it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AudioPriorityStatus {
+    pub requested: bool,
+    pub processing_elevated: bool,
+    /// Whether cpal's capture callback thread, which delivers the audio, was raised
+    pub capture_elevated: bool,
+}
+
+/// Try to raise the calling thread to the highest priority, logging on failure
+pub fn elevate_current_thread(thread_name: &str) -> bool {
+    match set_current_thread_priority(ThreadPriority::Max) {
+        Ok(()) => {
+            log::info!("Raised {} thread priority", thread_name);
+            true
+        }
+        Err(e) => {
+            log::warn!("Could not raise {} thread priority, continuing at normal priority: {:?}", thread_name, e);
+            false
+        }
+    }
+}
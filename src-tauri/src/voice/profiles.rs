@@ -0,0 +1,185 @@
+//! Per-user voice profiles
+//!
+//! A profile is a directory under `<models_dir>/profiles/<name>/` holding that
+//! user's personalized models and an optional `profile.json` with tuning
+//! overrides. Any model file missing from the profile directory falls back to
+//! the shared one in the models directory.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use super::config::VoiceConfig;
+
+/// Subdirectory of the models directory that holds profiles
+pub const PROFILES_DIR: &str = "profiles";
+
+/// Optional tuning overrides file inside a profile directory
+pub const PROFILE_TUNING_FILE: &str = "profile.json";
+
+#[derive(Error, Debug)]
+pub enum ProfileError {
+    #[error("Profile not found: {0}")]
+    NotFound(String),
+    #[error("Invalid profile name: {0}")]
+    InvalidName(String),
+    #[error("Invalid tuning for profile {0}: {1}")]
+    InvalidTuning(String, String),
+}
+
+/// Tuning overrides read from `profile.json`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ProfileTuning {
+    pub wake_word_threshold: Option<f32>,
+    pub sensitivity: Option<f32>,
+    pub silence_threshold: Option<f32>,
+    pub silence_timeout_ms: Option<u64>,
+    /// Older profiles give the silence timeout in chunks; `silence_timeout_ms` wins if both are set
+    pub silence_frames_threshold: Option<usize>,
+}
+
+impl ProfileTuning {
+    /// Apply the overrides that are set to a config
+    pub fn apply(&self, config: &mut VoiceConfig) {
+        if let Some(threshold) = self.wake_word_threshold {
+            config.wake_word_threshold = threshold;
+        }
+        if let Some(sensitivity) = self.sensitivity {
+            config.sensitivity = sensitivity.clamp(0.1, 3.0);
+        }
+        if let Some(threshold) = self.silence_threshold {
+            config.silence_threshold = threshold;
+        }
+        if let Some(timeout_ms) = self.silence_timeout_ms {
+            config.silence_timeout_ms = timeout_ms;
+        } else if let Some(frames) = self.silence_frames_threshold {
+            config.silence_timeout_ms = config.chunks_to_ms(frames);
+        }
+    }
+}
+
+/// Directory holding a named profile
+pub fn profile_dir(models_dir: &Path, name: &str) -> PathBuf {
+    models_dir.join(PROFILES_DIR).join(name)
+}
+
+/// List available profile names, sorted
+pub fn list_profiles(models_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(models_dir.join(PROFILES_DIR))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Build the complete config for a profile
+///
+/// The profile's config is the default config with the profile's models and
+/// tuning overrides applied, so switching profiles swaps the whole config.
+pub fn load_profile_config(models_dir: &Path, name: &str) -> Result<VoiceConfig, ProfileError> {
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(ProfileError::InvalidName(name.to_string()));
+    }
+
+    let dir = profile_dir(models_dir, name);
+    if !dir.is_dir() {
+        return Err(ProfileError::NotFound(name.to_string()));
+    }
+
+    let mut config = VoiceConfig {
+        profile: Some(name.to_string()),
+        ..Default::default()
+    };
+
+    let model_files = &mut config.model_files;
+    for file in [&mut model_files.melspec, &mut model_files.embedding, &mut model_files.wakeword] {
+        let candidate = dir.join(&*file);
+        if candidate.exists() {
+            *file = candidate.display().to_string();
+        }
+    }
+
+    let tuning_path = dir.join(PROFILE_TUNING_FILE);
+    if tuning_path.exists() {
+        let tuning: ProfileTuning = std::fs::read_to_string(&tuning_path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+            .map_err(|e| ProfileError::InvalidTuning(name.to_string(), e))?;
+        tuning.apply(&mut config);
+    }
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_models_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("jarvis-profiles-{}-{}", label, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(PROFILES_DIR)).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_list_profiles() {
+        let models_dir = temp_models_dir("list");
+        std::fs::create_dir(profile_dir(&models_dir, "bob")).unwrap();
+        std::fs::create_dir(profile_dir(&models_dir, "alice")).unwrap();
+        std::fs::write(models_dir.join(PROFILES_DIR).join("notes.txt"), "").unwrap();
+
+        assert_eq!(list_profiles(&models_dir), vec!["alice", "bob"]);
+        let _ = std::fs::remove_dir_all(&models_dir);
+    }
+
+    #[test]
+    fn test_load_profile_models_and_tuning() {
+        let models_dir = temp_models_dir("load");
+        let dir = profile_dir(&models_dir, "alice");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("hey_jarvis.onnx"), "").unwrap();
+        std::fs::write(dir.join(PROFILE_TUNING_FILE), r#"{ "sensitivity": 1.5 }"#).unwrap();
+
+        let config = load_profile_config(&models_dir, "alice").unwrap();
+        assert_eq!(config.profile.as_deref(), Some("alice"));
+        assert_eq!(config.sensitivity, 1.5);
+        assert_eq!(config.model_files.wakeword_path(&models_dir), dir.join("hey_jarvis.onnx"));
+        // Shared models still come from the models directory
+        assert_eq!(
+            config.model_files.melspec_path(&models_dir),
+            models_dir.join("melspectrogram.onnx")
+        );
+        let _ = std::fs::remove_dir_all(&models_dir);
+    }
+
+    #[test]
+    fn test_legacy_silence_frames_are_converted() {
+        let tuning = ProfileTuning { silence_frames_threshold: Some(10), ..Default::default() };
+        let mut config = VoiceConfig::default();
+        tuning.apply(&mut config);
+        assert_eq!(config.silence_timeout_ms, 800);
+        assert_eq!(config.silence_frames_threshold(), 10);
+    }
+
+    #[test]
+    fn test_missing_and_invalid_profiles() {
+        let models_dir = temp_models_dir("missing");
+        assert!(matches!(
+            load_profile_config(&models_dir, "nobody"),
+            Err(ProfileError::NotFound(_))
+        ));
+        assert!(matches!(
+            load_profile_config(&models_dir, "../etc"),
+            Err(ProfileError::InvalidName(_))
+        ));
+        let _ = std::fs::remove_dir_all(&models_dir);
+    }
+}
@@ -0,0 +1,246 @@
+//! Resampling with exact sample accounting
+//!
+//! For ratios like 44.1k → 16k the FFT resampler delays its output and holds
+//! back part of a block between calls. Counting every input and output sample lets chunk timestamps
+//! be derived from totals instead of accumulated per-chunk durations, so they
+//! don't drift over long sessions.
+//!
+//! The FFT resampler only accepts blocks of exactly
+//! [`ChunkResampler::input_frames_next`] samples; whatever is left over when
+//! capture stops goes through [`ChunkResampler::flush`]. Blocks are sized to
+//! a whole number of the rates' common period (441 samples for 44.1k → 16k)
+//! so every block maps to an integer number of output samples.
+
+use rubato::{FftFixedIn, Resampler};
+
+use super::audio_capture::AudioCaptureError;
+use super::chunk::AudioChunk;
+
+/// Mono resampler that tracks total input and output sample counts
+pub struct ChunkResampler {
+    inner: FftFixedIn<f32>,
+    source_rate: u32,
+    target_rate: u32,
+    input_frames: u64,
+    output_frames: u64,
+}
+
+impl ChunkResampler {
+    /// Create a resampler taking blocks of about `chunk_size` input samples
+    ///
+    /// The block is rounded up to [`aligned_block_size`]; see
+    /// [`Self::input_frames_next`] for the size actually required.
+    pub fn new(source_rate: u32, target_rate: u32, chunk_size: usize) -> Result<Self, AudioCaptureError> {
+        if source_rate == 0 || target_rate == 0 {
+            return Err(AudioCaptureError::ResamplerError(format!(
+                "invalid sample rates {} -> {}",
+                source_rate, target_rate
+            )));
+        }
+        let block = aligned_block_size(source_rate, target_rate, chunk_size);
+        let inner = FftFixedIn::<f32>::new(source_rate as usize, target_rate as usize, block, 1, 1)
+            .map_err(|e| AudioCaptureError::ResamplerError(e.to_string()))?;
+        Ok(Self {
+            inner,
+            source_rate,
+            target_rate,
+            input_frames: 0,
+            output_frames: 0,
+        })
+    }
+
+    /// Input samples the next [`Self::process`] call requires
+    pub fn input_frames_next(&self) -> usize {
+        self.inner.input_frames_next()
+    }
+
+    /// Resample a chunk of exactly [`Self::input_frames_next`] samples captured at `timestamp_ms`
+    ///
+    /// The returned chunk is stamped with the capture time of its own first
+    /// sample, accounting for the resampler's delay and buffered input.
+    pub fn process(&mut self, samples: Vec<f32>, timestamp_ms: f64) -> Result<AudioChunk, AudioCaptureError> {
+        let expected = self.inner.input_frames_next();
+        if samples.len() != expected {
+            return Err(AudioCaptureError::ResamplerError(format!(
+                "expected {} input samples, got {}",
+                expected,
+                samples.len()
+            )));
+        }
+        let input_start = self.input_frames;
+        let output_start = self.output_frames;
+        let input_len = samples.len() as u64;
+
+        let output = self
+            .inner
+            .process(&[samples], None)
+            .map_err(|e| AudioCaptureError::ResamplerError(e.to_string()))?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        self.input_frames += input_len;
+        self.output_frames += output.len() as u64;
+        Ok(self.stamp(output, input_start, output_start, timestamp_ms))
+    }
+
+    /// Resample a final partial chunk and drain everything the resampler holds back
+    ///
+    /// Afterwards the total output matches the total input at the target rate
+    /// (plus the fixed output delay), so no audio is lost when capture stops.
+    pub fn flush(&mut self, residual: Vec<f32>, timestamp_ms: f64) -> Result<AudioChunk, AudioCaptureError> {
+        let input_start = self.input_frames;
+        let output_start = self.output_frames;
+        self.input_frames += residual.len() as u64;
+        let total = self.expected_output_frames() + self.inner.output_delay() as u64;
+
+        let mut output = Vec::new();
+        let mut input = vec![residual];
+        while self.output_frames + (output.len() as u64) < total {
+            let wave_in = (!input.is_empty()).then_some(input.as_slice());
+            let block = self
+                .inner
+                .process_partial(wave_in, None)
+                .map_err(|e| AudioCaptureError::ResamplerError(e.to_string()))?
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            input.clear();
+            if block.is_empty() {
+                break;
+            }
+            output.extend(block);
+        }
+        output.truncate(total.saturating_sub(self.output_frames) as usize);
+
+        self.output_frames += output.len() as u64;
+        Ok(self.stamp(output, input_start, output_start, timestamp_ms))
+    }
+
+    /// Wrap output in a chunk stamped with the capture time of its first sample
+    fn stamp(&self, samples: Vec<f32>, input_start: u64, output_start: u64, timestamp_ms: f64) -> AudioChunk {
+        // Position of the first output sample on the input timeline, relative to this chunk
+        let output_pos_s = (output_start as f64 - self.inner.output_delay() as f64) / self.target_rate as f64;
+        let input_pos_s = input_start as f64 / self.source_rate as f64;
+        AudioChunk {
+            timestamp_ms: timestamp_ms + (output_pos_s - input_pos_s) * 1000.0,
+            samples,
+        }
+    }
+
+    /// Output samples the input so far corresponds to at the target rate
+    pub fn expected_output_frames(&self) -> u64 {
+        (self.input_frames as u128 * self.target_rate as u128 / self.source_rate as u128) as u64
+    }
+
+    /// Total output samples produced
+    pub fn output_frames(&self) -> u64 {
+        self.output_frames
+    }
+
+    /// Output samples by which the resampler lags the input
+    ///
+    /// Bounded by one FFT block (twice the output delay) however long it runs.
+    pub fn lag_frames(&self) -> i64 {
+        self.expected_output_frames() as i64 - self.output_frames as i64
+    }
+
+    /// Fixed delay of the resampler in output samples
+    pub fn output_delay(&self) -> usize {
+        self.inner.output_delay()
+    }
+}
+
+/// Smallest multiple of the rates' common period that is at least `chunk_size`
+///
+/// `source_rate / gcd` input samples map to exactly `target_rate / gcd`
+/// output samples, so blocks of a multiple of it resample without remainder.
+pub fn aligned_block_size(source_rate: u32, target_rate: u32, chunk_size: usize) -> usize {
+    let period = (source_rate / gcd(source_rate, target_rate)) as usize;
+    chunk_size.max(1).div_ceil(period) * period
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_drift_over_ten_minutes_at_44k() {
+        let mut resampler = ChunkResampler::new(44100, 16000, 1024).unwrap();
+        let block = resampler.input_frames_next();
+        let chunk = vec![0.0f32; block];
+        let chunks = 44100 * 60 * 10 / block;
+
+        let mut last = None;
+        for i in 0..chunks {
+            let timestamp_ms = (i * block) as f64 * 1000.0 / 44100.0;
+            last = Some(resampler.process(chunk.clone(), timestamp_ms).unwrap());
+        }
+
+        let lag = resampler.lag_frames();
+        assert!((0..=2 * resampler.output_delay() as i64).contains(&lag), "lag {}", lag);
+
+        // Output timestamps follow the output sample count, not summed chunk durations
+        let last = last.unwrap();
+        let first_output = resampler.output_frames() - last.samples.len() as u64;
+        let expected_ms = (first_output as f64 - resampler.output_delay() as f64) * 1000.0 / 16000.0;
+        assert!((last.timestamp_ms - expected_ms).abs() < 1e-3);
+    }
+
+    /// Resample one second of a 1kHz tone to 16kHz, returning the output without the delay
+    fn resample_tone(source_rate: u32) -> Vec<f32> {
+        let mut resampler = ChunkResampler::new(source_rate, 16000, 1024).unwrap();
+        let tone: Vec<f32> = (0..source_rate)
+            .map(|i| (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / source_rate as f32).sin() * 0.5)
+            .collect();
+
+        let block = resampler.input_frames_next();
+        let mut output = Vec::new();
+        let mut chunks = tone.chunks_exact(block);
+        for chunk in chunks.by_ref() {
+            let resampled = resampler.process(chunk.to_vec(), 0.0).unwrap().samples;
+            // Aligned blocks produce the same whole number of samples every time
+            assert_eq!(resampled.len(), block * 16000 / source_rate as usize);
+            output.extend(resampled);
+        }
+        output.extend(resampler.flush(chunks.remainder().to_vec(), 0.0).unwrap().samples);
+        output.split_off(resampler.output_delay())
+    }
+
+    /// Frequency of a tone from its zero crossings, skipping the filter's edges
+    fn tone_frequency(samples: &[f32]) -> f32 {
+        let steady = &samples[160..samples.len() - 160];
+        let crossings = steady.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+        crossings as f32 / 2.0 / (steady.len() as f32 / 16000.0)
+    }
+
+    #[test]
+    fn test_tones_keep_length_and_frequency() {
+        for source_rate in [8000, 44100, 48000] {
+            let output = resample_tone(source_rate);
+            assert_eq!(output.len(), 16000, "{}Hz source", source_rate);
+            let frequency = tone_frequency(&output);
+            assert!((frequency - 1000.0).abs() < 10.0, "{}Hz source: frequency {}", source_rate, frequency);
+        }
+    }
+
+    #[test]
+    fn test_aligned_block_size() {
+        assert_eq!(aligned_block_size(44100, 16000, 1024), 1323);
+        assert_eq!(aligned_block_size(48000, 16000, 1024), 1026);
+        assert_eq!(aligned_block_size(8000, 16000, 1024), 1024);
+    }
+
+    #[test]
+    fn test_wrong_block_size_is_an_error() {
+        let mut resampler = ChunkResampler::new(48000, 16000, 1024).unwrap();
+        assert!(resampler.process(vec![0.0; 1000], 0.0).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! Listening schedule - restricts wake word scanning to configured hours
+//!
+//! A window whose end is at or before its start runs past midnight
+//! (e.g. 22:00-06:00); its `days` refer to the day it starts on. Empty
+//! `days` means every day.
+
+use chrono::{Datelike, Duration as ChronoDuration, Local, NaiveDateTime, NaiveTime, Weekday};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::controller_state::VoiceControllerState;
+use super::events::{emit_event, EventSink};
+
+/// How often the stop flag is checked while waiting for the next boundary
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Longest wait between re-evaluations, in case the local clock or timezone changes
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// A recurring period during which listening is active
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub days: Vec<Weekday>,
+}
+
+impl ScheduleWindow {
+    fn runs_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn wraps_midnight(&self) -> bool {
+        self.end <= self.start
+    }
+
+    /// Start and end of the occurrence beginning on `start_day`'s date, if it runs that day
+    fn occurrence(&self, start_day: NaiveDateTime) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        if !self.runs_on(start_day.weekday()) {
+            return None;
+        }
+        let start = start_day.date().and_time(self.start);
+        let mut end = start_day.date().and_time(self.end);
+        if self.wraps_midnight() {
+            end += ChronoDuration::days(1);
+        }
+        Some((start, end))
+    }
+}
+
+/// Check whether `now` falls inside any window
+pub fn is_active(schedule: &[ScheduleWindow], now: NaiveDateTime) -> bool {
+    schedule.iter().any(|window| {
+        [now - ChronoDuration::days(1), now]
+            .into_iter()
+            .filter_map(|day| window.occurrence(day))
+            .any(|(start, end)| start <= now && now < end)
+    })
+}
+
+/// Next time after `now` at which any window starts or ends
+pub fn next_boundary(schedule: &[ScheduleWindow], now: NaiveDateTime) -> Option<NaiveDateTime> {
+    (-1..=7)
+        .map(|offset| now + ChronoDuration::days(offset))
+        .flat_map(|day| schedule.iter().filter_map(move |window| window.occurrence(day)))
+        .flat_map(|(start, end)| [start, end])
+        .filter(|boundary| *boundary > now)
+        .min()
+}
+
+/// Apply the schedule to the shared state, emitting an event when it changes
+fn apply_schedule(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    active: bool,
+) {
+    let mut state_guard = state.write();
+    if state_guard.schedule_active == active {
+        return;
+    }
+    state_guard.schedule_active = active;
+    drop(state_guard);
+
+    let event = if active { "voice-schedule-resumed" } else { "voice-schedule-paused" };
+    log::info!("Listening schedule: {}", if active { "resumed" } else { "paused" });
+    emit_event(sink, state, event, serde_json::json!({ "active": active }));
+}
+
+/// Re-evaluate the schedule at each window boundary until `stop` is set
+pub fn spawn_schedule_monitor(
+    sink: EventSink,
+    state: Arc<RwLock<VoiceControllerState>>,
+    schedule: Vec<ScheduleWindow>,
+    stop: Arc<AtomicBool>,
+) {
+    thread::spawn(move || {
+        while !stop.load(Ordering::SeqCst) {
+            let now = Local::now().naive_local();
+            apply_schedule(&sink, &state, is_active(&schedule, now));
+
+            let wait = next_boundary(&schedule, now)
+                .and_then(|boundary| (boundary - now).to_std().ok())
+                .map_or(MAX_WAIT, |wait| wait.min(MAX_WAIT));
+            let mut waited = Duration::ZERO;
+            while waited < wait && !stop.load(Ordering::SeqCst) {
+                let step = STOP_POLL_INTERVAL.min(wait - waited);
+                thread::sleep(step);
+                waited += step;
+            }
+        }
+        apply_schedule(&sink, &state, true);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2024-01-01 is a Monday
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn window(start: (u32, u32), end: (u32, u32), days: &[Weekday]) -> ScheduleWindow {
+        ScheduleWindow {
+            start: NaiveTime::from_hms_opt(start.0, start.1, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(end.0, end.1, 0).unwrap(),
+            days: days.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_weekday_daytime_window() {
+        let schedule = vec![window((8, 0), (22, 0), &[Weekday::Mon, Weekday::Tue])];
+        assert!(is_active(&schedule, at(1, 12, 0)));
+        assert!(!is_active(&schedule, at(1, 23, 0)));
+        assert!(!is_active(&schedule, at(3, 12, 0)));
+        assert_eq!(next_boundary(&schedule, at(1, 12, 0)), Some(at(1, 22, 0)));
+        assert_eq!(next_boundary(&schedule, at(2, 23, 0)), Some(at(8, 8, 0)));
+    }
+
+    #[test]
+    fn test_window_past_midnight_belongs_to_start_day() {
+        let schedule = vec![window((22, 0), (6, 0), &[Weekday::Fri])];
+        assert!(is_active(&schedule, at(5, 23, 0)));
+        assert!(is_active(&schedule, at(6, 5, 59)));
+        assert!(!is_active(&schedule, at(6, 23, 0)));
+        assert_eq!(next_boundary(&schedule, at(5, 23, 0)), Some(at(6, 6, 0)));
+    }
+}
@@ -0,0 +1,139 @@
+//! Rolling history of recent wake word scores
+
+use std::collections::VecDeque;
+
+/// Number of recent scores retained by the detector
+pub const SCORE_HISTORY_CAPACITY: usize = 16;
+
+/// Consecutive above-threshold scores required when rejecting impulsive noise
+pub const SUSTAINED_FRAMES: usize = 2;
+
+/// Ring buffer of the most recent classifier scores, oldest first
+#[derive(Debug, Clone)]
+pub struct ScoreHistory {
+    scores: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl ScoreHistory {
+    /// Create a history holding up to `capacity` scores
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            scores: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Record a new score, dropping the oldest if at capacity
+    pub fn push(&mut self, score: f32) {
+        if self.scores.len() >= self.capacity {
+            self.scores.pop_front();
+        }
+        self.scores.push_back(score);
+    }
+
+    /// Most recent score
+    pub fn latest(&self) -> Option<f32> {
+        self.scores.back().copied()
+    }
+
+    /// Check if the most recent `frames` scores are all at or above `threshold`
+    ///
+    /// An impulsive noise (clap, click) spikes a single inference and is
+    /// surrounded by low scores, so it never forms a sustained run.
+    pub fn sustained_above(&self, threshold: f32, frames: usize) -> bool {
+        self.scores.len() >= frames && self.scores.iter().rev().take(frames).all(|&s| s >= threshold)
+    }
+
+    /// Average of the most recent `frames` scores (fewer if not yet recorded)
+    ///
+    /// With `frames` of 1 this is the latest score.
+    pub fn smoothed(&self, frames: usize) -> Option<f32> {
+        let frames = frames.clamp(1, self.capacity).min(self.scores.len());
+        if frames == 0 {
+            return None;
+        }
+        Some(self.scores.iter().rev().take(frames).sum::<f32>() / frames as f32)
+    }
+
+    /// Current number of scores
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// Check if no scores are recorded
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// Clear all scores
+    pub fn clear(&mut self) {
+        self.scores.clear();
+    }
+}
+
+impl Default for ScoreHistory {
+    fn default() -> Self {
+        Self::new(SCORE_HISTORY_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detections(scores: &[f32], threshold: f32) -> usize {
+        let mut history = ScoreHistory::default();
+        scores
+            .iter()
+            .filter(|&&score| {
+                history.push(score);
+                history.sustained_above(threshold, SUSTAINED_FRAMES)
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_isolated_spike_rejected() {
+        assert_eq!(detections(&[0.02, 0.03, 0.95, 0.04, 0.02], 0.5), 0);
+    }
+
+    #[test]
+    fn test_sustained_scores_detected() {
+        assert_eq!(detections(&[0.02, 0.7, 0.85, 0.9, 0.1], 0.5), 2);
+    }
+
+    fn smoothed_detections(scores: &[f32], threshold: f32, frames: usize) -> usize {
+        let mut history = ScoreHistory::default();
+        scores
+            .iter()
+            .filter(|&&score| {
+                history.push(score);
+                history.smoothed(frames).unwrap() > threshold
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_smoothing_ignores_single_spike() {
+        let scores = [0.02, 0.03, 0.95, 0.04, 0.02];
+        assert_eq!(smoothed_detections(&scores, 0.5, 1), 1, "window of 1 is the raw score");
+        assert_eq!(smoothed_detections(&scores, 0.5, 3), 0);
+    }
+
+    #[test]
+    fn test_smoothing_detects_sustained_scores() {
+        assert_eq!(smoothed_detections(&[0.02, 0.7, 0.85, 0.9, 0.8], 0.5, 3), 3);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut history = ScoreHistory::new(3);
+        for score in [0.1, 0.2, 0.3, 0.4] {
+            history.push(score);
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.latest(), Some(0.4));
+    }
+}
@@ -0,0 +1,150 @@
+//! Session recording for reproducing field issues
+//!
+//! A recording is a JSON-lines file with one entry per raw audio chunk (as
+//! received from capture, before any processing) and one entry per emitted
+//! voice event, each stamped with milliseconds since the recording started.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+/// A single recorded entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SessionEntry {
+    /// Raw audio chunk as received by the processing loop
+    Audio { t_ms: u64, samples: Vec<f32> },
+    /// Event emitted to the frontend
+    Event {
+        t_ms: u64,
+        name: String,
+        payload: serde_json::Value,
+    },
+}
+
+/// Writes a session recording to disk
+pub struct SessionRecorder {
+    writer: BufWriter<File>,
+    started: Instant,
+    bytes_written: u64,
+}
+
+impl SessionRecorder {
+    /// Create a recording at the given path, truncating any existing file
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            started: Instant::now(),
+            bytes_written: 0,
+        })
+    }
+
+    /// Record a raw audio chunk
+    pub fn record_audio(&mut self, samples: &[f32]) -> io::Result<()> {
+        self.write_entry(&SessionEntry::Audio {
+            t_ms: self.elapsed_ms(),
+            samples: samples.to_vec(),
+        })
+    }
+
+    /// Record an emitted event
+    pub fn record_event<S: Serialize>(&mut self, name: &str, payload: &S) -> io::Result<()> {
+        let payload = serde_json::to_value(payload).map_err(io::Error::other)?;
+        self.write_entry(&SessionEntry::Event {
+            t_ms: self.elapsed_ms(),
+            name: name.to_string(),
+            payload,
+        })
+    }
+
+    /// Flush buffered entries to disk
+    pub fn finish(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Number of bytes written so far
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn write_entry(&mut self, entry: &SessionEntry) -> io::Result<()> {
+        let line = serde_json::to_string(entry).map_err(io::Error::other)?;
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.bytes_written += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+/// Read every entry of a session recording in order
+pub fn read_session(path: &Path) -> io::Result<Vec<SessionEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(serde_json::from_str(&line).map_err(io::Error::other)?);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::config::VoiceConfig;
+    use crate::voice::filters::FilterChain;
+    use crate::voice::vad::{VadResult, VoiceActivityDetector};
+
+    /// Replay a recording through the synchronous processing stages
+    fn replay_session(path: &Path) -> Vec<VadResult> {
+        let config = VoiceConfig::default();
+        let mut filter_chain = FilterChain::from_config(&config);
+        let mut vad = VoiceActivityDetector::new(&config);
+
+        read_session(path)
+            .unwrap()
+            .into_iter()
+            .filter_map(|entry| match entry {
+                SessionEntry::Audio { mut samples, .. } => {
+                    filter_chain.process(&mut samples);
+                    Some(vad.process(&samples))
+                }
+                SessionEntry::Event { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("jarvis-session-{}.jsonl", std::process::id()));
+        let loud: Vec<f32> = (0..1280).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let quiet = vec![0.0; 1280];
+
+        let mut recorder = SessionRecorder::create(&path).unwrap();
+        recorder.record_audio(&loud).unwrap();
+        recorder.record_event("voice-state-changed", &"listening").unwrap();
+        recorder.record_audio(&quiet).unwrap();
+        assert!(recorder.bytes_written() > 0);
+        recorder.finish().unwrap();
+
+        let entries = read_session(&path).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(matches!(&entries[0], SessionEntry::Audio { samples, .. } if *samples == loud));
+        assert!(matches!(&entries[1], SessionEntry::Event { name, .. } if name == "voice-state-changed"));
+
+        let replayed = replay_session(&path);
+        assert_eq!(replayed.len(), 2);
+        assert_eq!(replayed[0], VadResult::Speech);
+        assert_eq!(replay_session(&path), replayed);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
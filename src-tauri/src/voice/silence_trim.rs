@@ -0,0 +1,56 @@
+//! Trimming silence around a captured utterance before it goes to STT
+//!
+//! A capture ends only after `silence_timeout_ms` of quiet frames, so it
+//! carries over a second of trailing silence that STT still has to process.
+//! Frames are judged by RMS against the VAD's offset threshold, the same level
+//! that counted them as silence while listening.
+
+use std::ops::Range;
+
+use super::dsp::calculate_rms;
+
+/// Range of `audio` worth sending to STT, keeping `pad` samples after the last loud frame
+///
+/// Leading silence is cut at the start of the first loud `frame`-sample frame.
+/// Returns `None` when no frame reaches `threshold`, so an all-quiet capture
+/// is never trimmed to nothing.
+pub fn speech_range(audio: &[f32], frame: usize, threshold: f32, pad: usize) -> Option<Range<usize>> {
+    let frame = frame.max(1);
+    let is_loud = |samples: &[f32]| calculate_rms(samples) >= threshold;
+    let first = audio.chunks(frame).position(is_loud)?;
+    let last = audio.chunks(frame).rposition(is_loud)?;
+
+    let end = ((last + 1) * frame).min(audio.len());
+    Some(first * frame..(end + pad).min(audio.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn padded(leading: usize, speech: usize, trailing: usize) -> Vec<f32> {
+        let mut audio = vec![0.0; leading];
+        audio.extend(vec![0.5; speech]);
+        audio.extend(vec![0.0; trailing]);
+        audio
+    }
+
+    #[test]
+    fn test_trims_leading_and_trailing_silence() {
+        let audio = padded(3 * 1280, 5 * 1280, 16 * 1280);
+        assert_eq!(speech_range(&audio, 1280, 0.01, 3200), Some(3 * 1280..8 * 1280 + 3200));
+    }
+
+    #[test]
+    fn test_pad_is_capped_at_the_end() {
+        let audio = padded(0, 2 * 1280, 1000);
+        assert_eq!(speech_range(&audio, 1280, 0.01, 3200), Some(0..audio.len()));
+    }
+
+    #[test]
+    fn test_all_silent_is_not_trimmed() {
+        let audio = padded(10 * 1280, 0, 0);
+        assert_eq!(speech_range(&audio, 1280, 0.01, 3200), None);
+        assert_eq!(speech_range(&[], 1280, 0.01, 3200), None);
+    }
+}
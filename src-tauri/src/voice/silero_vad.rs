@@ -0,0 +1,162 @@
+//! Silero VAD - neural speech detection for noisy rooms and music
+//!
+//! Runs `silero_vad.onnx` on 512-sample (32ms at 16kHz) frames, carrying the
+//! recurrent state and the trailing context between frames as the reference
+//! implementation does. A chunk counts as speech when any of its frames
+//! reaches [`SPEECH_PROBABILITY_THRESHOLD`].
+
+use ort::session::Session;
+use ort::value::Tensor;
+use std::path::Path;
+
+use super::classifiers::load_session;
+use super::config::VoiceConfig;
+use super::dsp::calculate_rms;
+use super::vad::{SpeechEndTracker, VadResult};
+use super::wake_word::WakeWordError;
+
+/// Model filename inside the models directory
+pub const SILERO_MODEL_FILE: &str = "silero_vad.onnx";
+
+/// Speech probability at or above which a frame counts as speech
+pub const SPEECH_PROBABILITY_THRESHOLD: f32 = 0.5;
+
+/// Samples per model frame at 16kHz
+const FRAME_SAMPLES: usize = 512;
+
+/// Samples from the end of the previous frame prepended to each input
+const CONTEXT_SAMPLES: usize = 64;
+
+/// Recurrent state size, shape [2, 1, 128]
+const STATE_LEN: usize = 2 * 128;
+
+/// Silero ONNX voice activity detector
+pub struct SileroVad {
+    session: Session,
+    sample_rate: i64,
+    state: Vec<f32>,
+    context: Vec<f32>,
+    /// Samples waiting for a full frame
+    pending: Vec<f32>,
+    tracker: SpeechEndTracker,
+    last_probability: f32,
+    /// RMS of the latest chunk, for level feedback
+    last_rms: f32,
+}
+
+impl SileroVad {
+    /// Load the model from `path`
+    pub fn new(path: &Path, config: &VoiceConfig) -> Result<Self, WakeWordError> {
+        log::info!("Loading Silero VAD model from {:?}", path);
+        Ok(Self {
+            session: load_session(path, config.execution_provider)?,
+            sample_rate: config.sample_rate as i64,
+            state: vec![0.0; STATE_LEN],
+            context: vec![0.0; CONTEXT_SAMPLES],
+            pending: Vec::with_capacity(FRAME_SAMPLES * 4),
+            tracker: SpeechEndTracker::new(config.silence_frames_threshold()),
+            last_probability: 0.0,
+            last_rms: 0.0,
+        })
+    }
+
+    /// Process an audio chunk and return VAD result
+    pub fn process(&mut self, samples: &[f32]) -> VadResult {
+        self.last_rms = calculate_rms(samples);
+        self.pending.extend_from_slice(samples);
+
+        let mut is_speech = false;
+        while self.pending.len() >= FRAME_SAMPLES {
+            let frame: Vec<f32> = self.pending.drain(..FRAME_SAMPLES).collect();
+            match self.speech_probability(&frame) {
+                Ok(probability) => {
+                    self.last_probability = probability;
+                    is_speech |= probability >= SPEECH_PROBABILITY_THRESHOLD;
+                }
+                Err(e) => log::warn!("Silero VAD inference failed: {}", e),
+            }
+        }
+
+        self.tracker.update(is_speech)
+    }
+
+    /// Reset the VAD state
+    pub fn reset(&mut self) {
+        self.state.fill(0.0);
+        self.context.fill(0.0);
+        self.pending.clear();
+        self.tracker.reset();
+        self.last_probability = 0.0;
+        self.last_rms = 0.0;
+    }
+
+    /// Speech probability of the most recent frame
+    pub fn last_probability(&self) -> f32 {
+        self.last_probability
+    }
+
+    /// RMS of the most recent chunk
+    pub fn last_rms(&self) -> f32 {
+        self.last_rms
+    }
+
+    pub(super) fn tracker(&self) -> &SpeechEndTracker {
+        &self.tracker
+    }
+
+    /// Run one frame through the model, updating the recurrent state and context
+    fn speech_probability(&mut self, frame: &[f32]) -> Result<f32, WakeWordError> {
+        let mut input = Vec::with_capacity(CONTEXT_SAMPLES + FRAME_SAMPLES);
+        input.extend_from_slice(&self.context);
+        input.extend_from_slice(frame);
+        self.context.copy_from_slice(&input[input.len() - CONTEXT_SAMPLES..]);
+
+        let to_error = |e: ort::Error| WakeWordError::InferenceError(e.to_string());
+        let input_tensor = Tensor::from_array(([1_usize, input.len()], input)).map_err(to_error)?;
+        let state_tensor = Tensor::from_array(([2_usize, 1, 128], self.state.clone())).map_err(to_error)?;
+        let sr_tensor = Tensor::from_array(([1_usize], vec![self.sample_rate])).map_err(to_error)?;
+
+        let outputs = self
+            .session
+            .run(ort::inputs!["input" => input_tensor, "state" => state_tensor, "sr" => sr_tensor])
+            .map_err(to_error)?;
+
+        let (_, probability) = outputs["output"].try_extract_tensor::<f32>().map_err(to_error)?;
+        let (_, state) = outputs["stateN"].try_extract_tensor::<f32>().map_err(to_error)?;
+        if state.len() == STATE_LEN {
+            self.state.copy_from_slice(state);
+        }
+
+        Ok(probability.first().copied().unwrap_or(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::vad::VoiceActivityDetector;
+
+    // Requires silero_vad.onnx and a recorded speech clip to be present
+    #[test]
+    #[ignore]
+    fn test_speech_end_only_after_clip() {
+        let models_dir = Path::new("resources/models");
+        let config = VoiceConfig {
+            vad_backend: crate::voice::vad::VadBackend::Silero,
+            ..Default::default()
+        };
+        let mut vad = VoiceActivityDetector::load(models_dir, &config);
+        assert!(matches!(vad, VoiceActivityDetector::Silero(_)));
+
+        let mut reader = hound::WavReader::open("resources/test/speech_16k.wav").unwrap();
+        let speech: Vec<f32> = reader.samples::<i16>().map(|s| s.unwrap() as f32 / 32768.0).collect();
+        for chunk in speech.chunks(config.chunk_size) {
+            assert_ne!(vad.process(chunk), VadResult::SpeechEnd);
+        }
+        assert!(vad.has_speech());
+
+        let silence = vec![0.0; config.chunk_size];
+        let results: Vec<_> = (0..config.silence_frames_threshold()).map(|_| vad.process(&silence)).collect();
+        assert_eq!(results.last(), Some(&VadResult::SpeechEnd));
+    }
+}
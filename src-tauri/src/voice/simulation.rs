@@ -0,0 +1,106 @@
+//! Offline replay of a WAV file through the wake word detector and VAD
+//!
+//! Used for regression-testing threshold tuning: the file is resampled to the
+//! configured rate, cut into chunks and fed to a fresh detector and VAD, with
+//! no live audio, state machine or events involved.
+
+use serde::Serialize;
+use std::path::Path;
+
+use super::audio_source::FileAudioSource;
+use super::config::VoiceConfig;
+use super::vad::{VadResult, VoiceActivityDetector};
+use super::wake_word::WakeWordDetector;
+use super::VoiceError;
+
+/// What the pipeline made of one chunk
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulationFrame {
+    pub chunk_index: usize,
+    /// Best wake word score, `None` until enough audio was buffered for inference
+    pub wake_score: Option<f32>,
+    pub vad_result: VadResult,
+}
+
+/// First wake word detection in a replayed file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulatedDetection {
+    pub chunk_index: usize,
+    /// Start of the detecting chunk from the start of the file
+    pub time_ms: f64,
+    pub wake_word: String,
+    pub score: f32,
+}
+
+/// Timeline of a replayed file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulationReport {
+    pub frames: Vec<SimulationFrame>,
+    /// `None` if the wake word never fired
+    pub detection: Option<SimulatedDetection>,
+}
+
+/// Replay the WAV at `path` through fresh models loaded from `models_dir`
+pub fn simulate_detection(models_dir: &Path, path: &Path, config: &VoiceConfig) -> Result<SimulationReport, VoiceError> {
+    let chunks = FileAudioSource::new(path, false).read_chunks(config, 0.0)?;
+    let mut detector = WakeWordDetector::new(models_dir, config.clone())?;
+    let mut vad = VoiceActivityDetector::load(models_dir, config);
+    log::info!("Simulating detection over {} chunks from {}", chunks.len(), path.display());
+
+    let mut frames = Vec::with_capacity(chunks.len());
+    let mut detection = None;
+    for (chunk_index, chunk) in chunks.iter().enumerate() {
+        let best = detector.process_audio(&chunk.samples)?;
+        if let Some((ref name, score)) = best {
+            if detection.is_none() && detector.is_detected(name, score) {
+                detection = Some(SimulatedDetection {
+                    chunk_index,
+                    time_ms: chunk.timestamp_ms,
+                    wake_word: name.clone(),
+                    score,
+                });
+            }
+        }
+        frames.push(SimulationFrame {
+            chunk_index,
+            wake_score: best.map(|(_, score)| score),
+            vad_result: vad.process(&chunk.samples),
+        });
+    }
+
+    Ok(SimulationReport { frames, detection })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_missing_file_is_an_error() {
+        let result = simulate_detection(
+            &PathBuf::from("resources/models"),
+            &PathBuf::from("does-not-exist.wav"),
+            &VoiceConfig::default(),
+        );
+        assert!(matches!(result, Err(VoiceError::AudioCapture(_))));
+    }
+
+    // Requires models and test clips to be present
+    #[test]
+    #[ignore]
+    fn test_detects_only_the_wake_word_clip() {
+        let models_dir = PathBuf::from("resources/models");
+        let config = VoiceConfig::default();
+
+        // Recorded at 44.1kHz to exercise the resampling
+        let positive = simulate_detection(&models_dir, Path::new("resources/test/hey_jarvis_44k.wav"), &config).unwrap();
+        let negative = simulate_detection(&models_dir, Path::new("resources/test/speech_16k.wav"), &config).unwrap();
+
+        let detection = positive.detection.expect("wake word clip should trigger");
+        assert_eq!(detection.wake_word, "hey_jarvis");
+        assert!(positive.frames.iter().any(|frame| frame.vad_result == VadResult::Speech));
+        assert!(negative.detection.is_none());
+        assert!(negative.frames.iter().all(|frame| frame.wake_score.unwrap_or(0.0) < config.effective_threshold()));
+    }
+}
@@ -0,0 +1,325 @@
+//! Per-state chunk handling for the audio processing loop
+
+use parking_lot::RwLock;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::controller_state::VoiceControllerState;
+use super::barge_in::BargeInDetector;
+use super::buffer::AudioBuffer;
+use super::chunk::AudioChunk;
+use super::command_words::handle_command_word;
+use super::cooldown::DetectionCooldown;
+use super::events::{emit_debug_log, emit_event, emit_state_changed, EventSink};
+use super::state_machine::{StateAction, VoiceEvent};
+use super::vad::{VadResult, VoiceActivityDetector};
+use super::wake_word::{WakeWordDetector, WakeWordError};
+
+mod capture;
+
+pub use capture::CapturedUtterance;
+pub(super) use capture::{end_capture, finish_transcription, report_failure};
+
+/// Process audio in idle state (wake word detection)
+pub(super) fn process_idle_state(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    chunk: &AudioChunk,
+    preroll: &AudioBuffer,
+    wake_word_detector: &mut Option<WakeWordDetector>,
+    vad: &mut VoiceActivityDetector,
+    cooldown: &mut DetectionCooldown,
+) {
+    if let Some(ref mut detector) = wake_word_detector {
+        let result = detector.process_audio(&chunk.samples);
+        if !matches!(result, Err(WakeWordError::Cancelled)) {
+            state.read().health.record_inference(result.is_ok());
+        }
+        match result {
+            Ok(Some((name, score))) => {
+                if detector.is_detected(&name, score) {
+                    if !cooldown.try_trigger(Instant::now()) {
+                        log::debug!("Wake word '{}' ignored during cooldown", name);
+                        return;
+                    }
+                    detector.record_detection();
+                    let smoothed = detector.smoothed_score().unwrap_or(score);
+                    emit_debug_log(
+                        sink,
+                        "info",
+                        &format!("WAKE WORD '{}'! Score: {:.3} (smoothed {:.3})", name, score, smoothed),
+                    );
+                    log::info!("Wake word '{}' detected! Score: {}", name, score);
+
+                    let mut state_guard = state.write();
+                    state_guard.state_machine.transition(VoiceEvent::WakeWordDetected);
+                    seed_preroll(&mut state_guard, chunk, preroll);
+                    let new_state = state_guard.state_machine.state();
+                    drop(state_guard);
+
+                    emit_event(sink, state, "voice-wake-word", serde_json::json!({
+                        "name": name,
+                        "score": score,
+                        "phrase": detector.last_phrase(),
+                        "timestamp_ms": chunk.timestamp_ms,
+                    }));
+                    emit_state_changed(sink, state, new_state);
+
+                    vad.reset();
+                } else if let Some(command) = detector.take_command() {
+                    handle_command_word(sink, state, &command);
+                }
+            }
+            Ok(None) => {}
+            Err(WakeWordError::Cancelled) => {}
+            Err(e) => {
+                emit_debug_log(sink, "error", &format!("Wake word error: {}", e));
+            }
+        }
+    }
+}
+
+/// Seed the new capture with the audio leading up to (and including) the detection chunk
+///
+/// Wake word detection lags the end of the phrase, so the start of the
+/// user's request is already in the buffer by the time it fires.
+fn seed_preroll(state_guard: &mut VoiceControllerState, chunk: &AudioChunk, preroll: &AudioBuffer) {
+    let config = &state_guard.config;
+    let samples = preroll.get_last_ms(config.preroll_window_ms(), config.sample_rate);
+    let ms_per_sample = 1000.0 / state_guard.config.sample_rate as f64;
+    let chunk_end_ms = chunk.timestamp_ms + chunk.samples.len() as f64 * ms_per_sample;
+    let start_ms = chunk_end_ms - samples.len() as f64 * ms_per_sample;
+    state_guard.state_machine.seed_preroll(&samples, start_ms);
+}
+
+/// Process audio in listening state (VAD for speech end)
+///
+/// `capture` is the same audio as `chunk`, without the detection-only gain.
+pub(super) fn process_listening_state(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    chunk: &AudioChunk,
+    capture: &AudioChunk,
+    wake_word_detector: &mut Option<WakeWordDetector>,
+    vad: &mut VoiceActivityDetector,
+) {
+    state.write().state_machine.add_audio_at(&capture.samples, capture.timestamp_ms);
+
+    let vad_result = vad.process(&chunk.samples);
+    if vad_result == VadResult::Speech && state.read().state_machine.follow_up_timeout().is_some() {
+        state.write().state_machine.follow_up_heard();
+    }
+    if vad_result == VadResult::SpeechEnd {
+        // Push-to-talk captures end on release, not on a pause
+        if state.read().state_machine.manual_mode() {
+            return;
+        }
+        // A pause right after the wake word doesn't end the capture yet; once
+        // the grace period is over, the next silent chunk ends it
+        if in_listening_grace(state) {
+            return;
+        }
+        let speech_frames = vad.speech_frames();
+        let min_speech_frames = state.read().config.min_speech_frames;
+        if speech_frames < min_speech_frames {
+            discard_short_speech(sink, state, speech_frames, wake_word_detector, vad);
+            return;
+        }
+        log::info!("Speech end detected");
+        end_capture(sink, state, VoiceEvent::VadSpeechEnd);
+
+        vad.reset();
+
+        if let Some(ref mut detector) = wake_word_detector {
+            detector.reset();
+        }
+    }
+}
+
+/// Whether Listening was entered less than `listening_grace_ms` ago
+fn in_listening_grace(state: &Arc<RwLock<VoiceControllerState>>) -> bool {
+    let state = state.read();
+    state.state_machine.time_in_state() < Duration::from_millis(state.config.listening_grace_ms)
+}
+
+/// Throw away a capture whose speech was too short to be a request (a cough, a door)
+fn discard_short_speech(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    speech_frames: usize,
+    wake_word_detector: &mut Option<WakeWordDetector>,
+    vad: &mut VoiceActivityDetector,
+) {
+    log::info!("Discarding capture with only {} speech frames", speech_frames);
+    let result = state.write().state_machine.transition(VoiceEvent::SpeechTooShort);
+    emit_event(sink, state, "voice-speech-discarded", serde_json::json!({ "speech_frames": speech_frames }));
+    emit_state_changed(sink, state, result.new_state);
+
+    vad.reset();
+    if let Some(ref mut detector) = wake_word_detector {
+        detector.reset();
+    }
+}
+
+/// Process audio while TTS is playing (barge-in detection)
+pub(super) fn process_speaking_state(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    chunk: &AudioChunk,
+    capture: &AudioChunk,
+    barge_in: &mut BargeInDetector,
+    vad: &mut VoiceActivityDetector,
+) {
+    if !barge_in.process(&chunk.samples) {
+        return;
+    }
+
+    let mut state_guard = state.write();
+    let result = state_guard.state_machine.transition(VoiceEvent::BargeIn);
+    if !matches!(result.action, Some(StateAction::StopTts)) {
+        return;
+    }
+    state_guard.stop_tts_playback();
+    // The interrupting speech is the start of the next request
+    state_guard.state_machine.add_audio_at(&capture.samples, capture.timestamp_ms);
+    drop(state_guard);
+
+    log::info!("Barge-in detected");
+    emit_event(sink, state, "voice-barge-in", serde_json::json!({ "timestamp_ms": chunk.timestamp_ms }));
+    emit_state_changed(sink, state, result.new_state);
+
+    barge_in.reset();
+    vad.reset();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::config::VoiceConfig;
+    use crate::voice::events::CollectingSink;
+    use crate::voice::state_machine::VoiceState;
+
+    #[test]
+    fn test_loud_input_while_speaking_barges_in() {
+        let collected = Arc::new(CollectingSink::default());
+        let sink: EventSink = Some(collected.clone());
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let config = VoiceConfig::default();
+        let mut barge_in = BargeInDetector::new(&config);
+        let mut vad = VoiceActivityDetector::new(&config);
+
+        let mut state_guard = state.write();
+        state_guard.state_machine.transition(VoiceEvent::ManualTrigger);
+        state_guard.state_machine.transition(VoiceEvent::VadSpeechEnd);
+        state_guard.state_machine.transition(VoiceEvent::TranscriptionComplete("hi".into()));
+        state_guard.state_machine.transition(VoiceEvent::ResponseReady("hello".into()));
+        assert_eq!(state_guard.state_machine.state(), VoiceState::Speaking);
+        drop(state_guard);
+
+        let loud = AudioChunk {
+            timestamp_ms: 0.0,
+            samples: (0..1280).map(|i| (i as f32 * 0.05).sin() * 0.5).collect(),
+        };
+        for _ in 0..4 {
+            process_speaking_state(&sink, &state, &loud, &loud, &mut barge_in, &mut vad);
+        }
+
+        assert_eq!(state.read().state_machine.state(), VoiceState::Listening);
+        assert_eq!(collected.payloads("voice-barge-in").len(), 1);
+    }
+
+    /// Listen to `speech_chunks` loud chunks then silence, returning the final state and events
+    fn listen_to_burst(speech_chunks: usize) -> (VoiceState, Arc<CollectingSink>) {
+        let collected = Arc::new(CollectingSink::default());
+        let sink: EventSink = Some(collected.clone());
+        let config = VoiceConfig {
+            silence_timeout_ms: 160,
+            // RMS smoothing stretches one loud chunk to ~8 speech frames, five to ~15
+            min_speech_frames: 12,
+            ..Default::default()
+        };
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let mut vad = VoiceActivityDetector::new(&config);
+        let mut detector = None;
+
+        let mut state_guard = state.write();
+        state_guard.config = config;
+        state_guard.state_machine.transition(VoiceEvent::ManualTrigger);
+        drop(state_guard);
+
+        let loud = AudioChunk {
+            timestamp_ms: 0.0,
+            samples: vec![0.5; 1280],
+        };
+        let quiet = AudioChunk {
+            timestamp_ms: 0.0,
+            samples: vec![0.0; 1280],
+        };
+        for _ in 0..speech_chunks {
+            process_listening_state(&sink, &state, &loud, &loud, &mut detector, &mut vad);
+        }
+        for _ in 0..20 {
+            if state.read().state_machine.state() != VoiceState::Listening {
+                break;
+            }
+            process_listening_state(&sink, &state, &quiet, &quiet, &mut detector, &mut vad);
+        }
+
+        let final_state = state.read().state_machine.state();
+        (final_state, collected)
+    }
+
+    fn has_event(collected: &CollectingSink, event: &str) -> bool {
+        !collected.payloads(event).is_empty()
+    }
+
+    #[test]
+    fn test_short_burst_is_discarded() {
+        let (state, collected) = listen_to_burst(1);
+        assert_eq!(state, VoiceState::Idle);
+        assert!(has_event(&collected, "voice-speech-discarded"));
+        assert!(!has_event(&collected, "voice-audio-captured"));
+    }
+
+    #[test]
+    fn test_pause_during_grace_period_keeps_listening() {
+        let config = VoiceConfig {
+            silence_timeout_ms: 160,
+            listening_grace_ms: 300,
+            ..Default::default()
+        };
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let mut vad = VoiceActivityDetector::new(&config);
+        let mut detector = None;
+        state.write().config = config;
+        state.write().state_machine.transition(VoiceEvent::WakeWordDetected);
+
+        // The tail of the wake word, then the user gathers their thoughts
+        let loud = AudioChunk {
+            timestamp_ms: 0.0,
+            samples: vec![0.5; 1280],
+        };
+        let quiet = AudioChunk {
+            timestamp_ms: 80.0,
+            samples: vec![0.0; 1280],
+        };
+        process_listening_state(&None, &state, &loud, &loud, &mut detector, &mut vad);
+        for _ in 0..20 {
+            process_listening_state(&None, &state, &quiet, &quiet, &mut detector, &mut vad);
+        }
+        assert_eq!(vad.process(&quiet.samples), VadResult::SpeechEnd);
+        assert_eq!(state.read().state_machine.state(), VoiceState::Listening);
+
+        state.write().state_machine.backdate_transition(Duration::from_millis(350));
+        process_listening_state(&None, &state, &quiet, &quiet, &mut detector, &mut vad);
+        assert_eq!(state.read().state_machine.state(), VoiceState::Transcribing);
+    }
+
+    #[test]
+    fn test_long_enough_speech_is_accepted() {
+        let (state, collected) = listen_to_burst(5);
+        assert_eq!(state, VoiceState::Transcribing);
+        assert!(!has_event(&collected, "voice-speech-discarded"));
+        assert!(has_event(&collected, "voice-audio-captured"));
+    }
+}
@@ -0,0 +1,283 @@
+//! Ending a capture: trimming the audio and handing it to STT
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::voice::capture_quality::CaptureQuality;
+use crate::voice::config::VoiceConfig;
+use crate::voice::controller_state::VoiceControllerState;
+use crate::voice::events::{emit_debug_log, emit_error, emit_event, emit_transition, EventSink};
+use crate::voice::silence_trim::speech_range;
+use crate::voice::state_machine::{StateAction, TransitionResult, VoiceEvent};
+use crate::voice::stt::spawn_transcription;
+use crate::voice::wav::{utterance_path, write_wav};
+
+/// Utterance handed to STT, the payload of `voice-audio-captured`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapturedUtterance {
+    pub samples: Vec<f32>,
+    /// Length of `samples` after trimming
+    pub duration_ms: f64,
+    /// No frame reached the speech threshold, so silence was not trimmed
+    pub all_silent: bool,
+}
+
+/// End the current capture with `event` and send the audio to STT
+///
+/// Shared by VAD speech end and push-to-talk release. Must not be called
+/// while holding a lock on `state`.
+pub(in crate::voice) fn end_capture(sink: &EventSink, state: &Arc<RwLock<VoiceControllerState>>, event: VoiceEvent) {
+    let mut state_guard = state.write();
+    let result = state_guard.state_machine.transition(event);
+    let mut start_timestamp_ms = state_guard.state_machine.capture_start_ms();
+    let preroll_samples = state_guard.state_machine.preroll_samples();
+    let capture_id = state_guard.state_machine.capture_id();
+    let config = state_guard.config.clone();
+    drop(state_guard);
+
+    emit_transition(sink, state, &result);
+
+    if let Some(StateAction::SendToStt(mut audio)) = result.action {
+        // Keep only the end of the seeded window: the pre-roll if included, else the lookback
+        let excess = preroll_samples.saturating_sub(config.stt_preroll_samples());
+        if excess > 0 {
+            let trimmed = trim_preroll(&mut audio, excess);
+            start_timestamp_ms = start_timestamp_ms
+                .map(|start| start + trimmed as f64 * 1000.0 / config.sample_rate as f64);
+        }
+        let mut all_silent = false;
+        if config.trim_silence {
+            let (_, offset) = config.speech_thresholds();
+            match speech_range(&audio, config.chunk_size, offset, config.trailing_pad_samples()) {
+                Some(range) => {
+                    audio.truncate(range.end);
+                    audio.drain(..range.start);
+                    start_timestamp_ms = start_timestamp_ms
+                        .map(|start| start + range.start as f64 * 1000.0 / config.sample_rate as f64);
+                }
+                None => {
+                    log::warn!("Captured utterance is silent throughout, sending it untrimmed");
+                    all_silent = true;
+                }
+            }
+        }
+        let duration_ms = audio.len() as f64 * 1000.0 / config.sample_rate as f64;
+        let quality = CaptureQuality::assess(&audio, &config);
+        if !quality.likely_usable {
+            log::warn!("Captured utterance may be unusable: {:?}", quality);
+        }
+        emit_event(sink, state, "voice-capture-quality", quality);
+        emit_event(sink, state, "voice-utterance-metadata", serde_json::json!({
+            "start_timestamp_ms": start_timestamp_ms,
+            "duration_ms": duration_ms,
+        }));
+        if config.record_utterances {
+            save_utterance(sink, &audio, &config);
+        }
+        match config.stt_endpoint.clone().filter(|endpoint| !endpoint.is_empty()) {
+            Some(endpoint) => {
+                spawn_transcription(sink.clone(), state.clone(), endpoint, audio, capture_id, &config)
+            }
+            None => emit_event(sink, state, "voice-audio-captured", CapturedUtterance {
+                samples: audio,
+                duration_ms,
+                all_silent,
+            }),
+        }
+    }
+}
+
+/// Drop the pre-roll (and with it the wake word) from the front of a capture
+///
+/// Returns the number of samples removed.
+fn trim_preroll(audio: &mut Vec<f32>, preroll_samples: usize) -> usize {
+    let trimmed = preroll_samples.min(audio.len());
+    audio.drain(..trimmed);
+    trimmed
+}
+
+/// Report the outcome of a finished transcription
+///
+/// A blank transcription that returned to Idle also emits `voice-no-speech`.
+/// Must not be called while holding a lock on `state`.
+pub(in crate::voice) fn finish_transcription(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    result: &TransitionResult,
+) {
+    if let Some(StateAction::NoSpeech) = result.action {
+        emit_event(sink, state, "voice-no-speech", serde_json::json!({}));
+    }
+    emit_transition(sink, state, result);
+}
+
+/// Move to the Error state with `message` and tell the frontend
+///
+/// Must not be called while holding a lock on `state`.
+pub(in crate::voice) fn report_failure(sink: &EventSink, state: &Arc<RwLock<VoiceControllerState>>, message: String) {
+    let result = state.write().state_machine.transition(VoiceEvent::Error(message));
+    if let Some(StateAction::EmitError(ref message)) = result.action {
+        emit_error(sink, state, message.clone());
+    }
+    emit_transition(sink, state, &result);
+}
+
+/// Write a captured utterance to the debug directory, logging any failure
+fn save_utterance(sink: &EventSink, audio: &[f32], config: &VoiceConfig) {
+    let path = utterance_path(&config.utterance_dir);
+    let result = std::fs::create_dir_all(&config.utterance_dir)
+        .map_err(hound::Error::from)
+        .and_then(|_| write_wav(&path, audio, config.sample_rate));
+    match result {
+        Ok(()) => emit_debug_log(sink, "debug", &format!("Saved utterance to {}", path.display())),
+        Err(e) => {
+            log::warn!("Failed to save utterance to {}: {}", path.display(), e);
+            emit_debug_log(sink, "warn", &format!("Failed to save utterance: {}", e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::chunk::AudioChunk;
+    use crate::voice::events::CollectingSink;
+    use crate::voice::state_handlers::process_listening_state;
+    use crate::voice::state_machine::VoiceState;
+    use crate::voice::vad::VoiceActivityDetector;
+
+    /// Run a pre-rolled capture to speech end and return the audio sent to STT
+    fn captured_audio(include_preroll_in_stt: bool, trim_silence: bool, wake_lookback_ms: u32) -> CapturedUtterance {
+        captured_with(VoiceConfig {
+            include_preroll_in_stt,
+            trim_silence,
+            wake_lookback_ms,
+            ..Default::default()
+        })
+    }
+
+    /// [`captured_audio`] with any config, seeding 50ms of pre-roll
+    fn captured_with(config: VoiceConfig) -> CapturedUtterance {
+        let collected = Arc::new(CollectingSink::default());
+        let sink: EventSink = Some(collected.clone());
+        let config = VoiceConfig {
+            trailing_pad_ms: 0,
+            silence_timeout_ms: 160,
+            ..config
+        };
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let mut vad = VoiceActivityDetector::new(&config);
+        let mut detector = None;
+
+        let mut state_guard = state.write();
+        state_guard.config = config;
+        state_guard.state_machine.transition(VoiceEvent::WakeWordDetected);
+        state_guard.state_machine.seed_preroll(&[0.25; 800], 0.0);
+        drop(state_guard);
+
+        let loud = AudioChunk {
+            timestamp_ms: 50.0,
+            samples: vec![0.5; 1280],
+        };
+        let quiet = AudioChunk {
+            timestamp_ms: 130.0,
+            samples: vec![0.0; 1280],
+        };
+        process_listening_state(&sink, &state, &loud, &loud, &mut detector, &mut vad);
+        for _ in 0..20 {
+            if state.read().state_machine.state() != VoiceState::Listening {
+                break;
+            }
+            process_listening_state(&sink, &state, &quiet, &quiet, &mut detector, &mut vad);
+        }
+
+        let captured = collected.payloads("voice-audio-captured");
+        let payload = captured.into_iter().next().expect("capture sent to STT");
+        serde_json::from_value(payload).unwrap()
+    }
+
+    #[test]
+    fn test_preroll_included_in_stt() {
+        let audio = captured_audio(true, false, 0).samples;
+        assert_eq!(audio[..800], [0.25; 800]);
+        assert_eq!(audio[800], 0.5);
+    }
+
+    #[test]
+    fn test_preroll_excluded_from_stt() {
+        let included = captured_audio(true, false, 0).samples;
+        let excluded = captured_audio(false, false, 0).samples;
+        assert_eq!(excluded.len(), included.len() - 800);
+        assert_eq!(excluded[0], 0.5);
+    }
+
+    #[test]
+    fn test_lookback_prepends_capture_when_preroll_excluded() {
+        // 25ms of the 50ms pre-roll
+        let audio = captured_audio(false, false, 25).samples;
+        assert_eq!(audio[..400], [0.25; 400]);
+        assert_eq!(audio[400], 0.5);
+    }
+
+    #[test]
+    fn test_included_preroll_ignores_longer_lookback() {
+        // 25ms of pre-roll goes to STT even though 50ms was seeded for the lookback
+        let audio = captured_with(VoiceConfig {
+            preroll_ms: 25,
+            wake_lookback_ms: 50,
+            trim_silence: false,
+            ..Default::default()
+        })
+        .samples;
+        assert_eq!(audio[..400], [0.25; 400]);
+        assert_eq!(audio[400], 0.5);
+    }
+
+    #[test]
+    fn test_trailing_silence_trimmed() {
+        let untrimmed = captured_audio(false, false, 0);
+        assert!(untrimmed.samples.len() > 1280);
+
+        let trimmed = captured_audio(false, true, 0);
+        assert_eq!(trimmed.samples, vec![0.5; 1280]);
+        assert_eq!(trimmed.duration_ms, 80.0);
+        assert!(!trimmed.all_silent);
+    }
+
+    #[test]
+    fn test_push_to_talk_ignores_vad_until_release() {
+        let collected = Arc::new(CollectingSink::default());
+        let sink: EventSink = Some(collected.clone());
+        let config = VoiceConfig {
+            silence_timeout_ms: 160,
+            ..Default::default()
+        };
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let mut vad = VoiceActivityDetector::new(&config);
+        let mut detector = None;
+
+        let mut state_guard = state.write();
+        state_guard.config = config;
+        state_guard.state_machine.transition(VoiceEvent::PushToTalk);
+        drop(state_guard);
+
+        let loud = AudioChunk {
+            timestamp_ms: 0.0,
+            samples: vec![0.5; 1280],
+        };
+        let quiet = AudioChunk {
+            timestamp_ms: 80.0,
+            samples: vec![0.0; 1280],
+        };
+        process_listening_state(&sink, &state, &loud, &loud, &mut detector, &mut vad);
+        for _ in 0..20 {
+            process_listening_state(&sink, &state, &quiet, &quiet, &mut detector, &mut vad);
+        }
+        assert_eq!(state.read().state_machine.state(), VoiceState::Listening);
+
+        end_capture(&sink, &state, VoiceEvent::ManualRelease);
+        assert_eq!(state.read().state_machine.state(), VoiceState::Transcribing);
+        assert_eq!(collected.payloads("voice-audio-captured").len(), 1);
+    }
+}
@@ -1,99 +1,45 @@
 //! Voice state machine for managing voice interaction flow
 
-use serde::{Deserialize, Serialize};
-use std::time::Instant;
-
-/// Voice system states
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub enum VoiceState {
-    /// Idle - listening for wake word
-    Idle,
-    /// Listening - wake word detected, capturing user speech
-    Listening,
-    /// Transcribing - sending audio to STT
-    Transcribing,
-    /// Processing - waiting for AI response
-    Processing,
-    /// Speaking - playing TTS response
-    Speaking,
-}
-
-impl Default for VoiceState {
-    fn default() -> Self {
-        Self::Idle
-    }
-}
-
-impl std::fmt::Display for VoiceState {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            VoiceState::Idle => write!(f, "Idle"),
-            VoiceState::Listening => write!(f, "Listening"),
-            VoiceState::Transcribing => write!(f, "Transcribing"),
-            VoiceState::Processing => write!(f, "Processing"),
-            VoiceState::Speaking => write!(f, "Speaking"),
-        }
-    }
-}
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::watch;
 
-/// Events that trigger state transitions
-#[derive(Debug, Clone)]
-pub enum VoiceEvent {
-    /// Wake word was detected
-    WakeWordDetected,
-    /// User manually triggered listening (button press)
-    ManualTrigger,
-    /// VAD detected end of speech
-    VadSpeechEnd,
-    /// Transcription completed with text
-    TranscriptionComplete(String),
-    /// AI response is ready
-    ResponseReady(String),
-    /// TTS finished speaking
-    SpeechComplete,
-    /// User spoke during TTS (barge-in)
-    BargeIn,
-    /// Timeout occurred
-    Timeout,
-    /// An error occurred
-    Error(String),
-    /// Cancel current operation
-    Cancel,
-}
+use super::chunk::unix_time_ms;
+pub use super::states::{RejectedTransition, StateAction, StateChange, TransitionResult, VoiceEvent, VoiceState};
 
-/// Result of a state transition
-#[derive(Debug)]
-pub struct TransitionResult {
-    pub new_state: VoiceState,
-    pub action: Option<StateAction>,
-}
+/// Upper bound on captured audio (60s at 16kHz) so a stuck capture can't exhaust memory
+pub const MAX_CAPTURED_SAMPLES: usize = 16000 * 60;
 
-/// Actions to perform after state transition
-#[derive(Debug, Clone)]
-pub enum StateAction {
-    /// Start audio capture for user speech
-    StartCapture,
-    /// Stop audio capture
-    StopCapture,
-    /// Send audio to STT service
-    SendToStt(Vec<f32>),
-    /// Send text to AI for processing
-    ProcessText(String),
-    /// Play TTS response
-    PlayTts(String),
-    /// Stop TTS playback
-    StopTts,
-    /// Emit error event
-    EmitError(String),
-}
+mod capture;
+mod turns;
 
 /// Voice state machine
 #[derive(Debug)]
 pub struct VoiceStateMachine {
     state: VoiceState,
     last_transition: Instant,
+    /// Wall-clock time of `last_transition`, for the frontend
+    entered_at_ms: f64,
     captured_audio: Vec<f32>,
+    /// Capture time of the first sample of the current (or last) utterance, in ms since the UNIX epoch
+    capture_start_ms: Option<f64>,
+    /// Number of pre-roll samples at the front of the current (or last) capture
+    preroll_samples: usize,
+    /// How long to listen for a follow-up after speaking (zero = go back to Idle)
+    follow_up_window: Duration,
+    /// Treat an empty or whitespace-only transcription as no speech
+    skip_empty_transcriptions: bool,
+    /// In a follow-up Listening turn that hasn't heard speech yet
+    awaiting_follow_up: bool,
+    /// In a push-to-talk capture, which ends on release rather than on VAD
+    manual_mode: bool,
+    /// Message of the error being held in the Error state
+    error: Option<String>,
+    /// Latest interim transcription of the current utterance
+    partial_transcription: Option<String>,
+    /// Incremented each time Listening is entered, identifying the current capture
+    capture_id: u64,
+    /// Publishes every state change to async subscribers
+    state_tx: watch::Sender<VoiceState>,
 }
 
 impl Default for VoiceStateMachine {
@@ -107,45 +53,100 @@ impl VoiceStateMachine {
         Self {
             state: VoiceState::Idle,
             last_transition: Instant::now(),
+            entered_at_ms: unix_time_ms(SystemTime::now()),
             captured_audio: Vec::new(),
+            capture_start_ms: None,
+            preroll_samples: 0,
+            follow_up_window: Duration::ZERO,
+            skip_empty_transcriptions: true,
+            awaiting_follow_up: false,
+            manual_mode: false,
+            error: None,
+            partial_transcription: None,
+            capture_id: 0,
+            state_tx: watch::channel(VoiceState::Idle).0,
         }
     }
 
+    /// Subscribe to state changes
+    pub fn subscribe(&self) -> watch::Receiver<VoiceState> {
+        self.state_tx.subscribe()
+    }
+
     /// Get current state
     pub fn state(&self) -> VoiceState {
         self.state
     }
 
     /// Get time since last transition
-    pub fn time_in_state(&self) -> std::time::Duration {
+    pub fn time_in_state(&self) -> Duration {
         self.last_transition.elapsed()
     }
 
-    /// Add audio samples during Listening state
-    pub fn add_audio(&mut self, samples: &[f32]) {
-        if self.state == VoiceState::Listening {
-            self.captured_audio.extend_from_slice(samples);
-        }
+    /// When the current state was entered, in ms since the UNIX epoch
+    pub fn entered_at_ms(&self) -> f64 {
+        self.entered_at_ms
+    }
+
+    /// Identifier of the current (or last) capture, for matching asynchronous replies to it
+    pub fn capture_id(&self) -> u64 {
+        self.capture_id
+    }
+
+    /// Whether the current capture is push-to-talk, ending only on release
+    pub fn manual_mode(&self) -> bool {
+        self.manual_mode
+    }
+
+    /// Message of the error held in the Error state
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
     }
 
     /// Process an event and return the transition result
     pub fn transition(&mut self, event: VoiceEvent) -> TransitionResult {
+        let event_name = event.name();
+        let previous_state = self.state;
+        let mut rejected = None;
         let (new_state, action) = match (&self.state, event) {
             // From Idle
             (VoiceState::Idle, VoiceEvent::WakeWordDetected) => {
                 self.captured_audio.clear();
+                self.preroll_samples = 0;
                 (VoiceState::Listening, Some(StateAction::StartCapture))
             }
             (VoiceState::Idle, VoiceEvent::ManualTrigger) => {
                 self.captured_audio.clear();
+                self.preroll_samples = 0;
                 (VoiceState::Listening, Some(StateAction::StartCapture))
             }
+            (VoiceState::Idle, VoiceEvent::PushToTalk) => {
+                self.captured_audio.clear();
+                self.preroll_samples = 0;
+                self.manual_mode = true;
+                (VoiceState::Listening, Some(StateAction::StartCapture))
+            }
+            // Nothing to cancel, but the cancel button is never an invalid press
+            (VoiceState::Idle, VoiceEvent::Cancel) => (VoiceState::Idle, None),
 
             // From Listening
-            (VoiceState::Listening, VoiceEvent::VadSpeechEnd) => {
+            (VoiceState::Listening, VoiceEvent::VadSpeechEnd) if self.manual_mode => {
+                // A pause mid-sentence mustn't end a push-to-talk capture
+                rejected = Some(RejectedTransition {
+                    from: VoiceState::Listening,
+                    event: event_name,
+                    reason: "push-to-talk capture ends on release",
+                });
+                (VoiceState::Listening, None)
+            }
+            (VoiceState::Listening, VoiceEvent::VadSpeechEnd | VoiceEvent::ManualRelease) => {
                 let audio = std::mem::take(&mut self.captured_audio);
                 (VoiceState::Transcribing, Some(StateAction::SendToStt(audio)))
             }
+            (VoiceState::Listening, VoiceEvent::SpeechTooShort) => {
+                self.captured_audio.clear();
+                (VoiceState::Idle, Some(StateAction::StopCapture))
+            }
             (VoiceState::Listening, VoiceEvent::Timeout) => {
                 self.captured_audio.clear();
                 (VoiceState::Idle, Some(StateAction::StopCapture))
@@ -155,58 +156,114 @@ impl VoiceStateMachine {
                 (VoiceState::Idle, Some(StateAction::StopCapture))
             }
 
+            // Interim text streams in while speaking and until the final transcript
+            (VoiceState::Listening | VoiceState::Transcribing, VoiceEvent::PartialTranscription(text)) => {
+                self.partial_transcription = Some(text.clone());
+                (self.state, Some(StateAction::ShowPartial(text)))
+            }
+
             // From Transcribing
+            (VoiceState::Transcribing, VoiceEvent::TranscriptionComplete(ref text))
+                if self.skip_empty_transcriptions && text.trim().is_empty() =>
+            {
+                (VoiceState::Idle, Some(StateAction::NoSpeech))
+            }
+            (VoiceState::Transcribing, VoiceEvent::NoSpeechDetected) => (VoiceState::Idle, Some(StateAction::NoSpeech)),
             (VoiceState::Transcribing, VoiceEvent::TranscriptionComplete(text)) => {
                 (VoiceState::Processing, Some(StateAction::ProcessText(text)))
             }
-            (VoiceState::Transcribing, VoiceEvent::Error(e)) => {
-                (VoiceState::Idle, Some(StateAction::EmitError(e)))
+            (VoiceState::Transcribing, VoiceEvent::Timeout) => (VoiceState::Idle, None),
+            // A late transcript is then rejected in Idle
+            (VoiceState::Transcribing, VoiceEvent::Cancel) => {
+                self.captured_audio.clear();
+                (VoiceState::Idle, None)
             }
 
             // From Processing
             (VoiceState::Processing, VoiceEvent::ResponseReady(response)) => {
                 (VoiceState::Speaking, Some(StateAction::PlayTts(response)))
             }
-            (VoiceState::Processing, VoiceEvent::Error(e)) => {
-                (VoiceState::Idle, Some(StateAction::EmitError(e)))
+            (VoiceState::Processing, VoiceEvent::Timeout) => (VoiceState::Idle, None),
+            (VoiceState::Processing, VoiceEvent::Cancel) => {
+                self.captured_audio.clear();
+                (VoiceState::Idle, None)
             }
 
             // From Speaking
+            (VoiceState::Speaking, VoiceEvent::SpeechComplete) if !self.follow_up_window.is_zero() => {
+                self.captured_audio.clear();
+                self.preroll_samples = 0;
+                self.awaiting_follow_up = true;
+                (VoiceState::Listening, Some(StateAction::StartCapture))
+            }
             (VoiceState::Speaking, VoiceEvent::SpeechComplete) => {
                 (VoiceState::Idle, None)
             }
             (VoiceState::Speaking, VoiceEvent::BargeIn) => {
                 self.captured_audio.clear();
+                self.preroll_samples = 0;
                 (VoiceState::Listening, Some(StateAction::StopTts))
             }
             (VoiceState::Speaking, VoiceEvent::Cancel) => {
                 (VoiceState::Idle, Some(StateAction::StopTts))
             }
 
+            // From Error, once acknowledged or after the hold time
+            (VoiceState::Error, VoiceEvent::Cancel | VoiceEvent::Timeout) => {
+                self.error = None;
+                (VoiceState::Idle, None)
+            }
+
             // Global error handling
             (_, VoiceEvent::Error(e)) => {
                 self.captured_audio.clear();
-                (VoiceState::Idle, Some(StateAction::EmitError(e)))
+                self.error = Some(e.clone());
+                (VoiceState::Error, Some(StateAction::EmitError(e)))
             }
 
             // Invalid transitions - stay in current state
-            (current, _) => (*current, None),
+            (current, _) => {
+                let rejection = RejectedTransition {
+                    from: *current,
+                    event: event_name,
+                    reason: "event not valid in state",
+                };
+                log::debug!("Rejected {} in {:?}: {}", rejection.event, rejection.from, rejection.reason);
+                rejected = Some(rejection);
+                (*current, None)
+            }
         };
 
-        if new_state != self.state {
+        if new_state != previous_state {
+            if previous_state == VoiceState::Listening {
+                self.awaiting_follow_up = false;
+                self.manual_mode = false;
+            }
+            if new_state == VoiceState::Listening {
+                self.partial_transcription = None;
+                self.capture_id += 1;
+            }
             self.state = new_state;
             self.last_transition = Instant::now();
-            log::debug!("Voice state transition: {:?} -> {:?}", self.state, new_state);
+            self.entered_at_ms = unix_time_ms(SystemTime::now());
+            self.state_tx.send_replace(new_state);
+            log::debug!("Voice state transition: {:?} -> {:?}", previous_state, new_state);
         }
 
-        TransitionResult { new_state, action }
+        TransitionResult { previous_state, new_state, action, rejected }
     }
 
     /// Force reset to Idle state
     pub fn reset(&mut self) {
+        if self.state != VoiceState::Idle {
+            self.state_tx.send_replace(VoiceState::Idle);
+        }
         self.state = VoiceState::Idle;
         self.last_transition = Instant::now();
+        self.entered_at_ms = unix_time_ms(SystemTime::now());
         self.captured_audio.clear();
+        self.error = None;
+        self.partial_transcription = None;
     }
 }
 
@@ -220,10 +277,29 @@ mod tests {
         assert_eq!(sm.state(), VoiceState::Idle);
     }
 
+    #[test]
+    fn test_invalid_event_reports_rejection() {
+        let mut sm = VoiceStateMachine::new();
+        let result = sm.transition(VoiceEvent::ResponseReady("hello".to_string()));
+        assert_eq!(result.previous_state, VoiceState::Idle);
+        assert_eq!(result.new_state, VoiceState::Idle);
+        assert_eq!(
+            result.rejected,
+            Some(RejectedTransition {
+                from: VoiceState::Idle,
+                event: "ResponseReady",
+                reason: "event not valid in state",
+            })
+        );
+
+        assert!(sm.transition(VoiceEvent::ManualTrigger).rejected.is_none());
+    }
+
     #[test]
     fn test_wake_word_transition() {
         let mut sm = VoiceStateMachine::new();
         let result = sm.transition(VoiceEvent::WakeWordDetected);
+        assert_eq!(result.previous_state, VoiceState::Idle);
         assert_eq!(result.new_state, VoiceState::Listening);
         assert!(matches!(result.action, Some(StateAction::StartCapture)));
     }
@@ -256,7 +332,8 @@ mod tests {
         assert_eq!(sm.state(), VoiceState::Speaking);
 
         // Speech done -> Idle
-        sm.transition(VoiceEvent::SpeechComplete);
+        let result = sm.transition(VoiceEvent::SpeechComplete);
+        assert_eq!(result.previous_state, VoiceState::Speaking);
         assert_eq!(sm.state(), VoiceState::Idle);
     }
 
@@ -270,26 +347,23 @@ mod tests {
 
         // Barge in during speaking
         let result = sm.transition(VoiceEvent::BargeIn);
+        assert_eq!(result.previous_state, VoiceState::Speaking);
         assert_eq!(result.new_state, VoiceState::Listening);
         assert!(matches!(result.action, Some(StateAction::StopTts)));
     }
 
     #[test]
-    fn test_timeout() {
+    fn test_subscribe_sees_transitions() {
         let mut sm = VoiceStateMachine::new();
-        sm.transition(VoiceEvent::WakeWordDetected);
+        let mut rx = sm.subscribe();
+        assert!(!rx.has_changed().unwrap());
 
-        let result = sm.transition(VoiceEvent::Timeout);
-        assert_eq!(result.new_state, VoiceState::Idle);
-    }
-
-    #[test]
-    fn test_error_resets_to_idle() {
-        let mut sm = VoiceStateMachine::new();
         sm.transition(VoiceEvent::WakeWordDetected);
-        sm.transition(VoiceEvent::VadSpeechEnd);
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(*rx.borrow_and_update(), VoiceState::Listening);
 
-        let result = sm.transition(VoiceEvent::Error("test error".to_string()));
-        assert_eq!(result.new_state, VoiceState::Idle);
+        // Invalid transitions don't notify
+        sm.transition(VoiceEvent::SpeechComplete);
+        assert!(!rx.has_changed().unwrap());
     }
 }
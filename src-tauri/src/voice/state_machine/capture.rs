@@ -0,0 +1,156 @@
+//! Audio and interim text collected for the current utterance
+
+use super::{VoiceState, VoiceStateMachine, MAX_CAPTURED_SAMPLES};
+
+impl VoiceStateMachine {
+    /// Add audio samples during Listening state
+    ///
+    /// Audio beyond `MAX_CAPTURED_SAMPLES` is dropped.
+    pub fn add_audio(&mut self, samples: &[f32]) {
+        if self.state == VoiceState::Listening {
+            let room = MAX_CAPTURED_SAMPLES.saturating_sub(self.captured_audio.len());
+            self.captured_audio.extend_from_slice(&samples[..samples.len().min(room)]);
+        }
+    }
+
+    /// Add audio samples captured at `timestamp_ms`, tracking the utterance start time
+    pub fn add_audio_at(&mut self, samples: &[f32], timestamp_ms: f64) {
+        if self.state == VoiceState::Listening && self.captured_audio.is_empty() {
+            self.capture_start_ms = Some(timestamp_ms);
+        }
+        self.add_audio(samples);
+    }
+
+    /// Seed a fresh capture with audio from just before activation
+    ///
+    /// Only applies right after entering Listening, before any other audio was added.
+    pub fn seed_preroll(&mut self, samples: &[f32], start_ms: f64) {
+        if self.state == VoiceState::Listening && self.captured_audio.is_empty() && !samples.is_empty() {
+            self.capture_start_ms = Some(start_ms);
+            self.captured_audio.extend_from_slice(samples);
+            self.preroll_samples = self.captured_audio.len();
+        }
+    }
+
+    /// Number of pre-roll samples at the front of the current or most recent capture
+    pub fn preroll_samples(&self) -> usize {
+        self.preroll_samples
+    }
+
+    /// Number of samples allocated for captured audio
+    pub fn captured_allocated(&self) -> usize {
+        self.captured_audio.capacity()
+    }
+
+    /// Free the captured audio allocation unless a capture is in progress
+    pub fn release_captured_audio(&mut self) {
+        if self.state != VoiceState::Listening {
+            self.captured_audio = Vec::new();
+        }
+    }
+
+    /// Latest interim transcription of the current utterance
+    pub fn partial_transcription(&self) -> Option<&str> {
+        self.partial_transcription.as_deref()
+    }
+
+    /// Capture time of the first sample of the current or most recent utterance
+    pub fn capture_start_ms(&self) -> Option<f64> {
+        self.capture_start_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::state_machine::{StateAction, VoiceEvent};
+
+    #[test]
+    fn test_capture_start_timestamp() {
+        let mut sm = VoiceStateMachine::new();
+        sm.add_audio_at(&[0.0; 4], 500.0); // Ignored while idle
+        sm.transition(VoiceEvent::WakeWordDetected);
+
+        sm.add_audio_at(&[0.0; 4], 1000.0);
+        sm.add_audio_at(&[0.0; 4], 1080.0);
+        sm.transition(VoiceEvent::VadSpeechEnd);
+
+        assert_eq!(sm.capture_start_ms(), Some(1000.0));
+    }
+
+    #[test]
+    fn test_preroll_seeds_capture() {
+        use crate::voice::buffer::AudioBuffer;
+
+        // Ramp through a 4-sample pre-roll buffer: only the last 4 samples survive
+        let mut preroll = AudioBuffer::new(4);
+        let ramp: Vec<f32> = (0..10).map(|i| i as f32).collect();
+        preroll.push_samples(&ramp);
+
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::WakeWordDetected);
+        sm.seed_preroll(&preroll.get_all(), 900.0);
+        sm.add_audio_at(&[10.0, 11.0], 1000.0);
+
+        let result = sm.transition(VoiceEvent::VadSpeechEnd);
+        let Some(StateAction::SendToStt(audio)) = result.action else {
+            panic!("expected SendToStt");
+        };
+        assert_eq!(audio, vec![6.0, 7.0, 8.0, 9.0, 10.0, 11.0]);
+        assert_eq!(sm.capture_start_ms(), Some(900.0));
+        assert_eq!(sm.preroll_samples(), 4);
+
+        sm.reset();
+        sm.transition(VoiceEvent::ManualTrigger);
+        assert_eq!(sm.preroll_samples(), 0);
+    }
+
+    #[test]
+    fn test_captured_audio_is_capped() {
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::WakeWordDetected);
+        sm.add_audio(&vec![0.0; MAX_CAPTURED_SAMPLES - 10]);
+        sm.add_audio(&[0.0; 100]);
+
+        let Some(StateAction::SendToStt(audio)) = sm.transition(VoiceEvent::VadSpeechEnd).action else {
+            panic!("expected SendToStt");
+        };
+        assert_eq!(audio.len(), MAX_CAPTURED_SAMPLES);
+    }
+
+    #[test]
+    fn test_manual_release_ends_capture() {
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::PushToTalk);
+        assert!(sm.manual_mode());
+        sm.add_audio(&[0.5; 4]);
+
+        let result = sm.transition(VoiceEvent::ManualRelease);
+        assert_eq!(result.new_state, VoiceState::Transcribing);
+        assert!(matches!(result.action, Some(StateAction::SendToStt(ref audio)) if audio.len() == 4));
+        assert!(!sm.manual_mode());
+    }
+
+    #[test]
+    fn test_partial_transcription_is_retained_without_transition() {
+        let mut sm = VoiceStateMachine::new();
+        assert!(sm.transition(VoiceEvent::PartialTranscription("hi".to_string())).rejected.is_some());
+
+        sm.transition(VoiceEvent::WakeWordDetected);
+        let result = sm.transition(VoiceEvent::PartialTranscription("what's the".to_string()));
+        assert_eq!(result.new_state, VoiceState::Listening);
+        assert!(matches!(result.action, Some(StateAction::ShowPartial(ref text)) if text == "what's the"));
+
+        sm.transition(VoiceEvent::VadSpeechEnd);
+        let result = sm.transition(VoiceEvent::PartialTranscription("what's the weather".to_string()));
+        assert_eq!(result.new_state, VoiceState::Transcribing);
+        assert_eq!(sm.partial_transcription(), Some("what's the weather"));
+
+        // Kept through the rest of the turn, cleared by the next capture
+        sm.transition(VoiceEvent::TranscriptionComplete("what's the weather".to_string()));
+        assert_eq!(sm.partial_transcription(), Some("what's the weather"));
+        sm.transition(VoiceEvent::Cancel);
+        sm.transition(VoiceEvent::ManualTrigger);
+        assert_eq!(sm.partial_transcription(), None);
+    }
+}
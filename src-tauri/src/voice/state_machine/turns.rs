@@ -0,0 +1,260 @@
+//! How a turn ends: timeouts, cancels, errors, follow-ups and blank transcriptions
+
+use std::time::{Duration, Instant};
+
+use super::{TransitionResult, VoiceEvent, VoiceState, VoiceStateMachine};
+
+impl VoiceStateMachine {
+    /// Pretend the current state was entered `elapsed` earlier, so tests needn't sleep
+    #[cfg(test)]
+    pub(crate) fn backdate_transition(&mut self, elapsed: Duration) {
+        self.last_transition -= elapsed;
+    }
+
+    /// Listen for a follow-up this long after speaking (zero disables follow-ups)
+    pub fn set_follow_up_window(&mut self, window: Duration) {
+        self.follow_up_window = window;
+    }
+
+    /// Whether a blank transcription returns to Idle instead of being processed
+    pub fn set_skip_empty_transcriptions(&mut self, skip: bool) {
+        self.skip_empty_transcriptions = skip;
+    }
+
+    /// Timeout of the current follow-up turn, until the user starts speaking
+    pub fn follow_up_timeout(&self) -> Option<Duration> {
+        (self.state == VoiceState::Listening && self.awaiting_follow_up).then_some(self.follow_up_window)
+    }
+
+    /// Note that speech was heard, so a follow-up turn uses the normal listening timeout
+    pub fn follow_up_heard(&mut self) {
+        self.awaiting_follow_up = false;
+    }
+
+    /// Fire `Timeout` if the current state has lasted at least `timeout` as of `now`
+    ///
+    /// Taking `now` as a parameter keeps the check testable without sleeping.
+    /// A held push-to-talk capture never times out; it ends on release.
+    pub fn check_timeout(&mut self, now: Instant, timeout: Duration) -> Option<TransitionResult> {
+        if self.manual_mode || now.saturating_duration_since(self.last_transition) < timeout {
+            return None;
+        }
+        log::warn!("Voice state {} timed out after {:?}", self.state, timeout);
+        Some(self.transition(VoiceEvent::Timeout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::state_machine::StateAction;
+
+    #[test]
+    fn test_push_to_talk_suspends_listening_timeout() {
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::PushToTalk);
+        let later = Instant::now() + Duration::from_secs(3600);
+        assert!(sm.check_timeout(later, Duration::from_secs(10)).is_none());
+        assert_eq!(sm.state(), VoiceState::Listening);
+
+        // A tap-to-talk capture still times out
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::ManualTrigger);
+        let result = sm.check_timeout(later, Duration::from_secs(10)).unwrap();
+        assert_eq!(result.new_state, VoiceState::Idle);
+    }
+
+    #[test]
+    fn test_vad_ignored_in_manual_mode() {
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::PushToTalk);
+
+        let result = sm.transition(VoiceEvent::VadSpeechEnd);
+        assert_eq!(result.new_state, VoiceState::Listening);
+        assert!(result.rejected.is_some());
+
+        // A tap-to-talk capture still ends on VAD
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::ManualTrigger);
+        assert_eq!(sm.transition(VoiceEvent::VadSpeechEnd).new_state, VoiceState::Transcribing);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::WakeWordDetected);
+
+        let result = sm.transition(VoiceEvent::Timeout);
+        assert_eq!(result.previous_state, VoiceState::Listening);
+        assert_eq!(result.new_state, VoiceState::Idle);
+    }
+
+    #[test]
+    fn test_error_clears_after_hold_time() {
+        let hold = Duration::from_secs(5);
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::Error("no microphone".to_string()));
+        let entered = Instant::now();
+
+        assert!(sm.check_timeout(entered + Duration::from_secs(1), hold).is_none());
+        let result = sm.check_timeout(entered + Duration::from_secs(6), hold).unwrap();
+        assert_eq!(result.new_state, VoiceState::Idle);
+        assert_eq!(sm.error(), None);
+    }
+
+    #[test]
+    fn test_timeout_returns_to_idle() {
+        let timeout = Duration::from_secs(10);
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::WakeWordDetected);
+        let entered = Instant::now();
+
+        assert!(sm.check_timeout(entered + Duration::from_secs(5), timeout).is_none());
+        assert_eq!(sm.state(), VoiceState::Listening);
+
+        let result = sm.check_timeout(entered + Duration::from_secs(11), timeout).unwrap();
+        assert_eq!(result.new_state, VoiceState::Idle);
+
+        // A wedged backend recovers too
+        sm.transition(VoiceEvent::ManualTrigger);
+        sm.transition(VoiceEvent::VadSpeechEnd);
+        let result = sm.check_timeout(Instant::now() + Duration::from_secs(11), timeout).unwrap();
+        assert_eq!(result.new_state, VoiceState::Idle);
+    }
+
+    #[test]
+    fn test_follow_up_taken() {
+        let mut sm = VoiceStateMachine::new();
+        sm.set_follow_up_window(Duration::from_secs(5));
+        sm.transition(VoiceEvent::ManualTrigger);
+        sm.transition(VoiceEvent::VadSpeechEnd);
+        sm.transition(VoiceEvent::TranscriptionComplete("what time is it".to_string()));
+        sm.transition(VoiceEvent::ResponseReady("noon".to_string()));
+
+        let result = sm.transition(VoiceEvent::SpeechComplete);
+        assert_eq!(result.new_state, VoiceState::Listening);
+        assert!(matches!(result.action, Some(StateAction::StartCapture)));
+        assert_eq!(sm.follow_up_timeout(), Some(Duration::from_secs(5)));
+
+        sm.follow_up_heard();
+        assert_eq!(sm.follow_up_timeout(), None);
+        sm.add_audio(&[0.1; 4]);
+        let Some(StateAction::SendToStt(audio)) = sm.transition(VoiceEvent::VadSpeechEnd).action else {
+            panic!("expected SendToStt");
+        };
+        assert_eq!(audio.len(), 4);
+    }
+
+    #[test]
+    fn test_follow_up_expires() {
+        let window = Duration::from_secs(5);
+        let mut sm = VoiceStateMachine::new();
+        sm.set_follow_up_window(window);
+        sm.transition(VoiceEvent::ManualTrigger);
+        sm.transition(VoiceEvent::VadSpeechEnd);
+        sm.transition(VoiceEvent::TranscriptionComplete("hi".to_string()));
+        sm.transition(VoiceEvent::ResponseReady("hello".to_string()));
+        sm.transition(VoiceEvent::SpeechComplete);
+        let entered = Instant::now();
+
+        let timeout = sm.follow_up_timeout().unwrap();
+        assert!(sm.check_timeout(entered + Duration::from_secs(2), timeout).is_none());
+        let result = sm.check_timeout(entered + Duration::from_secs(6), timeout).unwrap();
+        assert_eq!(result.new_state, VoiceState::Idle);
+        assert_eq!(sm.follow_up_timeout(), None);
+
+        // Without a window, speaking ends in Idle
+        sm.set_follow_up_window(Duration::ZERO);
+        sm.transition(VoiceEvent::ManualTrigger);
+        sm.transition(VoiceEvent::VadSpeechEnd);
+        sm.transition(VoiceEvent::TranscriptionComplete("hi".to_string()));
+        sm.transition(VoiceEvent::ResponseReady("hello".to_string()));
+        assert_eq!(sm.transition(VoiceEvent::SpeechComplete).new_state, VoiceState::Idle);
+    }
+
+    fn transcribing() -> VoiceStateMachine {
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::WakeWordDetected);
+        sm.transition(VoiceEvent::VadSpeechEnd);
+        sm
+    }
+
+    #[test]
+    fn test_empty_transcription_returns_to_idle() {
+        for text in ["", "  \n\t "] {
+            let mut sm = transcribing();
+            let result = sm.transition(VoiceEvent::TranscriptionComplete(text.to_string()));
+            assert_eq!(result.new_state, VoiceState::Idle);
+            assert!(matches!(result.action, Some(StateAction::NoSpeech)));
+        }
+
+        let mut sm = transcribing();
+        let result = sm.transition(VoiceEvent::TranscriptionComplete(" hello ".to_string()));
+        assert_eq!(result.new_state, VoiceState::Processing);
+        assert!(matches!(result.action, Some(StateAction::ProcessText(ref text)) if text == " hello "));
+
+        let mut sm = transcribing();
+        assert!(matches!(sm.transition(VoiceEvent::NoSpeechDetected).action, Some(StateAction::NoSpeech)));
+        assert_eq!(sm.state(), VoiceState::Idle);
+    }
+
+    #[test]
+    fn test_empty_transcription_processed_when_not_skipped() {
+        let mut sm = transcribing();
+        sm.set_skip_empty_transcriptions(false);
+        sm.transition(VoiceEvent::TranscriptionComplete(" ".to_string()));
+        assert_eq!(sm.state(), VoiceState::Processing);
+    }
+
+    #[test]
+    fn test_error_is_held_until_cancelled() {
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::WakeWordDetected);
+        sm.transition(VoiceEvent::VadSpeechEnd);
+
+        let result = sm.transition(VoiceEvent::Error("test error".to_string()));
+        assert_eq!(result.previous_state, VoiceState::Transcribing);
+        assert_eq!(result.new_state, VoiceState::Error);
+        assert!(matches!(result.action, Some(StateAction::EmitError(ref e)) if e == "test error"));
+        assert_eq!(sm.error(), Some("test error"));
+
+        // Nothing else starts until the error is acknowledged
+        assert!(sm.transition(VoiceEvent::WakeWordDetected).rejected.is_some());
+
+        assert_eq!(sm.transition(VoiceEvent::Cancel).new_state, VoiceState::Idle);
+        assert_eq!(sm.error(), None);
+    }
+
+    #[test]
+    fn test_cancel_while_transcribing_or_processing() {
+        let mut sm = VoiceStateMachine::new();
+        sm.transition(VoiceEvent::WakeWordDetected);
+        sm.transition(VoiceEvent::VadSpeechEnd);
+
+        let result = sm.transition(VoiceEvent::Cancel);
+        assert_eq!(result.previous_state, VoiceState::Transcribing);
+        assert_eq!(result.new_state, VoiceState::Idle);
+        assert!(result.rejected.is_none());
+
+        // The transcript arriving after the cancel is dropped
+        assert!(sm.transition(VoiceEvent::TranscriptionComplete("hello".to_string())).rejected.is_some());
+
+        sm.transition(VoiceEvent::WakeWordDetected);
+        sm.transition(VoiceEvent::VadSpeechEnd);
+        sm.transition(VoiceEvent::TranscriptionComplete("hello".to_string()));
+        assert_eq!(sm.state(), VoiceState::Processing);
+
+        let result = sm.transition(VoiceEvent::Cancel);
+        assert_eq!(result.new_state, VoiceState::Idle);
+        assert!(result.rejected.is_none());
+        assert!(sm.transition(VoiceEvent::ResponseReady("hi".to_string())).rejected.is_some());
+    }
+
+    #[test]
+    fn test_cancel_in_idle_is_accepted() {
+        let mut sm = VoiceStateMachine::new();
+        let result = sm.transition(VoiceEvent::Cancel);
+        assert_eq!(result.new_state, VoiceState::Idle);
+        assert!(result.rejected.is_none());
+    }
+}
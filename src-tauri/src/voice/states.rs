@@ -0,0 +1,143 @@
+//! States, events and actions of the voice state machine
+
+use serde::{Deserialize, Serialize};
+
+/// Voice system states
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VoiceState {
+    /// Idle - listening for wake word
+    #[default]
+    Idle,
+    /// Listening - wake word detected, capturing user speech
+    Listening,
+    /// Transcribing - sending audio to STT
+    Transcribing,
+    /// Processing - waiting for AI response
+    Processing,
+    /// Speaking - playing TTS response
+    Speaking,
+    /// Error - something failed; held until cancelled or `error_hold_ms` passes
+    Error,
+}
+
+impl std::fmt::Display for VoiceState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VoiceState::Idle => write!(f, "Idle"),
+            VoiceState::Listening => write!(f, "Listening"),
+            VoiceState::Transcribing => write!(f, "Transcribing"),
+            VoiceState::Processing => write!(f, "Processing"),
+            VoiceState::Speaking => write!(f, "Speaking"),
+            VoiceState::Error => write!(f, "Error"),
+        }
+    }
+}
+
+/// Payload of `voice-state-changed`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct StateChange {
+    pub state: VoiceState,
+    /// When the state was entered, in ms since the UNIX epoch
+    pub entered_at_ms: f64,
+}
+
+/// Events that trigger state transitions
+#[derive(Debug, Clone)]
+pub enum VoiceEvent {
+    /// Wake word was detected
+    WakeWordDetected,
+    /// User manually triggered listening (button press)
+    ManualTrigger,
+    /// User pressed and is holding the push-to-talk button
+    PushToTalk,
+    /// User released the push-to-talk button (also ends a manual capture early)
+    ManualRelease,
+    /// VAD detected end of speech
+    VadSpeechEnd,
+    /// VAD detected end of speech, but too little of it to be an utterance
+    SpeechTooShort,
+    /// Interim STT hypothesis while the user is still speaking
+    PartialTranscription(String),
+    /// Transcription completed with text
+    TranscriptionComplete(String),
+    /// Transcription finished without any intelligible speech
+    NoSpeechDetected,
+    /// AI response is ready
+    ResponseReady(String),
+    /// TTS finished speaking
+    SpeechComplete,
+    /// User spoke during TTS (barge-in)
+    BargeIn,
+    /// Timeout occurred
+    Timeout,
+    /// An error occurred
+    Error(String),
+    /// Cancel current operation
+    Cancel,
+}
+
+impl VoiceEvent {
+    /// Event name without its payload, for logs and diagnostics
+    pub fn name(&self) -> &'static str {
+        match self {
+            VoiceEvent::WakeWordDetected => "WakeWordDetected",
+            VoiceEvent::ManualTrigger => "ManualTrigger",
+            VoiceEvent::PushToTalk => "PushToTalk",
+            VoiceEvent::ManualRelease => "ManualRelease",
+            VoiceEvent::VadSpeechEnd => "VadSpeechEnd",
+            VoiceEvent::SpeechTooShort => "SpeechTooShort",
+            VoiceEvent::PartialTranscription(_) => "PartialTranscription",
+            VoiceEvent::TranscriptionComplete(_) => "TranscriptionComplete",
+            VoiceEvent::NoSpeechDetected => "NoSpeechDetected",
+            VoiceEvent::ResponseReady(_) => "ResponseReady",
+            VoiceEvent::SpeechComplete => "SpeechComplete",
+            VoiceEvent::BargeIn => "BargeIn",
+            VoiceEvent::Timeout => "Timeout",
+            VoiceEvent::Error(_) => "Error",
+            VoiceEvent::Cancel => "Cancel",
+        }
+    }
+}
+
+/// Result of a state transition
+#[derive(Debug)]
+pub struct TransitionResult {
+    /// State before the event, equal to `new_state` when nothing changed
+    pub previous_state: VoiceState,
+    pub new_state: VoiceState,
+    pub action: Option<StateAction>,
+    /// Why nothing happened, when the event isn't valid in the current state
+    pub rejected: Option<RejectedTransition>,
+}
+
+/// An event the state machine ignored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RejectedTransition {
+    pub from: VoiceState,
+    pub event: &'static str,
+    pub reason: &'static str,
+}
+
+/// Actions to perform after state transition
+#[derive(Debug, Clone)]
+pub enum StateAction {
+    /// Start audio capture for user speech
+    StartCapture,
+    /// Stop audio capture
+    StopCapture,
+    /// Send audio to STT service
+    SendToStt(Vec<f32>),
+    /// Show interim transcription text
+    ShowPartial(String),
+    /// Send text to AI for processing
+    ProcessText(String),
+    /// Tell the user nothing was heard
+    NoSpeech,
+    /// Play TTS response
+    PlayTts(String),
+    /// Stop TTS playback
+    StopTts,
+    /// Emit error event
+    EmitError(String),
+}
@@ -0,0 +1,243 @@
+//! Streaming captured utterances to an external STT service
+//!
+//! By default the frontend receives `voice-audio-captured` and runs STT
+//! itself. With `stt_endpoint` set, the capture is streamed straight to that
+//! WebSocket endpoint instead, and the returned text completes the
+//! transcription here (the frontend gets it in `voice-transcription`).
+//!
+//! Protocol, one utterance per connection:
+//! 1. the client sends `{"sample_rate": 16000, "encoding": "pcm_s16le"}` as text
+//! 2. the client sends the audio as binary messages of 16-bit little-endian PCM
+//! 3. the client sends `{"event": "end"}` as text
+//! 4. the server replies with `{"text": "..."}` as text
+
+use futures_util::{SinkExt, StreamExt};
+use parking_lot::RwLock;
+use serde::Deserialize;
+use std::future::Future;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use thiserror::Error;
+use tokio_tungstenite::tungstenite::Message;
+
+use super::controller_state::VoiceControllerState;
+use super::config::VoiceConfig;
+use super::events::{emit_event, EventSink};
+use super::state_handlers::{finish_transcription, report_failure};
+use super::state_machine::{StateAction, VoiceEvent, VoiceState};
+
+/// Samples per binary message (100ms at 16kHz)
+const FRAME_SAMPLES: usize = 1600;
+
+#[derive(Error, Debug)]
+pub enum SttError {
+    #[error("STT connection failed: {0}")]
+    Connection(String),
+    #[error("STT protocol error: {0}")]
+    Protocol(String),
+    #[error("STT timed out after {0:?}")]
+    Timeout(Duration),
+}
+
+/// Speech-to-text service transcribing one utterance per call
+pub trait SttBackend: Send + Sync {
+    fn transcribe(&self, pcm: &[f32], sample_rate: u32) -> impl Future<Output = Result<String, SttError>> + Send;
+}
+
+/// STT service speaking the WebSocket protocol described in the module docs
+#[derive(Debug, Clone)]
+pub struct WebSocketStt {
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct SttReply {
+    text: String,
+}
+
+impl WebSocketStt {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self { endpoint: endpoint.into() }
+    }
+}
+
+impl SttBackend for WebSocketStt {
+    async fn transcribe(&self, pcm: &[f32], sample_rate: u32) -> Result<String, SttError> {
+        let protocol = |e: tokio_tungstenite::tungstenite::Error| SttError::Protocol(e.to_string());
+        let (mut ws, _) = tokio_tungstenite::connect_async(self.endpoint.as_str())
+            .await
+            .map_err(|e| SttError::Connection(e.to_string()))?;
+
+        let start = serde_json::json!({ "sample_rate": sample_rate, "encoding": "pcm_s16le" });
+        ws.send(Message::text(start.to_string())).await.map_err(protocol)?;
+        for frame in pcm.chunks(FRAME_SAMPLES) {
+            ws.send(Message::binary(pcm_s16le(frame))).await.map_err(protocol)?;
+        }
+        ws.send(Message::text(r#"{"event":"end"}"#)).await.map_err(protocol)?;
+
+        while let Some(message) = ws.next().await {
+            match message.map_err(protocol)? {
+                Message::Text(text) => {
+                    let reply: SttReply =
+                        serde_json::from_str(&text).map_err(|e| SttError::Protocol(e.to_string()))?;
+                    let _ = ws.close(None).await;
+                    return Ok(reply.text);
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+        Err(SttError::Protocol("connection closed without a transcription".to_string()))
+    }
+}
+
+/// Convert samples to 16-bit little-endian PCM, clipping out-of-range values
+fn pcm_s16le(samples: &[f32]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|&sample| ((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+        .collect()
+}
+
+/// Transcribe `audio` at `endpoint` on a background thread, then complete the transcription
+///
+/// Failures and timeouts (`backend_timeout_ms`) move to the Error state.
+/// `capture_id` is the state machine's id of the capture being transcribed;
+/// a reply arriving after that capture was cancelled or replaced is dropped.
+pub fn spawn_transcription(
+    sink: EventSink,
+    state: Arc<RwLock<VoiceControllerState>>,
+    endpoint: String,
+    audio: Vec<f32>,
+    capture_id: u64,
+    config: &VoiceConfig,
+) {
+    let sample_rate = config.sample_rate;
+    let timeout = Duration::from_millis(config.backend_timeout_ms);
+
+    thread::spawn(move || {
+        let backend = WebSocketStt::new(endpoint);
+        let result = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| SttError::Connection(e.to_string()))
+            .and_then(|rt| {
+                rt.block_on(async {
+                    tokio::time::timeout(timeout, backend.transcribe(&audio, sample_rate))
+                        .await
+                        .unwrap_or(Err(SttError::Timeout(timeout)))
+                })
+            });
+        apply_transcription(&sink, &state, capture_id, result);
+    });
+}
+
+/// Complete the transcription of capture `capture_id`, unless it is no longer the one being transcribed
+fn apply_transcription(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    capture_id: u64,
+    result: Result<String, SttError>,
+) {
+    let mut state_guard = state.write();
+    let machine = &state_guard.state_machine;
+    if machine.capture_id() != capture_id || machine.state() != VoiceState::Transcribing {
+        log::info!("Dropping STT reply for capture {}, which is no longer being transcribed", capture_id);
+        return;
+    }
+    match result {
+        Ok(text) => {
+            let result = state_guard.state_machine.transition(VoiceEvent::TranscriptionComplete(text));
+            drop(state_guard);
+            if let Some(StateAction::ProcessText(ref text)) = result.action {
+                emit_event(sink, state, "voice-transcription", serde_json::json!({ "text": text }));
+            }
+            finish_transcription(sink, state, &result);
+        }
+        Err(e) => {
+            drop(state_guard);
+            log::error!("Streaming STT failed: {}", e);
+            report_failure(sink, state, e.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pcm_conversion_clips() {
+        assert_eq!(pcm_s16le(&[0.0, 1.0, -2.0]), vec![0, 0, 0xff, 0x7f, 0x01, 0x80]);
+    }
+
+    #[test]
+    fn test_websocket_backend_returns_transcription() {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let endpoint = format!("ws://{}", listener.local_addr().unwrap());
+            let server = tokio::spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                let mut header = String::new();
+                let mut bytes = 0;
+                while let Some(Ok(message)) = ws.next().await {
+                    match message {
+                        Message::Text(text) if text.contains(r#""event""#) => break,
+                        Message::Text(text) => header = text.to_string(),
+                        Message::Binary(data) => bytes += data.len(),
+                        _ => {}
+                    }
+                }
+                ws.send(Message::text(r#"{"text":"turn on the lights"}"#)).await.unwrap();
+                (header, bytes)
+            });
+
+            let text = WebSocketStt::new(endpoint).transcribe(&vec![0.1; 4000], 16000).await.unwrap();
+            let (header, bytes) = server.await.unwrap();
+
+            assert_eq!(text, "turn on the lights");
+            assert!(header.contains("16000"), "{}", header);
+            assert_eq!(bytes, 8000);
+        });
+    }
+
+    #[test]
+    fn test_stale_reply_is_dropped() {
+        use crate::voice::events::CollectingSink;
+
+        let collected = Arc::new(CollectingSink::default());
+        let sink: EventSink = Some(collected.clone());
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let mut state_guard = state.write();
+        state_guard.state_machine.transition(VoiceEvent::ManualTrigger);
+        state_guard.state_machine.transition(VoiceEvent::VadSpeechEnd);
+        let stale_id = state_guard.state_machine.capture_id();
+        // The capture is cancelled and a new one is sent off before the reply arrives
+        state_guard.state_machine.transition(VoiceEvent::Cancel);
+        state_guard.state_machine.transition(VoiceEvent::ManualTrigger);
+        state_guard.state_machine.transition(VoiceEvent::VadSpeechEnd);
+        let current_id = state_guard.state_machine.capture_id();
+        drop(state_guard);
+
+        apply_transcription(&sink, &state, stale_id, Ok("turn off the lights".to_string()));
+        assert_eq!(state.read().state_machine.state(), VoiceState::Transcribing);
+        assert!(collected.payloads("voice-transcription").is_empty());
+
+        apply_transcription(&sink, &state, current_id, Ok("turn on the lights".to_string()));
+        assert_eq!(state.read().state_machine.state(), VoiceState::Processing);
+        assert_eq!(
+            collected.payloads("voice-transcription"),
+            vec![serde_json::json!({ "text": "turn on the lights" })]
+        );
+    }
+
+    #[test]
+    fn test_unreachable_endpoint_is_a_connection_error() {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let result = rt.block_on(WebSocketStt::new("ws://127.0.0.1:1").transcribe(&[0.0; 16], 16000));
+        assert!(matches!(result, Err(SttError::Connection(_))));
+    }
+}
@@ -0,0 +1,153 @@
+//! TTS audio played from Rust while the state machine is Speaking
+//!
+//! The controller hands synthesized samples to the processing loop as a
+//! [`ControlMessage::PlayTts`](super::control::ControlMessage::PlayTts). The
+//! loop starts playback on the selected output device and completes speech
+//! once the stream drains; barge-in, cancel and stop cut it short through
+//! the shared cancel flag.
+
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use super::controller_state::VoiceControllerState;
+use super::events::{emit_debug_log, emit_transition, EventSink};
+use super::playback::{play_samples_until, PlaybackError};
+use super::state_machine::{VoiceEvent, VoiceState};
+
+/// Play `samples` on the selected output device, completing speech when done
+pub(super) fn start_tts_playback(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    samples: Vec<f32>,
+    sample_rate: u32,
+) -> Option<JoinHandle<()>> {
+    start_tts_playback_with(sink, state, samples, sample_rate, play_samples_until)
+}
+
+/// [`start_tts_playback`] with the output replaced, e.g. by a fake in tests
+fn start_tts_playback_with<P>(
+    sink: &EventSink,
+    state: &Arc<RwLock<VoiceControllerState>>,
+    samples: Vec<f32>,
+    sample_rate: u32,
+    play: P,
+) -> Option<JoinHandle<()>>
+where
+    P: FnOnce(Option<&str>, &[f32], u32, &AtomicBool) -> Result<(), PlaybackError> + Send + 'static,
+{
+    let mut state_guard = state.write();
+    // The response may have been cancelled or barged in on since it was queued
+    if state_guard.state_machine.state() != VoiceState::Speaking {
+        drop(state_guard);
+        emit_debug_log(sink, "warn", "Dropping TTS audio: no longer speaking");
+        return None;
+    }
+    state_guard.stop_tts_playback();
+    let cancel = Arc::new(AtomicBool::new(false));
+    state_guard.tts_cancel = Some(cancel.clone());
+    let device = state_guard.output_device.clone();
+    drop(state_guard);
+
+    let state = state.clone();
+    let sink = sink.clone();
+    Some(thread::spawn(move || {
+        if let Err(e) = play(device.as_deref(), &samples, sample_rate, &cancel) {
+            log::error!("TTS playback failed: {}", e);
+            emit_debug_log(&sink, "error", &format!("TTS playback failed: {}", e));
+        }
+        let mut state_guard = state.write();
+        // Checked under the lock, as a cancel or newer playback may land while it is taken
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        if state_guard.tts_cancel.as_ref().is_some_and(|current| Arc::ptr_eq(current, &cancel)) {
+            state_guard.tts_cancel = None;
+        }
+        let result = state_guard.state_machine.transition(VoiceEvent::SpeechComplete);
+        drop(state_guard);
+        emit_transition(&sink, &state, &result);
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::events::CollectingSink;
+
+    fn speaking_state() -> Arc<RwLock<VoiceControllerState>> {
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let mut guard = state.write();
+        guard.state_machine.transition(VoiceEvent::ManualTrigger);
+        guard.state_machine.transition(VoiceEvent::VadSpeechEnd);
+        guard.state_machine.transition(VoiceEvent::TranscriptionComplete("hello".to_string()));
+        guard.state_machine.transition(VoiceEvent::ResponseReady("Hi there".to_string()));
+        drop(guard);
+        state
+    }
+
+    #[test]
+    fn test_drained_playback_completes_speech() {
+        let collected = Arc::new(CollectingSink::default());
+        let sink: EventSink = Some(collected.clone());
+        let state = speaking_state();
+        let played = Arc::new(AtomicBool::new(false));
+        let flag = played.clone();
+
+        let handle = start_tts_playback_with(&sink, &state, vec![0.0; 160], 16000, move |_, samples, _, _| {
+            assert_eq!(samples.len(), 160);
+            flag.store(true, Ordering::Relaxed);
+            Ok(())
+        });
+        handle.unwrap().join().unwrap();
+
+        assert!(played.load(Ordering::Relaxed));
+        assert_eq!(state.read().state_machine.state(), VoiceState::Idle);
+        assert!(state.read().tts_cancel.is_none());
+    }
+
+    #[test]
+    fn test_cancelled_playback_leaves_state_alone() {
+        let state = speaking_state();
+        let canceller = state.clone();
+
+        let handle = start_tts_playback_with(&None, &state, vec![0.0; 160], 16000, move |_, _, _, _| {
+            canceller.write().stop_tts_playback();
+            Ok(())
+        });
+        handle.unwrap().join().unwrap();
+
+        // Whoever cancelled owns the next transition
+        assert_eq!(state.read().state_machine.state(), VoiceState::Speaking);
+    }
+
+    #[test]
+    fn test_newer_playback_keeps_its_cancel_flag() {
+        let state = speaking_state();
+        let newer = Arc::new(AtomicBool::new(false));
+        let (replacer, flag) = (state.clone(), newer.clone());
+
+        let handle = start_tts_playback_with(&None, &state, vec![0.0; 160], 16000, move |_, _, _, _| {
+            // What a second PlayTts does before this playback gets the lock
+            let mut guard = replacer.write();
+            guard.stop_tts_playback();
+            guard.tts_cancel = Some(flag);
+            Ok(())
+        });
+        handle.unwrap().join().unwrap();
+
+        let state_guard = state.read();
+        assert_eq!(state_guard.state_machine.state(), VoiceState::Speaking);
+        assert!(Arc::ptr_eq(state_guard.tts_cancel.as_ref().unwrap(), &newer));
+    }
+
+    #[test]
+    fn test_audio_outside_speaking_is_dropped() {
+        let state = Arc::new(RwLock::new(VoiceControllerState::new()));
+        let handle = start_tts_playback_with(&None, &state, vec![0.0; 160], 16000, |_, _, _, _| {
+            panic!("nothing should play while idle")
+        });
+        assert!(handle.is_none());
+    }
+}
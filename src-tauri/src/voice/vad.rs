@@ -1,54 +1,165 @@
 //! Voice Activity Detection (VAD)
 //!
-//! Simple energy-based VAD for detecting speech end.
-//! Can be upgraded to Silero VAD later.
+//! Energy-based detection by default, or the Silero ONNX model when
+//! configured and installed. Both feed the same speech-end logic.
 
-use super::config::VoiceConfig;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 
-/// Voice activity detector state
-#[derive(Debug)]
-pub struct VoiceActivityDetector {
-    /// Energy threshold for silence detection
-    silence_threshold: f32,
+use super::config::{VoiceConfig, MAX_VAD_SMOOTHING_FACTOR};
+use super::dsp::calculate_rms;
+use super::silero_vad::{SileroVad, SILERO_MODEL_FILE};
+
+/// Which speech detector the VAD uses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VadBackend {
+    /// Smoothed RMS against `silence_threshold`
+    #[default]
+    Energy,
+    /// Silero speech probability model (`silero_vad.onnx`)
+    Silero,
+}
+
+/// Voice activity detector using the configured backend
+pub enum VoiceActivityDetector {
+    Energy(EnergyVad),
+    Silero(SileroVad),
+}
+
+impl VoiceActivityDetector {
+    /// Create an energy-based VAD
+    pub fn new(config: &VoiceConfig) -> Self {
+        Self::Energy(EnergyVad::new(config))
+    }
+
+    /// Create the VAD selected by `config.vad_backend`
+    ///
+    /// Falls back to the energy VAD when the Silero model is missing or fails to load.
+    pub fn load(models_dir: &Path, config: &VoiceConfig) -> Self {
+        if config.vad_backend == VadBackend::Energy {
+            return Self::new(config);
+        }
+
+        let path = models_dir.join(SILERO_MODEL_FILE);
+        if !path.exists() {
+            log::warn!("Silero VAD model not found at {:?}, using energy VAD", path);
+            return Self::new(config);
+        }
+        match SileroVad::new(&path, config) {
+            Ok(vad) => Self::Silero(vad),
+            Err(e) => {
+                log::warn!("Failed to load Silero VAD, using energy VAD: {}", e);
+                Self::new(config)
+            }
+        }
+    }
+
+    /// Process an audio chunk and return VAD result
+    pub fn process(&mut self, samples: &[f32]) -> VadResult {
+        match self {
+            Self::Energy(vad) => vad.process(samples),
+            Self::Silero(vad) => vad.process(samples),
+        }
+    }
+
+    /// Reset the VAD state
+    pub fn reset(&mut self) {
+        match self {
+            Self::Energy(vad) => vad.reset(),
+            Self::Silero(vad) => vad.reset(),
+        }
+    }
+
+    /// Check if speech has been detected in current session
+    pub fn has_speech(&self) -> bool {
+        self.tracker().has_speech()
+    }
+
+    /// Get the number of consecutive silent frames
+    pub fn silent_frames(&self) -> usize {
+        self.tracker().silent_frames()
+    }
+
+    /// Get the total number of speech frames since the last reset
+    pub fn speech_frames(&self) -> usize {
+        self.tracker().speech_frames()
+    }
+
+    /// Check if the latest chunk was judged to be speech
+    pub fn is_speech(&self) -> bool {
+        self.tracker().is_speech()
+    }
+
+    /// Level the detector is working from: smoothed RMS, or the raw RMS for Silero
+    pub fn current_rms(&self) -> f32 {
+        match self {
+            Self::Energy(vad) => vad.current_rms(),
+            Self::Silero(vad) => vad.last_rms(),
+        }
+    }
+
+    /// Snapshot for the listening indicator
+    pub fn status(&self) -> VadStatus {
+        VadStatus {
+            rms: self.current_rms(),
+            is_speech: self.is_speech(),
+            silent_frames: self.silent_frames(),
+        }
+    }
+
+    fn tracker(&self) -> &SpeechEndTracker {
+        match self {
+            Self::Energy(vad) => &vad.tracker,
+            Self::Silero(vad) => vad.tracker(),
+        }
+    }
+}
+
+/// Payload of `voice-vad`, emitted while listening
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct VadStatus {
+    pub rms: f32,
+    /// Whether the latest chunk was speech
+    pub is_speech: bool,
+    /// Consecutive silent chunks since speech was last heard
+    pub silent_frames: usize,
+}
+
+/// Turns per-chunk speech/silence decisions into [`VadResult`]s
+#[derive(Debug, Clone)]
+pub struct SpeechEndTracker {
     /// Number of consecutive silent frames to trigger speech end
     silence_frames_threshold: usize,
     /// Current count of consecutive silent frames
     silent_frame_count: usize,
     /// Whether speech has been detected at all
     speech_detected: bool,
-    /// Smoothed RMS level for more stable detection
-    smoothed_rms: f32,
-    /// Smoothing factor (0-1, higher = more smoothing)
-    smoothing_factor: f32,
+    /// Speech frames seen since the last reset
+    speech_frame_count: usize,
+    /// Whether the latest chunk contained speech
+    in_speech: bool,
 }
 
-impl VoiceActivityDetector {
-    /// Create a new VAD instance
-    pub fn new(config: &VoiceConfig) -> Self {
+impl SpeechEndTracker {
+    pub fn new(silence_frames_threshold: usize) -> Self {
         Self {
-            silence_threshold: config.silence_threshold,
-            silence_frames_threshold: config.silence_frames_threshold,
+            silence_frames_threshold,
             silent_frame_count: 0,
             speech_detected: false,
-            smoothed_rms: 0.0,
-            smoothing_factor: 0.3,
+            speech_frame_count: 0,
+            in_speech: false,
         }
     }
 
-    /// Process an audio chunk and return VAD result
-    pub fn process(&mut self, samples: &[f32]) -> VadResult {
-        let rms = calculate_rms(samples);
-
-        // Smooth the RMS value
-        self.smoothed_rms = self.smoothing_factor * rms
-            + (1.0 - self.smoothing_factor) * self.smoothed_rms;
-
-        let is_silent = self.smoothed_rms < self.silence_threshold;
-
-        if !is_silent {
+    /// Record whether the latest chunk contained speech
+    pub fn update(&mut self, is_speech: bool) -> VadResult {
+        self.in_speech = is_speech;
+        if is_speech {
             // Speech detected
             self.speech_detected = true;
             self.silent_frame_count = 0;
+            self.speech_frame_count += 1;
             VadResult::Speech
         } else if self.speech_detected {
             // Silent frame after speech
@@ -66,31 +177,96 @@ impl VoiceActivityDetector {
         }
     }
 
-    /// Reset the VAD state
     pub fn reset(&mut self) {
         self.silent_frame_count = 0;
         self.speech_detected = false;
-        self.smoothed_rms = 0.0;
+        self.speech_frame_count = 0;
+        self.in_speech = false;
     }
 
-    /// Get current RMS level (for debugging/visualization)
-    pub fn current_rms(&self) -> f32 {
-        self.smoothed_rms
+    pub fn is_speech(&self) -> bool {
+        self.in_speech
     }
 
-    /// Check if speech has been detected in current session
     pub fn has_speech(&self) -> bool {
         self.speech_detected
     }
 
-    /// Get the number of consecutive silent frames
     pub fn silent_frames(&self) -> usize {
         self.silent_frame_count
     }
+
+    pub fn speech_frames(&self) -> usize {
+        self.speech_frame_count
+    }
+}
+
+/// Energy-based voice activity detector state
+///
+/// Uses separate onset and offset thresholds (a Schmitt trigger) so a level
+/// hovering near one threshold doesn't flip between speech and silence.
+#[derive(Debug)]
+pub struct EnergyVad {
+    /// Level that must be reached to declare speech
+    onset_threshold: f32,
+    /// Level that must be dropped below to count silence
+    offset_threshold: f32,
+    /// Whether the last chunk was judged to be speech
+    in_speech: bool,
+    /// Speech end detection
+    tracker: SpeechEndTracker,
+    /// Smoothed RMS level for more stable detection
+    smoothed_rms: f32,
+    /// Weight of the previous smoothed level (0-1, higher = more smoothing)
+    smoothing_factor: f32,
+}
+
+impl EnergyVad {
+    /// Create a new VAD instance
+    pub fn new(config: &VoiceConfig) -> Self {
+        let (onset_threshold, offset_threshold) = config.speech_thresholds();
+        Self {
+            onset_threshold,
+            offset_threshold,
+            in_speech: false,
+            tracker: SpeechEndTracker::new(config.silence_frames_threshold()),
+            smoothed_rms: 0.0,
+            smoothing_factor: config.vad_smoothing_factor.clamp(0.0, MAX_VAD_SMOOTHING_FACTOR),
+        }
+    }
+
+    /// Process an audio chunk and return VAD result
+    pub fn process(&mut self, samples: &[f32]) -> VadResult {
+        let rms = calculate_rms(samples);
+
+        // Smooth the RMS value
+        self.smoothed_rms = (1.0 - self.smoothing_factor) * rms
+            + self.smoothing_factor * self.smoothed_rms;
+
+        self.in_speech = if self.in_speech {
+            self.smoothed_rms >= self.offset_threshold
+        } else {
+            self.smoothed_rms >= self.onset_threshold
+        };
+        self.tracker.update(self.in_speech)
+    }
+
+    /// Reset the VAD state
+    pub fn reset(&mut self) {
+        self.tracker.reset();
+        self.in_speech = false;
+        self.smoothed_rms = 0.0;
+    }
+
+    /// Get current RMS level (for debugging/visualization)
+    pub fn current_rms(&self) -> f32 {
+        self.smoothed_rms
+    }
 }
 
 /// Result of VAD processing
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub enum VadResult {
     /// Currently detecting speech
     Speech,
@@ -100,25 +276,6 @@ pub enum VadResult {
     SpeechEnd,
 }
 
-/// Calculate RMS (Root Mean Square) of audio samples
-fn calculate_rms(samples: &[f32]) -> f32 {
-    if samples.is_empty() {
-        return 0.0;
-    }
-    let sum_squares: f32 = samples.iter().map(|&s| s * s).sum();
-    (sum_squares / samples.len() as f32).sqrt()
-}
-
-/// Calculate peak amplitude of audio samples
-#[allow(dead_code)]
-fn calculate_peak(samples: &[f32]) -> f32 {
-    samples
-        .iter()
-        .map(|s| s.abs())
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap_or(0.0)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,7 +283,7 @@ mod tests {
     fn make_config() -> VoiceConfig {
         VoiceConfig {
             silence_threshold: 0.01,
-            silence_frames_threshold: 3,
+            silence_timeout_ms: 240,
             ..Default::default()
         }
     }
@@ -149,7 +306,8 @@ mod tests {
 
     #[test]
     fn test_speech_end_detection() {
-        let mut vad = VoiceActivityDetector::new(&make_config());
+        // Unsmoothed, so the level drops to silence on the first silent chunk
+        let mut vad = VoiceActivityDetector::new(&VoiceConfig { vad_smoothing_factor: 0.0, ..make_config() });
 
         // First, detect speech
         let loud_samples: Vec<f32> = (0..1280).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
@@ -181,9 +339,60 @@ mod tests {
     }
 
     #[test]
-    fn test_rms_calculation() {
-        let samples = vec![1.0, -1.0, 1.0, -1.0];
-        let rms = calculate_rms(&samples);
-        assert!((rms - 1.0).abs() < 0.001);
+    fn test_hysteresis_keeps_speech_near_threshold() {
+        let config = VoiceConfig {
+            speech_onset_threshold: Some(0.015),
+            speech_offset_threshold: Some(0.005),
+            ..make_config()
+        };
+        let mut vad = VoiceActivityDetector::new(&config);
+        let level = |amplitude: f32| vec![amplitude; 1280];
+
+        for _ in 0..10 {
+            vad.process(&level(0.05));
+        }
+        // Settle onto the old single threshold, then oscillate around it
+        for _ in 0..20 {
+            vad.process(&level(0.01));
+        }
+        for i in 0..20 {
+            let amplitude = if i % 2 == 0 { 0.012 } else { 0.008 };
+            assert_eq!(vad.process(&level(amplitude)), VadResult::Speech);
+            assert_eq!(vad.silent_frames(), 0);
+        }
+
+        // Silence is only counted once the level drops below the offset
+        let mut result = VadResult::Speech;
+        for _ in 0..20 {
+            result = vad.process(&level(0.0));
+            if result == VadResult::SpeechEnd {
+                break;
+            }
+        }
+        assert_eq!(result, VadResult::SpeechEnd);
+    }
+
+    #[test]
+    fn test_smoothing_factor_sets_response_speed() {
+        let rms_after_step = |vad_smoothing_factor: f32| {
+            let mut vad = VoiceActivityDetector::new(&VoiceConfig { vad_smoothing_factor, ..make_config() });
+            vad.process(&vec![0.1; 1280]);
+            vad.current_rms()
+        };
+
+        assert!((rms_after_step(0.0) - 0.1).abs() < 1e-6, "no smoothing follows the step at once");
+        assert!(rms_after_step(0.2) > rms_after_step(0.9));
+        // Out-of-range factors are clamped rather than freezing the level
+        assert!(rms_after_step(1.5) > 0.0);
+    }
+
+    #[test]
+    fn test_missing_silero_model_falls_back_to_energy() {
+        let config = VoiceConfig {
+            vad_backend: VadBackend::Silero,
+            ..make_config()
+        };
+        let vad = VoiceActivityDetector::load(Path::new("does-not-exist"), &config);
+        assert!(matches!(vad, VoiceActivityDetector::Energy(_)));
     }
 }
@@ -0,0 +1,141 @@
+//! Discovery of installed wake phrases
+//!
+//! Every classifier model in the models directory other than the shared
+//! melspectrogram/embedding models is a wake phrase. An optional
+//! `wake_phrases.json` maps model names to display names, and a preview clip
+//! may be bundled as `samples/<name>.wav`.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::config::ModelFiles;
+
+/// Optional metadata file mapping model names to display names
+pub const WAKE_PHRASES_FILE: &str = "wake_phrases.json";
+
+/// Subdirectory of the models directory holding preview clips
+pub const SAMPLES_DIR: &str = "samples";
+
+/// An installed wake phrase
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WakePhraseInfo {
+    /// Model name (file stem), e.g. `hey_jarvis`
+    pub name: String,
+    /// Human readable phrase, e.g. `Hey Jarvis`
+    pub display_name: String,
+    /// Model filename relative to the models directory
+    pub model_file: String,
+    /// Whether a preview clip is bundled
+    pub has_sample: bool,
+}
+
+/// Path of the preview clip for a wake phrase
+///
+/// `None` for names that could point outside the samples directory.
+pub fn sample_path(models_dir: &Path, name: &str) -> Option<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") || name.starts_with('.') {
+        return None;
+    }
+    Some(models_dir.join(SAMPLES_DIR).join(format!("{}.wav", name)))
+}
+
+/// List the wake phrases installed in the models directory, sorted by name
+pub fn list_wake_phrases(models_dir: &Path) -> Vec<WakePhraseInfo> {
+    let shared = ModelFiles::default();
+    let display_names = read_display_names(models_dir);
+
+    let mut phrases: Vec<WakePhraseInfo> = std::fs::read_dir(models_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|file| file.ends_with(".onnx"))
+                .filter(|file| *file != shared.melspec && *file != shared.embedding)
+                .map(|model_file| {
+                    let name = model_file.trim_end_matches(".onnx").to_string();
+                    WakePhraseInfo {
+                        display_name: display_names
+                            .get(&name)
+                            .cloned()
+                            .unwrap_or_else(|| default_display_name(&name)),
+                        has_sample: sample_path(models_dir, &name).is_some_and(|path| path.exists()),
+                        name,
+                        model_file,
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    phrases.sort_by(|a, b| a.name.cmp(&b.name));
+    phrases
+}
+
+fn read_display_names(models_dir: &Path) -> HashMap<String, String> {
+    let path = models_dir.join(WAKE_PHRASES_FILE);
+    let Ok(json) = std::fs::read_to_string(&path) else {
+        return HashMap::new();
+    };
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        log::warn!("Ignoring invalid {:?}: {}", path, e);
+        HashMap::new()
+    })
+}
+
+/// Derive a display name from a model name: `hey_jarvis` -> `Hey Jarvis`
+fn default_display_name(name: &str) -> String {
+    name.split(['_', '-'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_display_name() {
+        assert_eq!(default_display_name("hey_jarvis"), "Hey Jarvis");
+        assert_eq!(default_display_name("ok-computer"), "Ok Computer");
+    }
+
+    #[test]
+    fn test_sample_path_rejects_traversal() {
+        let dir = Path::new("models");
+        assert_eq!(sample_path(dir, "hey_jarvis"), Some(dir.join(SAMPLES_DIR).join("hey_jarvis.wav")));
+        for name in ["../../../etc/alarm", "..", "a/b", "a\\b", ".hidden", ""] {
+            assert_eq!(sample_path(dir, name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_list_wake_phrases() {
+        let dir = std::env::temp_dir().join(format!("jarvis-phrases-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(SAMPLES_DIR)).unwrap();
+        for file in ["melspectrogram.onnx", "embedding_model.onnx", "hey_jarvis.onnx", "alexa.onnx"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        std::fs::write(dir.join(WAKE_PHRASES_FILE), r#"{ "alexa": "Alexa (test)" }"#).unwrap();
+        std::fs::write(sample_path(&dir, "hey_jarvis").unwrap(), "").unwrap();
+
+        let phrases = list_wake_phrases(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(phrases.len(), 2);
+        assert_eq!(phrases[0].name, "alexa");
+        assert_eq!(phrases[0].display_name, "Alexa (test)");
+        assert!(!phrases[0].has_sample);
+        assert_eq!(phrases[1].display_name, "Hey Jarvis");
+        assert_eq!(phrases[1].model_file, "hey_jarvis.onnx");
+        assert!(phrases[1].has_sample);
+    }
+}
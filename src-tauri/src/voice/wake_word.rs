@@ -1,19 +1,32 @@
 //! Wake word detection using OpenWakeWord ONNX models
 //!
 //! Pipeline:
-//! 1. Audio chunk (1280 samples) → melspectrogram.onnx → mel features
-//! 2. Transform: (value / 10.0) + 2.0
+//! 1. Audio chunk (1280 samples) → melspectrogram.onnx → several mel frames
+//! 2. Transform each frame: (value / 10.0) + 2.0
 //! 3. Accumulate 76 mel frames in sliding buffer
 //! 4. 76 frames → embedding_model.onnx → embeddings
-//! 5. Embeddings → hey_jarvis.onnx → detection score
+//! 5. Embeddings → hey_jarvis.onnx (plus any extra wake word models) → detection score
 
-use ort::session::{builder::GraphOptimizationLevel, Session};
-use ort::value::Tensor;
 use std::path::Path;
+use std::time::Instant;
 use thiserror::Error;
 
+use super::adaptive_threshold::AdaptiveThreshold;
 use super::buffer::MelBuffer;
+use super::classifiers::{load_session, ClassifierSet};
+use super::command_words::{detect_command, load_command_words};
 use super::config::VoiceConfig;
+use super::inference_cancel::InferenceCanceller;
+use super::inference_model::{InferenceModel, OnnxModel};
+use super::model_shapes::{first_input_shape, first_output_shape, mel_bands_from_output, validate_embedding_input};
+use super::score_history::{ScoreHistory, SUSTAINED_FRAMES};
+use super::wake_word_models::{load_wake_word_models, pick_wake_word, primary_wake_word_name, threshold_for};
+use super::wake_word_stats::WakeWordStats;
+
+#[cfg(test)]
+pub(super) mod mock;
+mod runtime;
+mod stages;
 
 #[derive(Error, Debug)]
 pub enum WakeWordError {
@@ -23,224 +36,196 @@ pub enum WakeWordError {
     InferenceError(String),
     #[error("Model not found: {0}")]
     ModelNotFound(String),
+    #[error("Inference cancelled")]
+    Cancelled,
 }
 
 /// OpenWakeWord detector using ONNX models
 pub struct WakeWordDetector {
-    melspec_session: Session,
-    embedding_session: Session,
-    wakeword_session: Session,
+    melspec_model: Box<dyn InferenceModel>,
+    embedding_model: Box<dyn InferenceModel>,
+    wakeword_model: Box<dyn InferenceModel>,
     mel_buffer: MelBuffer,
     config: VoiceConfig,
     /// Number of mel bands output by melspectrogram model
     mel_bands: usize,
+    /// Shared run options used to abort in-flight inference
+    canceller: InferenceCanceller,
+    /// Recent classifier scores, oldest first
+    score_history: ScoreHistory,
+    /// Score statistics since the last `reset_stats`
+    stats: WakeWordStats,
+    /// Threshold raise following the background score level
+    adaptive: AdaptiveThreshold,
+    /// Name reported when the primary classifier fires
+    primary_name: String,
+    /// Extra wake word classifiers sharing the embeddings
+    wake_word_models: ClassifierSet,
+    /// Secondary command word classifiers sharing the embeddings
+    command_words: ClassifierSet,
+    /// Command word detected by the last inference, if any
+    detected_command: Option<String>,
+    /// Best-scoring phrase of a multi-label classifier from the last inference
+    last_phrase: Option<String>,
+    /// Whether the processing loop is currently feeding audio for detection
+    scanning: bool,
 }
 
 impl WakeWordDetector {
     /// Create a new wake word detector, loading models from the given directory
     pub fn new(models_dir: &Path, config: VoiceConfig) -> Result<Self, WakeWordError> {
-        // Load models
-        let melspec_path = models_dir.join("melspectrogram.onnx");
-        let embedding_path = models_dir.join("embedding_model.onnx");
-        let wakeword_path = models_dir.join("hey_jarvis.onnx");
-
-        // Check files exist
-        for path in [&melspec_path, &embedding_path, &wakeword_path] {
-            if !path.exists() {
-                return Err(WakeWordError::ModelNotFound(path.display().to_string()));
-            }
-        }
+        let [melspec_path, embedding_path, wakeword_path] = config.model_files.paths(models_dir);
+        // Resolve once so every session shares the provider and a fallback is logged once
+        let provider = config.execution_provider.resolve();
 
         log::info!("Loading melspectrogram model from {:?}", melspec_path);
-        let melspec_session = Session::builder()
-            .map_err(|e| {
-                log::error!("Failed to create session builder: {}", e);
-                WakeWordError::ModelLoadError(e.to_string())
-            })?
-            .with_optimization_level(GraphOptimizationLevel::Level3)
-            .map_err(|e| {
-                log::error!("Failed to set optimization level: {}", e);
-                WakeWordError::ModelLoadError(e.to_string())
-            })?
-            .commit_from_file(&melspec_path)
-            .map_err(|e| {
-                log::error!("Failed to load melspec model: {}", e);
-                WakeWordError::ModelLoadError(e.to_string())
-            })?;
-        log::info!("Melspectrogram model loaded successfully");
-
+        let melspec_session = load_session(&melspec_path, provider)?;
         log::info!("Loading embedding model from {:?}", embedding_path);
-        let embedding_session = Session::builder()
-            .map_err(|e| WakeWordError::ModelLoadError(e.to_string()))?
-            .with_optimization_level(GraphOptimizationLevel::Level3)
-            .map_err(|e| WakeWordError::ModelLoadError(e.to_string()))?
-            .commit_from_file(&embedding_path)
-            .map_err(|e| {
-                log::error!("Failed to load embedding model: {}", e);
-                WakeWordError::ModelLoadError(e.to_string())
-            })?;
-        log::info!("Embedding model loaded successfully");
-
+        let embedding_session = load_session(&embedding_path, provider)?;
         log::info!("Loading wakeword model from {:?}", wakeword_path);
-        let wakeword_session = Session::builder()
-            .map_err(|e| WakeWordError::ModelLoadError(e.to_string()))?
-            .with_optimization_level(GraphOptimizationLevel::Level3)
-            .map_err(|e| WakeWordError::ModelLoadError(e.to_string()))?
-            .commit_from_file(&wakeword_path)
-            .map_err(|e| {
-                log::error!("Failed to load wakeword model: {}", e);
-                WakeWordError::ModelLoadError(e.to_string())
-            })?;
-        log::info!("Wakeword model loaded successfully");
-
-        // OpenWakeWord uses 32 mel bands
-        let mel_bands = 32;
-
-        let mel_buffer = MelBuffer::new(config.mel_frame_count, mel_bands);
+        let wakeword_session = load_session(&wakeword_path, provider)?;
+
+        let wake_word_models = load_wake_word_models(models_dir, &config.wake_word_models, provider)?;
+        let command_words = load_command_words(models_dir, &config.command_models, provider)?;
+        let primary_name = primary_wake_word_name(&config.model_files);
+
+        // OpenWakeWord uses 32 mel bands, but custom models may differ
+        let mel_bands = match first_output_shape(&melspec_session) {
+            Some(shape) => mel_bands_from_output(&shape)?,
+            None => 32,
+        };
+        if let Some(shape) = first_input_shape(&embedding_session) {
+            validate_embedding_input(&shape, config.mel_frame_count, mel_bands)?;
+        }
+
+        let mut detector = Self::with_models(
+            config,
+            [
+                Box::new(OnnxModel::new(melspec_session, "melspectrogram")),
+                Box::new(OnnxModel::new(embedding_session, "embedding")),
+                Box::new(OnnxModel::new(wakeword_session, "wake word classifier")),
+            ],
+            mel_bands,
+            primary_name,
+            wake_word_models,
+            command_words,
+        )?;
+        detector.warm_up()?;
 
         log::info!("Wake word detector initialized with models from {:?}", models_dir);
+        Ok(detector)
+    }
+
+    /// Assemble a detector around already loaded melspectrogram, embedding and wake word models
+    fn with_models(
+        config: VoiceConfig,
+        [melspec_model, embedding_model, wakeword_model]: [Box<dyn InferenceModel>; 3],
+        mel_bands: usize,
+        primary_name: String,
+        wake_word_models: ClassifierSet,
+        command_words: ClassifierSet,
+    ) -> Result<Self, WakeWordError> {
+        let mut mel_buffer = MelBuffer::new(config.mel_frame_count, mel_bands);
+        mel_buffer.set_stride(config.inference_stride);
+        let adaptive = AdaptiveThreshold::from_config(&config);
 
         Ok(Self {
-            melspec_session,
-            embedding_session,
-            wakeword_session,
+            melspec_model,
+            embedding_model,
+            wakeword_model,
             mel_buffer,
             config,
             mel_bands,
+            canceller: InferenceCanceller::new()?,
+            score_history: ScoreHistory::default(),
+            stats: WakeWordStats::default(),
+            adaptive,
+            primary_name,
+            wake_word_models,
+            command_words,
+            detected_command: None,
+            last_phrase: None,
+            scanning: true,
         })
     }
 
-    /// Process an audio chunk and return wake word detection score
+    /// Process an audio chunk and return the best wake word and its score
     ///
-    /// Returns Some(score) if enough frames accumulated, None otherwise
-    pub fn process_audio(&mut self, samples: &[f32]) -> Result<Option<f32>, WakeWordError> {
-        // Step 1: Convert audio to mel spectrogram
-        let mel_frame = self.compute_mel_spectrogram(samples)?;
+    /// Returns Some((name, score)) if enough frames accumulated, None otherwise
+    pub fn process_audio(&mut self, samples: &[f32]) -> Result<Option<(String, f32)>, WakeWordError> {
+        if self.canceller.is_cancelled() {
+            return Err(WakeWordError::Cancelled);
+        }
 
-        // Step 2: Apply transform: (value / 10.0) + 2.0
-        let transformed: Vec<f32> = mel_frame.iter().map(|&v| (v / 10.0) + 2.0).collect();
+        // Step 1: Convert audio to mel spectrogram
+        let started = Instant::now();
+        let mel_output = self.compute_mel_spectrogram(samples)?;
+        self.stats.record_melspec(started.elapsed());
 
-        // Step 3: Accumulate mel frames
-        self.mel_buffer.push_frame(transformed);
+        // Steps 2-3: Transform and accumulate every mel frame in the output
+        self.mel_buffer.push_melspec_output(&mel_output);
 
-        // Only run inference when we have enough frames
-        if !self.mel_buffer.is_ready() {
+        // Only run inference when we have enough frames and the stride elapsed
+        if !self.mel_buffer.should_infer() {
             return Ok(None);
         }
+        self.mel_buffer.mark_inferred();
 
         // Step 4: Run embedding model
+        let started = Instant::now();
         let embeddings = self.compute_embeddings()?;
+        let embedding_time = started.elapsed();
 
-        // Step 5: Run wake word classifier
-        let score = self.compute_wake_word_score(&embeddings)?;
-
-        Ok(Some(score))
-    }
-
-    /// Check if wake word was detected based on threshold
-    pub fn is_detected(&self, score: f32) -> bool {
-        score > self.config.effective_threshold()
-    }
-
-    /// Set sensitivity (affects detection threshold)
-    pub fn set_sensitivity(&mut self, sensitivity: f32) {
-        let mut config = self.config.clone();
-        config.sensitivity = sensitivity.clamp(0.1, 3.0);
-        self.config = config;
-    }
+        // Step 5: Run wake word classifiers and keep the best
+        let started = Instant::now();
+        let mut scores = vec![(self.primary_name.clone(), self.compute_wake_word_score(&embeddings)?)];
+        if !self.wake_word_models.is_empty() {
+            scores.extend(self.wake_word_models.score(&embeddings, &self.canceller)?);
+        }
+        self.stats.record_inference_latency(embedding_time, started.elapsed());
+        let best = pick_wake_word(&self.config, scores);
+        if let Some((ref name, score)) = best {
+            self.score_history.push(score);
+            self.stats.record(score);
+            self.adaptive.observe(score, threshold_for(&self.config, name));
+        }
 
-    /// Get current sensitivity
-    pub fn sensitivity(&self) -> f32 {
-        self.config.sensitivity
-    }
+        // Step 6: Run command word classifiers on the same embeddings
+        if !self.command_words.is_empty() {
+            let scores = self.command_words.score(&embeddings, &self.canceller)?;
+            self.detected_command = detect_command(&scores, self.config.effective_threshold());
+        }
 
-    /// Reset the internal buffers
-    pub fn reset(&mut self) {
-        self.mel_buffer.clear();
+        Ok(best)
     }
 
-    /// Compute mel spectrogram from audio samples
-    fn compute_mel_spectrogram(&mut self, samples: &[f32]) -> Result<Vec<f32>, WakeWordError> {
-        // Input shape: [batch, samples] = [1, N]
-        let shape = [1_usize, samples.len()];
-        let input_tensor = Tensor::from_array((shape, samples.to_vec()))
-            .map_err(|e| WakeWordError::InferenceError(e.to_string()))?;
-
-        let outputs = self
-            .melspec_session
-            .run(ort::inputs![input_tensor])
-            .map_err(|e| WakeWordError::InferenceError(e.to_string()))?;
-
-        // Get first output by index
-        let output = &outputs[0];
-
-        let (_, data) = output
-            .try_extract_tensor::<f32>()
-            .map_err(|e| WakeWordError::InferenceError(e.to_string()))?;
-
-        // The output might have multiple frames, take the relevant portion
-        let mel_frame = if data.len() >= self.mel_bands {
-            data[..self.mel_bands].to_vec()
+    /// Check if the named wake word was detected based on its threshold
+    ///
+    /// With `wake_word_smoothing_frames` above 1, the average of the recent
+    /// scores is compared instead of `score`. With `reject_impulsive` enabled,
+    /// the score must also be part of a sustained run rather than an isolated spike.
+    /// With `adaptive_threshold` enabled, the threshold is first raised above
+    /// the background score level.
+    pub fn is_detected(&self, name: &str, score: f32) -> bool {
+        let threshold = self.adaptive.apply(threshold_for(&self.config, name));
+        let score = if self.config.wake_word_smoothing_frames > 1 {
+            self.smoothed_score().unwrap_or(score)
         } else {
-            // Pad with zeros if needed
-            let mut padded = data.to_vec();
-            padded.resize(self.mel_bands, 0.0);
-            padded
+            score
         };
-
-        Ok(mel_frame)
-    }
-
-    /// Compute embeddings from accumulated mel frames
-    fn compute_embeddings(&mut self) -> Result<Vec<f32>, WakeWordError> {
-        let mel_data = self.mel_buffer.get_flattened();
-
-        // Input shape: [batch, frames, mel_bands] = [1, 76, 32]
-        let shape = [1_usize, self.config.mel_frame_count, self.mel_bands];
-        let input_tensor = Tensor::from_array((shape, mel_data))
-            .map_err(|e| WakeWordError::InferenceError(e.to_string()))?;
-
-        let outputs = self
-            .embedding_session
-            .run(ort::inputs![input_tensor])
-            .map_err(|e| WakeWordError::InferenceError(e.to_string()))?;
-
-        let output = &outputs[0];
-
-        let (_, data) = output
-            .try_extract_tensor::<f32>()
-            .map_err(|e| WakeWordError::InferenceError(e.to_string()))?;
-
-        Ok(data.to_vec())
-    }
-
-    /// Compute wake word detection score from embeddings
-    fn compute_wake_word_score(&mut self, embeddings: &[f32]) -> Result<f32, WakeWordError> {
-        // Input shape: [batch, embedding_size] = [1, N]
-        let shape = [1_usize, embeddings.len()];
-        let input_tensor = Tensor::from_array((shape, embeddings.to_vec()))
-            .map_err(|e| WakeWordError::InferenceError(e.to_string()))?;
-
-        let outputs = self
-            .wakeword_session
-            .run(ort::inputs![input_tensor])
-            .map_err(|e| WakeWordError::InferenceError(e.to_string()))?;
-
-        let output = &outputs[0];
-
-        let (_, data) = output
-            .try_extract_tensor::<f32>()
-            .map_err(|e| WakeWordError::InferenceError(e.to_string()))?;
-
-        // Score is typically a single value or we take the positive class probability
-        let score = data.first().copied().unwrap_or(0.0);
-
-        Ok(score)
+        if score < threshold {
+            return false;
+        }
+        if !self.config.reject_impulsive {
+            return true;
+        }
+        self.score_history.sustained_above(threshold, SUSTAINED_FRAMES)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::mock::mock_detector;
     use super::*;
     use std::path::PathBuf;
 
@@ -254,6 +239,54 @@ mod tests {
         assert!((config.effective_threshold() - 0.25).abs() < 0.001);
     }
 
+    #[test]
+    fn test_mock_detector_waits_for_full_window() {
+        let mut mock = mock_detector(VoiceConfig::default(), 0.0);
+        let chunk = vec![0.0; 1280];
+
+        // 76 frames at 5 per chunk need 16 chunks
+        for _ in 0..15 {
+            assert!(mock.detector.process_audio(&chunk).unwrap().is_none());
+        }
+        assert!(mock.detector.process_audio(&chunk).unwrap().is_some());
+        assert_eq!(mock.detector.stats().inference_count, 1);
+
+        mock.detector.reset();
+        assert!(mock.detector.process_audio(&chunk).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_mock_mel_frames_are_transformed() {
+        let mut mock = mock_detector(VoiceConfig::default(), 30.0);
+        let chunk = vec![0.0; 1280];
+        while mock.detector.process_audio(&chunk).unwrap().is_none() {}
+
+        let window = mock.embedding_input.lock().clone();
+        assert_eq!(window.len(), 76 * 32);
+        assert!(window.iter().all(|&v| (v - 5.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_mock_score_propagates_to_detection() {
+        let mut mock = mock_detector(VoiceConfig::default(), 0.0);
+        let chunk = vec![0.0; 1280];
+
+        *mock.score.lock() = 0.2;
+        let (name, score) = loop {
+            if let Some(best) = mock.detector.process_audio(&chunk).unwrap() {
+                break best;
+            }
+        };
+        assert_eq!(name, "hey_jarvis");
+        assert_eq!(score, 0.2);
+        assert!(!mock.detector.is_detected(&name, score));
+
+        *mock.score.lock() = 0.9;
+        let (name, score) = mock.detector.process_audio(&chunk).unwrap().unwrap();
+        assert_eq!(score, 0.9);
+        assert!(mock.detector.is_detected(&name, score));
+    }
+
     // Integration tests require models to be present
     #[test]
     #[ignore]
@@ -263,4 +296,51 @@ mod tests {
         let result = WakeWordDetector::new(&models_dir, config);
         assert!(result.is_ok());
     }
+
+    #[test]
+    #[ignore]
+    fn test_mel_bands_detected_from_shipped_model() {
+        let models_dir = PathBuf::from("resources/models");
+        let detector = WakeWordDetector::new(&models_dir, VoiceConfig::default()).unwrap();
+        assert_eq!(detector.mel_bands(), 32);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_cancel_stops_inference_promptly() {
+        let models_dir = PathBuf::from("resources/models");
+        let mut detector = WakeWordDetector::new(&models_dir, VoiceConfig::default()).unwrap();
+        let canceller = detector.canceller();
+
+        let worker = std::thread::spawn(move || {
+            let chunk = vec![0.0; 1280];
+            loop {
+                if let Err(WakeWordError::Cancelled) = detector.process_audio(&chunk) {
+                    return;
+                }
+            }
+        });
+
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        let cancelled_at = std::time::Instant::now();
+        canceller.cancel();
+        worker.join().unwrap();
+        assert!(cancelled_at.elapsed() < std::time::Duration::from_millis(500));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_stage_latency_populated() {
+        let models_dir = PathBuf::from("resources/models");
+        let mut detector = WakeWordDetector::new(&models_dir, VoiceConfig::default()).unwrap();
+        let chunk = vec![0.0; 1280];
+        while detector.stats().inference_count < 5 {
+            detector.process_audio(&chunk).unwrap();
+        }
+
+        let latency = detector.stats().latency;
+        assert!(latency.melspec_ms > 0.0);
+        assert!(latency.embedding_ms > 0.0);
+        assert!(latency.classifier_ms > 0.0);
+    }
 }
@@ -0,0 +1,77 @@
+//! Mock models for testing the detector pipeline without ONNX files
+
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+use super::{WakeWordDetector, WakeWordError};
+use crate::voice::classifiers::ClassifierSet;
+use crate::voice::config::VoiceConfig;
+use crate::voice::inference_cancel::InferenceCanceller;
+use crate::voice::inference_model::InferenceModel;
+use crate::voice::wake_word_models::primary_wake_word_name;
+
+const MOCK_MEL_FRAMES: usize = 5;
+
+/// Returns five 32-band frames filled with `value` for every chunk
+struct MockMelspec {
+    value: f32,
+}
+
+impl InferenceModel for MockMelspec {
+    fn run(&mut self, _: &[usize], _: Vec<f32>, _: &InferenceCanceller) -> Result<Vec<f32>, WakeWordError> {
+        Ok(vec![self.value; MOCK_MEL_FRAMES * 32])
+    }
+}
+
+/// Records the mel window it was given and returns a fixed embedding
+struct MockEmbedding {
+    last_input: Arc<Mutex<Vec<f32>>>,
+}
+
+impl InferenceModel for MockEmbedding {
+    fn run(&mut self, _: &[usize], input: Vec<f32>, _: &InferenceCanceller) -> Result<Vec<f32>, WakeWordError> {
+        *self.last_input.lock() = input;
+        Ok(vec![0.5; 96])
+    }
+}
+
+/// Returns whatever score the test last programmed
+struct MockScore {
+    score: Arc<Mutex<f32>>,
+}
+
+impl InferenceModel for MockScore {
+    fn run(&mut self, _: &[usize], _: Vec<f32>, _: &InferenceCanceller) -> Result<Vec<f32>, WakeWordError> {
+        Ok(vec![*self.score.lock()])
+    }
+}
+
+/// A detector wired to mock models, with handles to inspect and script them
+pub(in crate::voice) struct MockDetector {
+    pub(in crate::voice) detector: WakeWordDetector,
+    /// Mel window last passed to the embedding model
+    pub(in crate::voice) embedding_input: Arc<Mutex<Vec<f32>>>,
+    /// Score the classifier returns next
+    pub(in crate::voice) score: Arc<Mutex<f32>>,
+}
+
+/// Build a detector around mock models producing 32-band frames of `mel_value`
+pub(in crate::voice) fn mock_detector(config: VoiceConfig, mel_value: f32) -> MockDetector {
+    let embedding_input = Arc::new(Mutex::new(Vec::new()));
+    let score = Arc::new(Mutex::new(0.0));
+    let primary_name = primary_wake_word_name(&config.model_files);
+    let detector = WakeWordDetector::with_models(
+        config,
+        [
+            Box::new(MockMelspec { value: mel_value }),
+            Box::new(MockEmbedding { last_input: embedding_input.clone() }),
+            Box::new(MockScore { score: score.clone() }),
+        ],
+        32,
+        primary_name,
+        ClassifierSet::default(),
+        ClassifierSet::default(),
+    )
+    .unwrap();
+    MockDetector { detector, embedding_input, score }
+}
@@ -0,0 +1,210 @@
+//! Runtime tuning, statistics and read-outs of a wake word detector
+
+use super::WakeWordDetector;
+use crate::voice::inference_cancel::InferenceCanceller;
+use crate::voice::wake_word_stats::WakeWordStats;
+
+impl WakeWordDetector {
+    /// Average of the last `wake_word_smoothing_frames` scores
+    pub fn smoothed_score(&self) -> Option<f32> {
+        self.score_history.smoothed(self.config.wake_word_smoothing_frames)
+    }
+
+    /// Phrase that produced the last score, when using a multi-label classifier
+    pub fn last_phrase(&self) -> Option<&str> {
+        self.last_phrase.as_deref()
+    }
+
+    /// Take the command word detected by the last inference, if any
+    pub fn take_command(&mut self) -> Option<String> {
+        self.detected_command.take()
+    }
+
+    /// Set sensitivity (affects detection threshold)
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        let mut config = self.config.clone();
+        config.sensitivity = sensitivity.clamp(0.1, 3.0);
+        self.config = config;
+    }
+
+    /// Get current sensitivity
+    pub fn sensitivity(&self) -> f32 {
+        self.config.sensitivity
+    }
+
+    /// Set the base detection threshold before sensitivity scaling
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.config.wake_word_threshold = threshold.clamp(0.0, 1.0);
+    }
+
+    /// Get the base detection threshold
+    pub fn threshold(&self) -> f32 {
+        self.config.wake_word_threshold
+    }
+
+    /// Set how many new mel frames must accumulate between inference runs
+    pub fn set_inference_stride(&mut self, stride: usize) {
+        self.mel_buffer.set_stride(stride);
+        self.config.inference_stride = self.mel_buffer.stride();
+    }
+
+    /// Get the current inference stride in mel frames
+    pub fn inference_stride(&self) -> usize {
+        self.mel_buffer.stride()
+    }
+
+    /// Number of mel bands the melspectrogram model produces
+    pub fn mel_bands(&self) -> usize {
+        self.mel_bands
+    }
+
+    /// Get a handle that can abort this detector's inference from another thread
+    pub fn canceller(&self) -> InferenceCanceller {
+        self.canceller.clone()
+    }
+
+    /// Score statistics since the detector was created or [`Self::reset_stats`]
+    pub fn stats(&self) -> WakeWordStats {
+        WakeWordStats {
+            adapted_threshold: self.adapted_threshold(),
+            ..self.stats
+        }
+    }
+
+    /// Primary wake word threshold after adaptation, `None` when adaptation is off
+    pub fn adapted_threshold(&self) -> Option<f32> {
+        self.adaptive
+            .is_enabled()
+            .then(|| self.adaptive.apply(self.config.effective_threshold()))
+    }
+
+    /// Count a detection that was acted on
+    pub fn record_detection(&mut self) {
+        self.stats.record_detection();
+    }
+
+    /// Start collecting score statistics afresh
+    pub fn reset_stats(&mut self) {
+        self.stats = WakeWordStats::default();
+    }
+
+    /// Note whether audio is being fed for detection, clearing the buffers when scanning stops
+    ///
+    /// Frames buffered before wake word detection was disabled (or paused for
+    /// presence or the schedule) would otherwise be scored again on resume.
+    pub fn set_scanning(&mut self, scanning: bool) {
+        if self.scanning && !scanning {
+            self.reset();
+        }
+        self.scanning = scanning;
+    }
+
+    /// Number of mel frames currently buffered
+    pub fn buffered_frames(&self) -> usize {
+        self.mel_buffer.len()
+    }
+
+    /// Forget the recent scores used for smoothing and impulse rejection
+    pub fn clear_score_history(&mut self) {
+        self.score_history.clear();
+    }
+
+    /// Reset the internal buffers (statistics are kept)
+    pub fn reset(&mut self) {
+        self.mel_buffer.clear();
+        self.score_history.clear();
+        self.detected_command = None;
+        self.last_phrase = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::mock::mock_detector;
+    use crate::voice::config::VoiceConfig;
+
+    #[test]
+    fn test_disabling_scanning_clears_buffers() {
+        let mut mock = mock_detector(VoiceConfig::default(), 0.0);
+        let chunk = vec![0.0; 1280];
+        for _ in 0..15 {
+            mock.detector.process_audio(&chunk).unwrap();
+        }
+        assert_eq!(mock.detector.buffered_frames(), 75);
+
+        mock.detector.set_scanning(true);
+        assert_eq!(mock.detector.buffered_frames(), 75);
+        mock.detector.set_scanning(false);
+        assert_eq!(mock.detector.buffered_frames(), 0);
+
+        // After re-enabling, a full window of fresh audio is needed again
+        mock.detector.set_scanning(true);
+        for _ in 0..15 {
+            assert!(mock.detector.process_audio(&chunk).unwrap().is_none());
+        }
+        assert!(mock.detector.process_audio(&chunk).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_clearing_score_history_keeps_mel_buffer() {
+        let mut mock = mock_detector(VoiceConfig::default(), 0.0);
+        let chunk = vec![0.0; 1280];
+        while mock.detector.process_audio(&chunk).unwrap().is_none() {}
+        assert!(mock.detector.smoothed_score().is_some());
+
+        mock.detector.clear_score_history();
+        assert_eq!(mock.detector.smoothed_score(), None);
+        assert_eq!(mock.detector.buffered_frames(), 76);
+    }
+
+    #[test]
+    fn test_stats_follow_scored_inferences() {
+        let mut mock = mock_detector(VoiceConfig::default(), 0.0);
+        let chunk = vec![0.0; 1280];
+        while mock.detector.process_audio(&chunk).unwrap().is_none() {}
+
+        for score in [0.2, 0.9, 0.4] {
+            *mock.score.lock() = score;
+            mock.detector.process_audio(&chunk).unwrap();
+        }
+        mock.detector.record_detection();
+
+        let stats = mock.detector.stats();
+        assert_eq!(stats.inference_count, 4);
+        assert_eq!(stats.max_score, 0.9);
+        assert!((stats.mean_score - 0.375).abs() < 1e-6);
+        assert_eq!(stats.detections, 1);
+
+        mock.detector.reset_stats();
+        assert_eq!(mock.detector.stats().inference_count, 0);
+        assert_eq!(mock.detector.stats().max_score, 0.0);
+    }
+
+    #[test]
+    fn test_noisy_scores_raise_adapted_threshold() {
+        let config = VoiceConfig {
+            adaptive_threshold: true,
+            adaptive_threshold_margin: 0.3,
+            adaptive_threshold_ceiling: 0.75,
+            ..Default::default()
+        };
+        let base = config.effective_threshold();
+        let mut mock = mock_detector(config, 0.0);
+        let chunk = vec![0.0; 1280];
+        assert_eq!(mock.detector.stats().adapted_threshold, Some(base));
+
+        *mock.score.lock() = base - 0.05;
+        for _ in 0..300 {
+            mock.detector.process_audio(&chunk).unwrap();
+            let adapted = mock.detector.stats().adapted_threshold.unwrap();
+            assert!(adapted <= 0.75, "{}", adapted);
+        }
+        let adapted = mock.detector.stats().adapted_threshold.unwrap();
+        assert!(adapted > base, "{} <= {}", adapted, base);
+
+        // A score that would have fired in a quiet room no longer does
+        let score = base + 0.05;
+        assert!(score < adapted);
+        assert!(!mock.detector.is_detected("hey_jarvis", score));
+    }
+}
@@ -0,0 +1,90 @@
+//! Running each ONNX stage of the wake word pipeline
+
+use std::time::Instant;
+
+use super::{WakeWordDetector, WakeWordError};
+use crate::voice::wake_word_labels::argmax_label;
+
+impl WakeWordDetector {
+    /// Run one zero-filled inference through every session
+    ///
+    /// ort allocates on the first run, so priming here keeps that latency off
+    /// the first real chunk. A failure means the models don't fit together,
+    /// which is reported as a load error rather than at the first real audio.
+    pub(super) fn warm_up(&mut self) -> Result<(), WakeWordError> {
+        let started = Instant::now();
+        let load_error = |e| WakeWordError::ModelLoadError(format!("warmup inference failed: {}", e));
+
+        self.compute_mel_spectrogram(&vec![0.0; self.config.chunk_size]).map_err(load_error)?;
+        let mel_frames = vec![0.0; self.config.mel_frame_count * self.mel_bands];
+        let embeddings = self.run_embedding(mel_frames).map_err(load_error)?;
+        self.compute_wake_word_score(&embeddings).map_err(load_error)?;
+        if !self.wake_word_models.is_empty() {
+            self.wake_word_models.score(&embeddings, &self.canceller).map_err(load_error)?;
+        }
+        if !self.command_words.is_empty() {
+            self.command_words.score(&embeddings, &self.canceller).map_err(load_error)?;
+        }
+        self.last_phrase = None;
+
+        log::info!("Wake word models warmed up in {:?}", started.elapsed());
+        Ok(())
+    }
+
+    /// Compute mel spectrogram from audio samples, as `[frames][mel_bands]` values
+    pub(super) fn compute_mel_spectrogram(&mut self, samples: &[f32]) -> Result<Vec<f32>, WakeWordError> {
+        // Input shape: [batch, samples] = [1, N]
+        let shape = [1_usize, samples.len()];
+        self.melspec_model.run(&shape, samples.to_vec(), &self.canceller)
+    }
+
+    /// Compute embeddings from accumulated mel frames
+    pub(super) fn compute_embeddings(&mut self) -> Result<Vec<f32>, WakeWordError> {
+        let mel_data = self.mel_buffer.get_flattened();
+        self.run_embedding(mel_data)
+    }
+
+    /// Run the embedding model on `mel_frame_count` flattened mel frames
+    pub(super) fn run_embedding(&mut self, mel_data: Vec<f32>) -> Result<Vec<f32>, WakeWordError> {
+        // Input shape: [batch, frames, mel_bands] = [1, 76, 32]
+        let shape = [1_usize, self.config.mel_frame_count, self.mel_bands];
+        self.embedding_model.run(&shape, mel_data, &self.canceller)
+    }
+
+    /// Compute wake word detection score from embeddings
+    pub(super) fn compute_wake_word_score(&mut self, embeddings: &[f32]) -> Result<f32, WakeWordError> {
+        // Input shape: [batch, embedding_size] = [1, N]
+        let shape = [1_usize, embeddings.len()];
+        let data = self.wakeword_model.run(&shape, embeddings.to_vec(), &self.canceller)?;
+
+        // Score is typically a single value or we take the positive class probability
+        if self.config.wake_word_labels.is_empty() {
+            return Ok(data.first().copied().unwrap_or(0.0));
+        }
+
+        // Multi-label model: one output per phrase, report the best one
+        let best = argmax_label(&data, &self.config.wake_word_labels);
+        let score = best.as_ref().map_or(0.0, |(_, score)| *score);
+        self.last_phrase = best.map(|(label, _)| label);
+        Ok(score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice::config::VoiceConfig;
+    use std::path::PathBuf;
+
+    #[test]
+    #[ignore]
+    fn test_warmup_leaves_detector_fresh() {
+        let models_dir = PathBuf::from("resources/models");
+        let mut detector = WakeWordDetector::new(&models_dir, VoiceConfig::default()).unwrap();
+        detector.warm_up().unwrap();
+
+        // Priming runs must not count as real inferences
+        assert_eq!(detector.stats().inference_count, 0);
+        assert!(detector.last_phrase().is_none());
+    }
+}
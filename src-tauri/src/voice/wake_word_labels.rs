@@ -0,0 +1,45 @@
+//! Multi-label wake word classifiers
+//!
+//! Some classifiers score several wake phrases in a single model, one output
+//! per phrase. The configured label map names each output index; detection
+//! reports the best-scoring phrase.
+
+/// Pick the highest-scoring output and its label
+///
+/// Outputs beyond the label map are named by index (`output_3`).
+pub fn argmax_label(scores: &[f32], labels: &[String]) -> Option<(String, f32)> {
+    scores
+        .iter()
+        .copied()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(index, score)| {
+            let label = labels
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| format!("output_{}", index));
+            (label, score)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels() -> Vec<String> {
+        ["hey_jarvis", "ok_computer", "hey_friday"].map(String::from).to_vec()
+    }
+
+    #[test]
+    fn test_argmax_picks_best_phrase() {
+        let output = [0.1, 0.05, 0.92];
+        assert_eq!(argmax_label(&output, &labels()), Some(("hey_friday".to_string(), 0.92)));
+    }
+
+    #[test]
+    fn test_unlabeled_outputs_named_by_index() {
+        let output = [0.1, 0.2, 0.3, 0.8];
+        assert_eq!(argmax_label(&output, &labels()), Some(("output_3".to_string(), 0.8)));
+        assert_eq!(argmax_label(&[], &labels()), None);
+    }
+}
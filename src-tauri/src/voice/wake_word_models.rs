@@ -0,0 +1,103 @@
+//! Additional wake word classifiers running alongside the primary one
+//!
+//! Every model scores the same embeddings; the one furthest above its own
+//! threshold is reported by name in `voice-wake-word`.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::classifiers::{ClassifierSet, ExecutionProvider};
+use super::config::{ModelFiles, VoiceConfig};
+use super::wake_word::WakeWordError;
+
+/// An extra wake word and its classifier model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WakeWordModel {
+    /// Name reported when this wake word fires
+    pub name: String,
+    /// Classifier filename, relative to the models directory
+    pub model: String,
+    /// Base threshold before sensitivity scaling (`None` = `wake_word_threshold`)
+    pub threshold: Option<f32>,
+}
+
+/// Name reported for the primary classifier: its filename without extension
+pub fn primary_wake_word_name(model_files: &ModelFiles) -> String {
+    Path::new(&model_files.wakeword)
+        .file_stem()
+        .map_or_else(|| model_files.wakeword.clone(), |stem| stem.to_string_lossy().into_owned())
+}
+
+/// Load the classifier for each extra wake word
+pub fn load_wake_word_models(
+    models_dir: &Path,
+    models: &[WakeWordModel],
+    provider: ExecutionProvider,
+) -> Result<ClassifierSet, WakeWordError> {
+    ClassifierSet::load(models_dir, models.iter().map(|m| (m.name.as_str(), m.model.as_str())), provider)
+}
+
+/// Effective threshold for the named wake word
+pub fn threshold_for(config: &VoiceConfig, name: &str) -> f32 {
+    let base = config
+        .wake_word_models
+        .iter()
+        .find(|model| model.name == name)
+        .and_then(|model| model.threshold)
+        .unwrap_or(config.wake_word_threshold);
+    config.scaled_threshold(base)
+}
+
+/// Pick the `(name, score)` whose score is furthest above its threshold
+///
+/// When nothing crosses, the closest candidate is still returned so score
+/// history and debugging see a value every inference.
+pub fn pick_wake_word(config: &VoiceConfig, scores: Vec<(String, f32)>) -> Option<(String, f32)> {
+    scores.into_iter().max_by(|a, b| {
+        let margin_a = a.1 - threshold_for(config, &a.0);
+        let margin_b = b.1 - threshold_for(config, &b.0);
+        margin_a.total_cmp(&margin_b)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with(models: &[(&str, Option<f32>)]) -> VoiceConfig {
+        VoiceConfig {
+            wake_word_models: models
+                .iter()
+                .map(|(name, threshold)| WakeWordModel {
+                    name: name.to_string(),
+                    model: format!("{}.onnx", name),
+                    threshold: *threshold,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_higher_scoring_wake_word_is_reported() {
+        let config = config_with(&[("alexa", None), ("computer", None)]);
+        let scores = vec![
+            ("hey_jarvis".to_string(), 0.2),
+            ("alexa".to_string(), 0.6),
+            ("computer".to_string(), 0.9),
+        ];
+        assert_eq!(pick_wake_word(&config, scores), Some(("computer".to_string(), 0.9)));
+    }
+
+    #[test]
+    fn test_per_model_thresholds() {
+        let config = config_with(&[("alexa", Some(0.3)), ("computer", Some(0.95))]);
+        assert_eq!(threshold_for(&config, "computer"), 0.95);
+        assert_eq!(threshold_for(&config, "hey_jarvis"), 0.5);
+
+        // 0.8 clears alexa's threshold by more than 0.9 clears computer's
+        let scores = vec![("alexa".to_string(), 0.8), ("computer".to_string(), 0.9)];
+        assert_eq!(pick_wake_word(&config, scores), Some(("alexa".to_string(), 0.8)));
+        assert_eq!(primary_wake_word_name(&config.model_files), "hey_jarvis");
+    }
+}
@@ -0,0 +1,118 @@
+//! Running wake word score statistics for threshold tuning
+//!
+//! Plain counters updated once per inference, so tracking costs nothing
+//! measurable next to the models themselves. Per-stage latencies are moving
+//! averages of `Instant` timings taken around each model run.
+
+use serde::Serialize;
+use std::time::Duration;
+
+/// Weight of the newest timing in the latency moving averages
+const LATENCY_SMOOTHING: f32 = 0.1;
+
+/// Moving-average latency of each inference stage, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct StageLatency {
+    /// Melspectrogram model, run on every chunk
+    pub melspec_ms: f32,
+    /// Embedding model, run when an inference is due
+    pub embedding_ms: f32,
+    /// Wake word classifiers, primary and additional
+    pub classifier_ms: f32,
+}
+
+impl std::fmt::Display for StageLatency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "melspec {:.1}ms, embedding {:.1}ms, classifier {:.1}ms",
+            self.melspec_ms, self.embedding_ms, self.classifier_ms
+        )
+    }
+}
+
+/// Fold `elapsed` into a moving average, starting from the first timing
+fn smooth(average_ms: &mut f32, elapsed: Duration) {
+    let ms = elapsed.as_secs_f32() * 1000.0;
+    if *average_ms == 0.0 {
+        *average_ms = ms;
+    } else {
+        *average_ms += LATENCY_SMOOTHING * (ms - *average_ms);
+    }
+}
+
+/// Aggregate wake word scores since the last reset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct WakeWordStats {
+    /// Highest score seen
+    pub max_score: f32,
+    /// Mean of all scores
+    pub mean_score: f32,
+    /// Inference runs that produced a score
+    pub inference_count: u64,
+    /// Scores that crossed the threshold
+    pub detections: u64,
+    /// Time spent in each model
+    pub latency: StageLatency,
+    /// Primary threshold after adapting to background scores, `None` when adaptation is off
+    pub adapted_threshold: Option<f32>,
+}
+
+impl WakeWordStats {
+    /// Fold in the score of one inference run
+    pub fn record(&mut self, score: f32) {
+        self.inference_count += 1;
+        self.max_score = self.max_score.max(score);
+        self.mean_score += (score - self.mean_score) / self.inference_count as f32;
+    }
+
+    /// Count a detection
+    pub fn record_detection(&mut self) {
+        self.detections += 1;
+    }
+
+    /// Time taken by the melspectrogram model for one chunk
+    pub fn record_melspec(&mut self, elapsed: Duration) {
+        smooth(&mut self.latency.melspec_ms, elapsed);
+    }
+
+    /// Time taken by the embedding model and the classifiers for one inference
+    pub fn record_inference_latency(&mut self, embedding: Duration, classifier: Duration) {
+        smooth(&mut self.latency.embedding_ms, embedding);
+        smooth(&mut self.latency.classifier_ms, classifier);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_aggregate() {
+        let mut stats = WakeWordStats::default();
+        for score in [0.1, 0.3, 0.8, 0.2] {
+            stats.record(score);
+        }
+        stats.record_detection();
+
+        assert_eq!(stats.inference_count, 4);
+        assert_eq!(stats.max_score, 0.8);
+        assert!((stats.mean_score - 0.35).abs() < 1e-6);
+        assert_eq!(stats.detections, 1);
+    }
+
+    #[test]
+    fn test_latency_moving_average() {
+        let mut stats = WakeWordStats::default();
+        stats.record_melspec(Duration::from_millis(2));
+        assert_eq!(stats.latency.melspec_ms, 2.0);
+
+        for _ in 0..100 {
+            stats.record_melspec(Duration::from_millis(4));
+            stats.record_inference_latency(Duration::from_micros(800), Duration::from_micros(300));
+        }
+        assert!((stats.latency.melspec_ms - 4.0).abs() < 0.01);
+        assert!((stats.latency.embedding_ms - 0.8).abs() < 0.01);
+        assert!((stats.latency.classifier_ms - 0.3).abs() < 0.01);
+    }
+}
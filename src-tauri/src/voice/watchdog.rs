@@ -0,0 +1,342 @@
+//! Self-healing supervisor for unattended deployments
+//!
+//! The processing loop publishes health signals (a per-chunk heartbeat, a
+//! liveness flag, consecutive inference failures and dropped audio chunks).
+//! The stall clock starts once the loop has loaded its models and begins
+//! waiting for audio, so a slow model load never counts as a stall. When
+//! `auto_restart` is enabled, a supervisor thread polls them and performs a
+//! full stop/start cycle on failure, backing off exponentially up to a
+//! maximum number of attempts.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::config::VoiceConfig;
+use super::controller::VoiceController;
+
+/// How often the supervisor checks the health signals
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest wait between restart attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Healthy running time after which the restart budget is refilled
+const HEALTHY_RESET: Duration = Duration::from_secs(300);
+
+/// Why the supervisor considers the voice system unhealthy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthFailure {
+    /// The processing thread exited or panicked while the system was running
+    ProcessingStopped,
+    /// No audio chunk was processed within the stall timeout
+    AudioStalled,
+    /// Wake word inference failed repeatedly in a row
+    InferenceDegraded,
+    /// The processing loop fell behind and dropped too many audio chunks
+    AudioOverrun,
+}
+
+/// `last_beat_ms` value before the processing loop has started
+const NOT_STARTED: u64 = u64::MAX;
+
+/// Health signals published by one run of the processing loop
+#[derive(Debug)]
+pub struct HealthSignals {
+    started: Instant,
+    /// Time of the last processed chunk, in ms since `started`
+    last_beat_ms: AtomicU64,
+    processing_alive: AtomicBool,
+    inference_failures: AtomicU32,
+    dropped_chunks: AtomicU64,
+}
+
+impl HealthSignals {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            last_beat_ms: AtomicU64::new(NOT_STARTED),
+            processing_alive: AtomicBool::new(true),
+            inference_failures: AtomicU32::new(0),
+            dropped_chunks: AtomicU64::new(0),
+        }
+    }
+
+    /// Note that a chunk was processed (or the loop started waiting) at `now`
+    pub fn beat(&self, now: Instant) {
+        let ms = now.saturating_duration_since(self.started).as_millis() as u64;
+        self.last_beat_ms.store(ms, Ordering::Relaxed);
+    }
+
+    /// Record the outcome of a wake word inference run
+    pub fn record_inference(&self, ok: bool) {
+        if ok {
+            self.inference_failures.store(0, Ordering::Relaxed);
+        } else {
+            self.inference_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Record the total number of audio chunks dropped by the queue so far
+    pub fn record_dropped(&self, total: u64) {
+        self.dropped_chunks.store(total, Ordering::Relaxed);
+    }
+
+    /// Guard that marks the processing thread dead when dropped, including on panic
+    pub fn processing_guard(self: &Arc<Self>) -> ProcessingGuard {
+        ProcessingGuard(self.clone())
+    }
+
+    /// First failing signal as of `now`, if any
+    pub fn check(&self, now: Instant, config: &VoiceConfig) -> Option<HealthFailure> {
+        if !self.processing_alive.load(Ordering::Relaxed) {
+            return Some(HealthFailure::ProcessingStopped);
+        }
+        let last_beat_ms = self.last_beat_ms.load(Ordering::Relaxed);
+        if last_beat_ms != NOT_STARTED {
+            let last_beat = self.started + Duration::from_millis(last_beat_ms);
+            if now.saturating_duration_since(last_beat) >= Duration::from_millis(config.watchdog_stall_ms) {
+                return Some(HealthFailure::AudioStalled);
+            }
+        }
+        if self.inference_failures.load(Ordering::Relaxed) >= config.watchdog_max_inference_failures {
+            return Some(HealthFailure::InferenceDegraded);
+        }
+        let max_dropped = config.watchdog_max_dropped_chunks;
+        if max_dropped > 0 && self.dropped_chunks.load(Ordering::Relaxed) >= max_dropped {
+            return Some(HealthFailure::AudioOverrun);
+        }
+        None
+    }
+}
+
+impl Default for HealthSignals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Clears the liveness flag when the processing thread ends
+pub struct ProcessingGuard(Arc<HealthSignals>);
+
+impl Drop for ProcessingGuard {
+    fn drop(&mut self) {
+        self.0.processing_alive.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Delay before restart attempt `attempt` (1-based), doubling from `base_ms`
+pub fn restart_backoff(base_ms: u64, attempt: u32) -> Duration {
+    let factor = 1_u64 << attempt.saturating_sub(1).min(16);
+    Duration::from_millis(base_ms.saturating_mul(factor)).min(MAX_BACKOFF)
+}
+
+/// Supervise the controller in `shared` until it is dropped or gives up
+///
+/// Does nothing while `auto_restart` is disabled in the controller's config.
+pub fn spawn_watchdog(shared: Arc<Mutex<Option<VoiceController>>>, stop: Arc<AtomicBool>) {
+    supervise(shared, stop, POLL_INTERVAL);
+}
+
+fn supervise(
+    shared: Arc<Mutex<Option<VoiceController>>>,
+    stop: Arc<AtomicBool>,
+    poll_interval: Duration,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut attempts = 0;
+        let mut healthy_since = Instant::now();
+        // Failure carried over from a restart whose start() failed
+        let mut pending: Option<HealthFailure> = None;
+
+        while !stop.load(Ordering::SeqCst) {
+            thread::sleep(poll_interval);
+            let guard = shared.lock();
+            let Some(ref controller) = *guard else {
+                return;
+            };
+            let config = controller.config();
+            if stop.load(Ordering::SeqCst) || !config.auto_restart {
+                continue;
+            }
+            if pending.is_none() && !controller.is_running() {
+                continue;
+            }
+
+            let Some(failure) = pending.take().or_else(|| controller.health_failure(Instant::now())) else {
+                if attempts > 0 && healthy_since.elapsed() >= HEALTHY_RESET {
+                    attempts = 0;
+                }
+                continue;
+            };
+
+            if attempts >= config.auto_restart_max_attempts {
+                log::error!("Voice system still unhealthy ({:?}) after {} restarts", failure, attempts);
+                controller.notify("voice-auto-restart-failed", serde_json::json!({
+                    "reason": failure,
+                    "attempts": attempts,
+                }));
+                return;
+            }
+            attempts += 1;
+            let delay = restart_backoff(config.auto_restart_backoff_ms, attempts);
+            log::warn!("Voice system unhealthy ({:?}), restart {} in {:?}", failure, attempts, delay);
+            controller.notify("voice-auto-restarting", serde_json::json!({
+                "reason": failure,
+                "attempt": attempts,
+                "delay_ms": delay.as_millis() as u64,
+            }));
+            drop(guard);
+
+            thread::sleep(delay);
+            let mut guard = shared.lock();
+            let Some(ref mut controller) = *guard else {
+                return;
+            };
+            if stop.load(Ordering::SeqCst) {
+                return;
+            }
+            controller.stop();
+            if let Err(e) = controller.start() {
+                log::error!("Voice auto-restart failed: {}", e);
+                pending = Some(failure);
+            }
+            healthy_since = Instant::now();
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_failures() {
+        let config = VoiceConfig {
+            watchdog_stall_ms: 2000,
+            watchdog_max_inference_failures: 3,
+            ..Default::default()
+        };
+        let signals = Arc::new(HealthSignals::new());
+        let start = signals.started;
+
+        // Loading models can take longer than the stall timeout
+        assert_eq!(signals.check(start + Duration::from_millis(10_000), &config), None);
+
+        signals.beat(start + Duration::from_millis(500));
+        assert_eq!(signals.check(start + Duration::from_millis(1000), &config), None);
+        assert_eq!(
+            signals.check(start + Duration::from_millis(2500), &config),
+            Some(HealthFailure::AudioStalled)
+        );
+
+        signals.beat(start + Duration::from_millis(2500));
+        for _ in 0..3 {
+            signals.record_inference(false);
+        }
+        assert_eq!(
+            signals.check(start + Duration::from_millis(2600), &config),
+            Some(HealthFailure::InferenceDegraded)
+        );
+        signals.record_inference(true);
+        assert_eq!(signals.check(start + Duration::from_millis(2600), &config), None);
+
+        signals.record_dropped(config.watchdog_max_dropped_chunks);
+        assert_eq!(
+            signals.check(start + Duration::from_millis(2600), &config),
+            Some(HealthFailure::AudioOverrun)
+        );
+
+        drop(signals.processing_guard());
+        assert_eq!(
+            signals.check(start + Duration::from_millis(2600), &config),
+            Some(HealthFailure::ProcessingStopped)
+        );
+    }
+
+    #[test]
+    fn test_restart_backoff_doubles_up_to_cap() {
+        assert_eq!(restart_backoff(1000, 1), Duration::from_secs(1));
+        assert_eq!(restart_backoff(1000, 3), Duration::from_secs(4));
+        assert_eq!(restart_backoff(1000, 30), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_supervisor_restarts_dead_processing_loop() {
+        use crate::voice::audio_capture::AudioCaptureError;
+        use crate::voice::controller_state::VoiceControllerState;
+        use crate::voice::audio_source::AudioSource;
+        use crate::voice::chunk::AudioSender;
+        use crate::voice::events::{CollectingSink, EventSink};
+        use parking_lot::RwLock;
+
+        /// Silent source that kills the processing loop's liveness on its first start
+        #[derive(Default)]
+        struct FailOnce {
+            starts: AtomicU32,
+        }
+
+        impl AudioSource for FailOnce {
+            fn start(
+                &self,
+                _sink: EventSink,
+                state: Arc<RwLock<VoiceControllerState>>,
+                _config: VoiceConfig,
+                _input_device: Option<String>,
+                _audio_tx: AudioSender,
+                _stop: Arc<AtomicBool>,
+            ) -> Result<(), AudioCaptureError> {
+                if self.starts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    drop(state.read().health.processing_guard());
+                }
+                Ok(())
+            }
+        }
+
+        let dir = std::env::temp_dir().join(format!("jarvis-watchdog-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = Arc::new(FailOnce::default());
+        let sink = Arc::new(CollectingSink::default());
+        let mut controller = VoiceController::new(dir.clone());
+        controller.set_event_sink(sink.clone());
+        controller.set_wake_word_enabled(false);
+        controller.set_audio_source(source.clone());
+        controller
+            .set_config(VoiceConfig {
+                auto_restart: true,
+                auto_restart_max_attempts: 1,
+                auto_restart_backoff_ms: 0,
+                ..Default::default()
+            })
+            .unwrap();
+        controller.start().unwrap();
+
+        let shared = Arc::new(Mutex::new(Some(controller)));
+        let stop = Arc::new(AtomicBool::new(false));
+        let supervisor = supervise(shared.clone(), stop.clone(), Duration::from_millis(10));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while source.starts.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        stop.store(true, Ordering::SeqCst);
+        supervisor.join().unwrap();
+
+        let restarting = sink.payloads("voice-auto-restarting");
+        assert_eq!(restarting.len(), 1);
+        assert_eq!(restarting[0]["reason"], "processingStopped");
+        assert_eq!(source.starts.load(Ordering::SeqCst), 2);
+        let mut guard = shared.lock();
+        let controller = guard.as_mut().unwrap();
+        assert!(controller.is_running());
+        // The fresh loop has not stalled: its clock starts when it is ready for audio
+        assert_eq!(controller.health_failure(Instant::now()), None);
+        controller.stop();
+        drop(guard);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,54 @@
+//! WAV export of captured audio for debugging transcription quality
+
+use chrono::Local;
+use std::path::{Path, PathBuf};
+
+/// Write mono samples as 16-bit PCM, clamping anything outside [-1.0, 1.0]
+pub fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<(), hound::Error> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for &sample in samples {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16)?;
+    }
+    writer.finalize()
+}
+
+/// Timestamped path for a captured utterance inside `dir`
+pub fn utterance_path(dir: &Path) -> PathBuf {
+    dir.join(format!("utterance-{}.wav", Local::now().format("%Y%m%d-%H%M%S%.3f")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_within_quantization_error() {
+        let path = std::env::temp_dir().join(format!("jarvis-wav-{}.wav", std::process::id()));
+        let samples: Vec<f32> = (0..1600).map(|i| (i as f32 * 0.01).sin() * 0.8).collect();
+        let mut with_clipping = samples.clone();
+        with_clipping.extend([1.5, -2.0]);
+
+        write_wav(&path, &with_clipping, 16000).unwrap();
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        let spec = reader.spec();
+        assert_eq!((spec.channels, spec.sample_rate, spec.bits_per_sample), (1, 16000, 16));
+
+        let read: Vec<f32> = reader
+            .samples::<i16>()
+            .map(|s| s.unwrap() as f32 / i16::MAX as f32)
+            .collect();
+        assert_eq!(read.len(), with_clipping.len());
+        for (out, expected) in read.iter().zip(&samples) {
+            assert!((out - expected).abs() <= 1.0 / i16::MAX as f32);
+        }
+        assert_eq!(read[samples.len()..], [1.0, -1.0]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
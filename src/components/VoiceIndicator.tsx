@@ -18,6 +18,7 @@ const stateColors: Record<VoiceState, string> = {
   Transcribing: '#eab308', // yellow
   Processing: '#3b82f6', // blue
   Speaking: '#a855f7',   // purple
+  Error: '#ef4444',      // red
 };
 
 const stateLabels: Record<VoiceState, string> = {
@@ -26,6 +27,7 @@ const stateLabels: Record<VoiceState, string> = {
   Transcribing: 'Transcribing...',
   Processing: 'Thinking...',
   Speaking: 'Speaking...',
+  Error: 'Error',
 };
 
 export const VoiceIndicator: React.FC<VoiceIndicatorProps> = ({
@@ -8,6 +8,20 @@ import { invoke } from '@tauri-apps/api/core';
 export interface AudioDeviceInfo {
   name: string;
   is_default: boolean;
+  /** Rate the device opens at by default (0 = unknown) */
+  default_sample_rate: number;
+  /** Default channel count (0 = unknown) */
+  channels: number;
+  /** Common sample rates the device supports */
+  supported_sample_rates: number[];
+}
+
+/** Devices and current selection, read together by `get_audio_config` */
+interface AudioConfigSnapshot {
+  inputs: AudioDeviceInfo[];
+  outputs: AudioDeviceInfo[];
+  current_input: string | null;
+  current_output: string | null;
 }
 
 export interface UseAudioDevicesResult {
@@ -47,13 +61,12 @@ export function useAudioDevices(): UseAudioDevicesResult {
       setIsLoading(true);
       setError(null);
 
-      const [inputs, outputs] = await Promise.all([
-        invoke<AudioDeviceInfo[]>('get_input_devices'),
-        invoke<AudioDeviceInfo[]>('get_output_devices'),
-      ]);
+      const snapshot = await invoke<AudioConfigSnapshot>('get_audio_config');
 
-      setInputDevices(inputs);
-      setOutputDevices(outputs);
+      setInputDevices(snapshot.inputs);
+      setOutputDevices(snapshot.outputs);
+      setSelectedInputDevice(snapshot.current_input);
+      setSelectedOutputDevice(snapshot.current_output);
     } catch (e) {
       const message = e instanceof Error ? e.message : String(e);
       setError(message);
@@ -59,8 +59,8 @@ export function useDebugLog(): UseDebugLogResult {
 
     const setupListeners = async () => {
       // Voice state changes
-      const unlistenState = await listen<string>('voice-state-changed', (event) => {
-        addLog('info', 'Voice', `State changed to: ${event.payload}`);
+      const unlistenState = await listen<{ state: string }>('voice-state-changed', (event) => {
+        addLog('info', 'Voice', `State changed to: ${event.payload.state}`);
       });
       unlisteners.push(unlistenState);
 
@@ -72,12 +72,13 @@ export function useDebugLog(): UseDebugLogResult {
 
       // Audio level (throttled)
       let lastAudioLog = 0;
-      const unlistenAudioLevel = await listen<number>('voice-audio-level', (event) => {
+      const unlistenAudioLevel = await listen<{ rms: number; peak: number; dbfs: number }>('voice-audio-level', (event) => {
         const now = Date.now();
+        const { rms, dbfs } = event.payload;
         // Only log every 500ms to avoid spam
-        if (now - lastAudioLog > 500 && event.payload > 0.01) {
+        if (now - lastAudioLog > 500 && rms > 0.01) {
           lastAudioLog = now;
-          addLog('debug', 'Audio', `Level: ${event.payload.toFixed(4)}`);
+          addLog('debug', 'Audio', `Level: ${rms.toFixed(4)} (${dbfs.toFixed(1)} dBFS)`);
         }
       });
       unlisteners.push(unlistenAudioLevel);
@@ -89,11 +90,32 @@ export function useDebugLog(): UseDebugLogResult {
       unlisteners.push(unlistenError);
 
       // Audio captured
-      const unlistenCaptured = await listen<number[]>('voice-audio-captured', (event) => {
-        addLog('info', 'Voice', `Audio captured: ${event.payload.length} samples`);
-      });
+      const unlistenCaptured = await listen<{ samples: number[]; duration_ms: number; all_silent: boolean }>(
+        'voice-audio-captured',
+        (event) => {
+          const { samples, duration_ms, all_silent } = event.payload;
+          const note = all_silent ? ' (no speech detected)' : '';
+          addLog('info', 'Voice', `Audio captured: ${samples.length} samples, ${Math.round(duration_ms)}ms${note}`);
+        },
+      );
       unlisteners.push(unlistenCaptured);
 
+      // Transcription from the streaming STT service
+      const unlistenTranscription = await listen<{ text: string }>('voice-transcription', (event) => {
+        addLog('info', 'STT', `Transcribed: "${event.payload.text}"`);
+      });
+      unlisteners.push(unlistenTranscription);
+
+      // Processing fell behind capture and audio was dropped
+      const unlistenOverrun = await listen<{ dropped: number; total_dropped: number }>(
+        'voice-audio-overrun',
+        (event) => {
+          const { dropped, total_dropped } = event.payload;
+          addLog('warn', 'Voice', `Audio overrun: dropped ${dropped} chunks (${total_dropped} this session)`);
+        },
+      );
+      unlisteners.push(unlistenOverrun);
+
       // Debug log from Rust
       const unlistenDebug = await listen<{ level: string; message: string }>('debug-log', (event) => {
         const level = event.payload.level as LogEntry['level'];
@@ -6,12 +6,24 @@ import { useState, useEffect, useCallback } from 'react';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/core';
 
-export type VoiceState = 'Idle' | 'Listening' | 'Transcribing' | 'Processing' | 'Speaking';
+export type VoiceState = 'Idle' | 'Listening' | 'Transcribing' | 'Processing' | 'Speaking' | 'Error';
+
+interface StateChange {
+  state: VoiceState;
+  /** When the state was entered, in ms since the UNIX epoch */
+  entered_at_ms: number;
+}
 
 interface WakeWordEvent {
   score: number;
 }
 
+interface VadStatus {
+  rms: number;
+  is_speech: boolean;
+  silent_frames: number;
+}
+
 export interface UseVoiceStateResult {
   /** Current voice state */
   state: VoiceState;
@@ -19,6 +31,10 @@ export interface UseVoiceStateResult {
   isRunning: boolean;
   /** Current audio level (RMS) */
   audioLevel: number;
+  /** When the current state was entered, in ms since the UNIX epoch */
+  stateEnteredAt: number | null;
+  /** Whether speech is currently being heard while listening */
+  speechDetected: boolean;
   /** Last wake word detection score */
   lastWakeWordScore: number | null;
   /** Start the voice system */
@@ -37,6 +53,8 @@ export function useVoiceState(): UseVoiceStateResult {
   const [state, setState] = useState<VoiceState>('Idle');
   const [isRunning, setIsRunning] = useState(false);
   const [audioLevel, setAudioLevel] = useState(0);
+  const [speechDetected, setSpeechDetected] = useState(false);
+  const [stateEnteredAt, setStateEnteredAt] = useState<number | null>(null);
   const [lastWakeWordScore, setLastWakeWordScore] = useState<number | null>(null);
   const [error, setError] = useState<string | null>(null);
 
@@ -46,8 +64,12 @@ export function useVoiceState(): UseVoiceStateResult {
 
     const setupListeners = async () => {
       // Voice state changes
-      const unlistenState = await listen<VoiceState>('voice-state-changed', (event) => {
-        setState(event.payload);
+      const unlistenState = await listen<StateChange>('voice-state-changed', (event) => {
+        setState(event.payload.state);
+        setStateEnteredAt(event.payload.entered_at_ms);
+        if (event.payload.state !== 'Listening') {
+          setSpeechDetected(false);
+        }
       });
       unlisteners.push(unlistenState);
 
@@ -58,16 +80,29 @@ export function useVoiceState(): UseVoiceStateResult {
       unlisteners.push(unlistenWakeWord);
 
       // Audio level updates
-      const unlistenAudioLevel = await listen<number>('voice-audio-level', (event) => {
-        setAudioLevel(event.payload);
+      const unlistenAudioLevel = await listen<{ rms: number; peak: number; dbfs: number }>('voice-audio-level', (event) => {
+        setAudioLevel(event.payload.rms);
       });
       unlisteners.push(unlistenAudioLevel);
 
+      // VAD status while listening
+      const unlistenVad = await listen<VadStatus>('voice-vad', (event) => {
+        setSpeechDetected(event.payload.is_speech);
+      });
+      unlisteners.push(unlistenVad);
+
       // Error events
       const unlistenError = await listen<string>('voice-error', (event) => {
         setError(event.payload);
       });
       unlisteners.push(unlistenError);
+
+      // Start failed because the machine has no microphone
+      const unlistenNoInput = await listen('voice-no-input-device', () => {
+        setIsRunning(false);
+        setError('No microphone found. Connect one and try again.');
+      });
+      unlisteners.push(unlistenNoInput);
     };
 
     setupListeners();
@@ -137,6 +172,8 @@ export function useVoiceState(): UseVoiceStateResult {
     state,
     isRunning,
     audioLevel,
+    stateEnteredAt,
+    speechDetected,
     lastWakeWordScore,
     start,
     stop,